@@ -44,6 +44,14 @@ mod tests {
         assert!(path_str.ends_with("state/state.json"));
         assert!(path_str.starts_with("/tmp/custom2"));
     }
+
+    #[test]
+    fn test_replica_id_override() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        env::set_var("PLANTER_REPLICA_ID", "replica-a");
+        assert_eq!(replica_id(), "replica-a");
+        env::remove_var("PLANTER_REPLICA_ID");
+    }
 }
 
 pub fn state_file_path() -> PathBuf {
@@ -85,4 +93,30 @@ impl Config {
             log_level: env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()),
         }
     }
-}
\ No newline at end of file
+}
+/// Where schedules are kept when Redis is not configured
+pub fn schedules_file_path() -> PathBuf {
+    let mut root = planter_root();
+    root.push("state");
+    std::fs::create_dir_all(&root).ok();
+    root.push("schedules.json");
+    root
+}
+
+/// Where `file` secrets are read from
+pub fn secrets_dir() -> PathBuf {
+    let mut root = planter_root();
+    root.push("secrets");
+    root
+}
+
+/// Name of this replica in the leases of the runs it checkpoints:
+/// `PLANTER_REPLICA_ID`, else the host name, which a restarted container
+/// usually keeps, else a fresh id
+pub fn replica_id() -> String {
+    ["PLANTER_REPLICA_ID", "HOSTNAME"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|id| !id.is_empty())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
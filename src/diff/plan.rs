@@ -1,6 +1,8 @@
+use crate::executor::matrix;
 use crate::model::Phase;
 use std::collections::HashMap;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum DiffResult {
     Add(Phase),
@@ -11,11 +13,15 @@ pub enum DiffResult {
 /// Compute the difference between two sets of phases.
 /// `current` is the last applied state.
 /// `incoming` is the new plan being submitted.
+/// Phases with a `matrix` are compared instance by instance, so adding a
+/// value shows up as one added phase rather than an update.
 pub fn diff_plans(
     current: &[Phase],
     incoming: &[Phase],
 ) -> Vec<DiffResult> {
     let mut results = vec![];
+    let current = matrix::expand(current).unwrap_or_else(|_| current.to_vec());
+    let incoming = matrix::expand(incoming).unwrap_or_else(|_| incoming.to_vec());
 
     let current_map: HashMap<_, _> = current.iter()
         .map(|p| ((p.kind.clone(), p.id.clone()), p))
@@ -53,4 +59,5 @@ pub fn diff_plans(
 }
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests;
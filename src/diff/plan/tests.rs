@@ -1,6 +1,5 @@
 #[cfg(test)]
 mod tests {
-    use super::*;
     use crate::model::*;
     use crate::diff::{diff_plans, DiffResult};
     use std::collections::HashMap;
@@ -18,11 +17,7 @@ mod tests {
                         labels
                     },
                 },
-                instance_mode: None,
-                wait_for: None,
-                retry: None,
-                on_failure: None,
-                on_success: None,
+                ..Default::default()
             },
         }
     }
@@ -157,14 +152,7 @@ mod tests {
             id: "same-id".to_string(),
             spec: PhaseSpec {
                 description: "Original".to_string(),
-                selector: Selector {
-                    match_labels: HashMap::new(),
-                },
-                instance_mode: None,
-                wait_for: None,
-                retry: None,
-                on_failure: None,
-                on_success: None,
+                ..Default::default()
             },
         }];
         
@@ -173,14 +161,7 @@ mod tests {
             id: "same-id".to_string(),
             spec: PhaseSpec {
                 description: "New".to_string(),
-                selector: Selector {
-                    match_labels: HashMap::new(),
-                },
-                instance_mode: None,
-                wait_for: None,
-                retry: None,
-                on_failure: None,
-                on_success: None,
+                ..Default::default()
             },
         }];
         
@@ -209,4 +190,30 @@ mod tests {
         assert!(has_delete);
         assert!(has_add);
     }
+
+    #[test]
+    fn test_diff_matrix_instances() {
+        let matrix = |values: &[&str]| {
+            let mut phase = create_test_phase("deploy", "Deploy to ${matrix.region}");
+            phase.spec.matrix = Some(std::collections::BTreeMap::from([(
+                "region".to_string(),
+                values.iter().map(|v| v.to_string()).collect(),
+            )]));
+            phase
+        };
+        let current = vec![matrix(&["eu-west", "us-east"])];
+        let incoming = vec![matrix(&["eu-west", "ap-south"])];
+
+        let mut changes: Vec<String> = diff_plans(&current, &incoming)
+            .iter()
+            .map(|result| match result {
+                DiffResult::Add(p) => format!("add {}", p.id),
+                DiffResult::Update { new, .. } => format!("update {}", new.id),
+                DiffResult::Delete(p) => format!("delete {}", p.id),
+            })
+            .collect();
+        changes.sort();
+        assert_eq!(changes, vec!["add deploy[ap-south]", "delete deploy[us-east]"]);
+        assert!(diff_plans(&current, &crate::executor::matrix::expand(&current).unwrap()).is_empty());
+    }
 }
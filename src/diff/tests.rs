@@ -17,11 +17,7 @@ mod tests {
                         labels
                     },
                 },
-                instance_mode: None,
-                wait_for: None,
-                retry: None,
-                on_failure: None,
-                on_success: None,
+                ..Default::default()
             },
         }
     }
@@ -157,14 +153,7 @@ mod tests {
             id: "same-id".to_string(),
             spec: PhaseSpec {
                 description: "Original".to_string(),
-                selector: Selector {
-                    match_labels: HashMap::new(),
-                },
-                instance_mode: None,
-                wait_for: None,
-                retry: None,
-                on_failure: None,
-                on_success: None,
+                ..Default::default()
             },
         }];
         
@@ -173,14 +162,7 @@ mod tests {
             id: "same-id".to_string(),
             spec: PhaseSpec {
                 description: "New".to_string(),
-                selector: Selector {
-                    match_labels: HashMap::new(),
-                },
-                instance_mode: None,
-                wait_for: None,
-                retry: None,
-                on_failure: None,
-                on_success: None,
+                ..Default::default()
             },
         }];
         
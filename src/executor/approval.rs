@@ -0,0 +1,74 @@
+use crate::executor::attempt::{max_output_bytes, AttemptRecord};
+use crate::executor::control::ApprovalDecision;
+use crate::executor::driver::approval::APPROVAL_DRIVER;
+use crate::executor::driver::{self, PhaseOutcome};
+use crate::executor::hooks;
+use crate::executor::outputs::PhaseOutputs;
+use crate::executor::runner;
+use crate::executor::ExecutionOptions;
+use crate::log::Event;
+use crate::model::Phase;
+use chrono::Utc;
+use std::collections::HashMap;
+
+/// Whether `phase` is an approval gate rather than work for a driver
+pub fn is_gate(phase: &Phase) -> bool {
+    driver::driver_name(phase) == APPROVAL_DRIVER
+}
+
+/// Park an approval gate until it is decided through `options.control`,
+/// failing it if `approval.timeout` passes first. The decision is recorded
+/// as the gate's only attempt, so the approver and comment appear in the
+/// attempt log and the run.
+pub async fn wait(phase: &Phase, options: &ExecutionOptions) -> Result<PhaseOutputs, String> {
+    println!("Phase {} is waiting for approval", phase.id);
+    let spec = phase.spec.approval.clone().unwrap_or_default();
+    if let Some(logging_service) = &options.logging_service {
+        let _ = logging_service
+            .log_event_with_context(
+                Event::ApprovalRequested {
+                    id: phase.id.clone(),
+                    message: spec.message.clone(),
+                },
+                options.plan_id.clone(),
+                Some(phase.id.clone()),
+                HashMap::new(),
+            )
+            .await;
+    }
+
+    let started_at = Utc::now();
+    let limit = spec.timeout.as_deref().and_then(|t| humantime::parse_duration(t).ok());
+    let outcome = match &options.control {
+        None => PhaseOutcome::failure(format!("Approval gate {} can only be decided within a run", phase.id)),
+        Some(control) => match limit {
+            Some(limit) => match tokio::time::timeout(limit, control.decision(&phase.id)).await {
+                Ok(decision) => outcome_of(&decision),
+                Err(_) => PhaseOutcome::timed_out(format!(
+                    "Approval gate {} was not decided within {:?}",
+                    phase.id, limit
+                )),
+            },
+            None => outcome_of(&control.decision(&phase.id).await),
+        },
+    };
+    let record = AttemptRecord::new(&phase.id, 1, &outcome, started_at, Utc::now(), max_output_bytes());
+    runner::record_attempt(options, APPROVAL_DRIVER, &record).await;
+
+    if outcome.is_success() {
+        hooks::handle_success(phase).await;
+        return Ok(PhaseOutputs::new());
+    }
+    hooks::handle_failure(phase).await;
+    Err(outcome.message.unwrap_or_else(|| "approval gate failed".to_string()))
+}
+
+fn outcome_of(decision: &ApprovalDecision) -> PhaseOutcome {
+    let approver = decision.approver.as_deref().map(|a| format!(" by {}", a)).unwrap_or_default();
+    let comment = decision.comment.as_deref().map(|c| format!(": {}", c)).unwrap_or_default();
+    if decision.approved {
+        PhaseOutcome::success().with_message(format!("Approved{}{}", approver, comment))
+    } else {
+        PhaseOutcome::failure(format!("Rejected{}{}", approver, comment))
+    }
+}
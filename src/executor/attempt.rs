@@ -0,0 +1,218 @@
+use crate::executor::driver::{OutcomeStatus, PhaseOutcome};
+use crate::log::{Event, LogEntry};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// Per-stream cap on captured output from `PLANTER_MAX_OUTPUT_BYTES`
+/// (default 64 KiB)
+pub fn max_output_bytes() -> usize {
+    std::env::var("PLANTER_MAX_OUTPUT_BYTES")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_OUTPUT_BYTES)
+}
+
+/// What happened during one driver attempt of a phase
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttemptRecord {
+    pub phase_id: String,
+    pub attempt: u32,
+    pub status: OutcomeStatus,
+    pub message: Option<String>,
+    pub exit_code: Option<i32>,
+    pub http_status: Option<u16>,
+    pub stdout: String,
+    pub stderr: String,
+    /// The start of stdout was dropped to respect the output cap
+    pub stdout_truncated: bool,
+    pub stderr_truncated: bool,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: u64,
+}
+
+impl AttemptRecord {
+    /// Record `outcome`, keeping at most `max_output` bytes of each stream
+    pub fn new(
+        phase_id: &str,
+        attempt: u32,
+        outcome: &PhaseOutcome,
+        started_at: DateTime<Utc>,
+        finished_at: DateTime<Utc>,
+        max_output: usize,
+    ) -> Self {
+        let (stdout, stdout_truncated) = cap_output(&outcome.stdout, max_output);
+        let (stderr, stderr_truncated) = cap_output(&outcome.stderr, max_output);
+        let stdout_truncated = stdout_truncated || outcome.stdout_truncated;
+        let stderr_truncated = stderr_truncated || outcome.stderr_truncated;
+        Self {
+            phase_id: phase_id.to_string(),
+            attempt,
+            status: outcome.status,
+            message: outcome.message.clone(),
+            exit_code: outcome.exit_code,
+            http_status: outcome.http_status,
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+            stdout_truncated,
+            stderr_truncated,
+            started_at,
+            finished_at,
+            duration_ms: (finished_at - started_at).num_milliseconds().max(0) as u64,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status == OutcomeStatus::Succeeded
+    }
+
+    /// Log context under which the record is persisted with its
+    /// `PhaseAttempt` event
+    pub fn to_context(&self) -> HashMap<String, String> {
+        let mut context = HashMap::new();
+        let status = serde_json::to_value(self.status)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        context.insert("status".to_string(), status);
+        if self.status == OutcomeStatus::TimedOut {
+            context.insert("timed_out".to_string(), "true".to_string());
+        }
+        if let Some(code) = self.exit_code {
+            context.insert("exit_code".to_string(), code.to_string());
+        }
+        if let Some(status) = self.http_status {
+            context.insert("http_status".to_string(), status.to_string());
+        }
+        if let Some(message) = &self.message {
+            context.insert("message".to_string(), message.clone());
+        }
+        if !self.stdout.is_empty() {
+            context.insert("stdout".to_string(), self.stdout.clone());
+        }
+        if !self.stderr.is_empty() {
+            context.insert("stderr".to_string(), self.stderr.clone());
+        }
+        if self.stdout_truncated {
+            context.insert("stdout_truncated".to_string(), "true".to_string());
+        }
+        if self.stderr_truncated {
+            context.insert("stderr_truncated".to_string(), "true".to_string());
+        }
+        context.insert("started_at".to_string(), self.started_at.to_rfc3339());
+        context.insert("finished_at".to_string(), self.finished_at.to_rfc3339());
+        context.insert("duration_ms".to_string(), self.duration_ms.to_string());
+        context
+    }
+
+    /// Rebuild a record from a persisted `PhaseAttempt` log entry
+    pub fn from_log(entry: &LogEntry) -> Option<Self> {
+        let Event::PhaseAttempt { id, attempt, success } = &entry.event else {
+            return None;
+        };
+        let context = &entry.context;
+        let get = |key: &str| context.get(key).cloned();
+        let time = |key: &str| {
+            context
+                .get(key)
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.with_timezone(&Utc))
+        };
+        let status = context
+            .get("status")
+            .and_then(|s| serde_json::from_value(serde_json::Value::String(s.clone())).ok())
+            .unwrap_or(if *success {
+                OutcomeStatus::Succeeded
+            } else {
+                OutcomeStatus::Failed
+            });
+
+        Some(Self {
+            phase_id: id.clone(),
+            attempt: *attempt,
+            status,
+            message: get("message"),
+            exit_code: get("exit_code").and_then(|c| c.parse().ok()),
+            http_status: get("http_status").and_then(|c| c.parse().ok()),
+            stdout: get("stdout").unwrap_or_default(),
+            stderr: get("stderr").unwrap_or_default(),
+            stdout_truncated: context.contains_key("stdout_truncated"),
+            stderr_truncated: context.contains_key("stderr_truncated"),
+            started_at: time("started_at").unwrap_or(entry.timestamp),
+            finished_at: time("finished_at").unwrap_or(entry.timestamp),
+            duration_ms: get("duration_ms").and_then(|d| d.parse().ok()).unwrap_or(0),
+        })
+    }
+}
+
+/// The last `max` bytes of `output` (on a character boundary), and whether
+/// anything was dropped
+pub fn cap_output(output: &str, max: usize) -> (&str, bool) {
+    if output.len() <= max {
+        return (output, false);
+    }
+    let mut start = output.len() - max;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    (&output[start..], true)
+}
+
+/// The last `max` bytes of a stream a driver is capturing, so output is
+/// never buffered in full
+pub struct OutputTail {
+    max: usize,
+    kept: Vec<u8>,
+    truncated: bool,
+}
+
+impl OutputTail {
+    pub fn new(max: usize) -> Self {
+        Self {
+            max,
+            kept: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.kept.extend_from_slice(bytes);
+        if self.kept.len() > self.max {
+            let excess = self.kept.len() - self.max;
+            self.kept.drain(..excess);
+            self.truncated = true;
+        }
+    }
+
+    /// The kept output as text, starting on a character boundary, and
+    /// whether anything was dropped
+    pub fn finish(self) -> (String, bool) {
+        let start = if self.truncated {
+            self.kept.iter().take_while(|b| *b & 0xC0 == 0x80).count()
+        } else {
+            0
+        };
+        (String::from_utf8_lossy(&self.kept[start..]).into_owned(), self.truncated)
+    }
+}
+
+/// Read `reader` to the end, keeping its last `max` bytes
+pub async fn read_tail<R: AsyncRead + Unpin>(mut reader: R, max: usize) -> std::io::Result<(String, bool)> {
+    let mut tail = OutputTail::new(max);
+    let mut chunk = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(tail.finish());
+        }
+        tail.push(&chunk[..n]);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use crate::executor::attempt::{cap_output, read_tail, AttemptRecord, OutputTail};
+    use crate::executor::driver::{OutcomeStatus, PhaseOutcome};
+    use crate::log::{Event, LogEntry};
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_cap_output_keeps_tail() {
+        assert_eq!(cap_output("short", 10), ("short", false));
+        assert_eq!(cap_output("0123456789", 4), ("6789", true));
+        // Never split a multi-byte character
+        assert_eq!(cap_output("aé€", 4), ("€", true));
+    }
+
+    #[test]
+    fn test_output_tail_keeps_last_bytes_on_a_character_boundary() {
+        let mut tail = OutputTail::new(4);
+        tail.push("ab".as_bytes());
+        tail.push("é€".as_bytes());
+        assert_eq!(tail.finish(), ("€".to_string(), true));
+
+        let mut tail = OutputTail::new(8);
+        tail.push(b"short");
+        assert_eq!(tail.finish(), ("short".to_string(), false));
+    }
+
+    #[tokio::test]
+    async fn test_read_tail_bounds_what_is_kept() {
+        let input = "x".repeat(100_000) + "end";
+        let (kept, truncated) = read_tail(input.as_bytes(), 10).await.unwrap();
+        assert_eq!(kept, "xxxxxxxend");
+        assert!(truncated);
+    }
+
+    #[test]
+    fn test_record_keeps_truncation_reported_by_driver() {
+        let outcome = PhaseOutcome {
+            stdout: "tail".to_string(),
+            stdout_truncated: true,
+            ..PhaseOutcome::success()
+        };
+        let started = Utc::now();
+        let record = AttemptRecord::new("deploy", 1, &outcome, started, started, 1024);
+        assert_eq!(record.stdout, "tail");
+        assert!(record.stdout_truncated);
+        assert!(!record.stderr_truncated);
+    }
+
+    #[test]
+    fn test_record_caps_output_and_measures_duration() {
+        let outcome = PhaseOutcome {
+            exit_code: Some(2),
+            stdout: "x".repeat(100),
+            stderr: "boom".to_string(),
+            ..PhaseOutcome::failure("exited with status 2")
+        };
+        let started = Utc::now();
+        let record = AttemptRecord::new("deploy", 3, &outcome, started, started + Duration::milliseconds(1500), 10);
+
+        assert_eq!(record.attempt, 3);
+        assert_eq!(record.status, OutcomeStatus::Failed);
+        assert_eq!(record.exit_code, Some(2));
+        assert_eq!(record.stdout.len(), 10);
+        assert!(record.stdout_truncated);
+        assert_eq!(record.stderr, "boom");
+        assert!(!record.stderr_truncated);
+        assert_eq!(record.duration_ms, 1500);
+    }
+
+    #[test]
+    fn test_record_round_trips_through_log_entry() {
+        let outcome = PhaseOutcome {
+            stdout: "done".to_string(),
+            ..PhaseOutcome::timed_out("attempt timed out")
+        };
+        let started = Utc::now();
+        let record = AttemptRecord::new("deploy", 1, &outcome, started, started + Duration::milliseconds(20), 1024);
+
+        let mut entry = LogEntry::new(Event::PhaseAttempt {
+            id: "deploy".to_string(),
+            attempt: 1,
+            success: false,
+        });
+        entry.context = record.to_context();
+        let restored = AttemptRecord::from_log(&entry).unwrap();
+        assert_eq!(restored.status, OutcomeStatus::TimedOut);
+        assert_eq!(restored.stdout, "done");
+        assert_eq!(restored.message.as_deref(), Some("attempt timed out"));
+        assert_eq!(restored.duration_ms, 20);
+        assert_eq!(restored.started_at.timestamp_millis(), record.started_at.timestamp_millis());
+
+        assert!(AttemptRecord::from_log(&LogEntry::new(Event::Error("x".to_string()))).is_none());
+    }
+}
@@ -0,0 +1,318 @@
+use crate::executor::scheduler::PhaseReport;
+use std::collections::HashMap;
+
+/// A parsed `spec.when` expression.
+///
+/// The language is deliberately small: string, number, `true`, `false` and
+/// `null` literals; references to `params.<name>`, `labels.<name>` (the
+/// phase's own selector labels), `phases.<id>.status` and
+/// `phases.<id>.outputs.<name>`; `==` and `!=`; `!`, `&&`, `||` and
+/// parentheses. Numbers compare as the text they are written as, and a
+/// reference to something that does not exist is `null`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Reference(Reference),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Eq(Box<Expr>, Box<Expr>),
+    Ne(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Bool(bool),
+    Null,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reference {
+    Param(String),
+    Label(String),
+    Status(String),
+    Output { phase: String, name: String },
+}
+
+/// What a condition is evaluated against
+pub struct Context<'a> {
+    pub params: &'a HashMap<String, String>,
+    pub labels: &'a HashMap<String, String>,
+    /// Current status and outputs of the phases of the plan
+    pub phases: &'a [PhaseReport],
+}
+
+/// Parse `source`, checking that it yields true or false
+pub fn parse(source: &str) -> Result<Expr, String> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.or()?;
+    if let Some(token) = parser.tokens.get(parser.pos) {
+        return Err(format!("unexpected {}", token));
+    }
+    if !expr.is_boolean() {
+        return Err("expression must be a comparison or a boolean".to_string());
+    }
+    Ok(expr)
+}
+
+/// Parse and evaluate `source`
+pub fn evaluate(source: &str, ctx: &Context) -> Result<bool, String> {
+    Ok(parse(source)?.holds(ctx))
+}
+
+impl Expr {
+    /// Whether a (boolean) expression is true in `ctx`
+    pub fn holds(&self, ctx: &Context) -> bool {
+        self.value(ctx) == Value::Bool(true)
+    }
+
+    /// Ids of the phases the expression refers to
+    pub fn phases(&self) -> Vec<&str> {
+        let mut ids = Vec::new();
+        self.collect_phases(&mut ids);
+        ids
+    }
+
+    fn value(&self, ctx: &Context) -> Value {
+        match self {
+            Expr::Literal(value) => value.clone(),
+            Expr::Reference(reference) => reference.resolve(ctx).map_or(Value::Null, Value::Str),
+            Expr::Not(e) => Value::Bool(!e.holds(ctx)),
+            Expr::And(a, b) => Value::Bool(a.holds(ctx) && b.holds(ctx)),
+            Expr::Or(a, b) => Value::Bool(a.holds(ctx) || b.holds(ctx)),
+            Expr::Eq(a, b) => Value::Bool(a.value(ctx) == b.value(ctx)),
+            Expr::Ne(a, b) => Value::Bool(a.value(ctx) != b.value(ctx)),
+        }
+    }
+
+    fn is_boolean(&self) -> bool {
+        match self {
+            Expr::Literal(value) => matches!(value, Value::Bool(_)),
+            Expr::Reference(_) => false,
+            Expr::Eq(..) | Expr::Ne(..) => true,
+            Expr::Not(e) => e.is_boolean(),
+            Expr::And(a, b) | Expr::Or(a, b) => a.is_boolean() && b.is_boolean(),
+        }
+    }
+
+    fn collect_phases<'a>(&'a self, ids: &mut Vec<&'a str>) {
+        match self {
+            Expr::Reference(Reference::Status(id)) | Expr::Reference(Reference::Output { phase: id, .. }) => {
+                if !ids.contains(&id.as_str()) {
+                    ids.push(id);
+                }
+            }
+            Expr::Literal(_) | Expr::Reference(_) => {}
+            Expr::Not(e) => e.collect_phases(ids),
+            Expr::And(a, b) | Expr::Or(a, b) | Expr::Eq(a, b) | Expr::Ne(a, b) => {
+                a.collect_phases(ids);
+                b.collect_phases(ids);
+            }
+        }
+    }
+}
+
+impl Reference {
+    fn parse(path: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = path.split('.').collect();
+        let reference = match parts.as_slice() {
+            ["params", name] => Reference::Param(name.to_string()),
+            ["labels", name] => Reference::Label(name.to_string()),
+            ["phases", id, "status"] => Reference::Status(id.to_string()),
+            ["phases", id, "outputs", name] => Reference::Output {
+                phase: id.to_string(),
+                name: name.to_string(),
+            },
+            _ => return Err(format!("unknown reference '{}'", path)),
+        };
+        if parts.iter().any(|p| p.is_empty()) {
+            return Err(format!("unknown reference '{}'", path));
+        }
+        Ok(reference)
+    }
+
+    fn resolve(&self, ctx: &Context) -> Option<String> {
+        let phase = |id: &str| ctx.phases.iter().find(|p| p.id == id);
+        match self {
+            Reference::Param(name) => ctx.params.get(name).cloned(),
+            Reference::Label(name) => ctx.labels.get(name).cloned(),
+            Reference::Status(id) => phase(id)
+                .and_then(|p| serde_json::to_value(p.status).ok())
+                .and_then(|v| v.as_str().map(str::to_string)),
+            Reference::Output { phase: id, name } => phase(id).and_then(|p| p.outputs.get(name).cloned()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Str(String),
+    Word(String),
+    LParen,
+    RParen,
+    Not,
+    And,
+    Or,
+    Eq,
+    Ne,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Str(s) => write!(f, "\"{}\"", s),
+            Token::Word(w) => write!(f, "'{}'", w),
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::Not => write!(f, "'!'"),
+            Token::And => write!(f, "'&&'"),
+            Token::Or => write!(f, "'||'"),
+            Token::Eq => write!(f, "'=='"),
+            Token::Ne => write!(f, "'!='"),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::LParen } else { Token::RParen });
+            }
+            '!' | '=' | '&' | '|' => {
+                chars.next();
+                let next = chars.peek().copied();
+                let token = match (c, next) {
+                    ('!', Some('=')) => Token::Ne,
+                    ('!', _) => Token::Not,
+                    ('=', Some('=')) => Token::Eq,
+                    ('&', Some('&')) => Token::And,
+                    ('|', Some('|')) => Token::Or,
+                    _ => return Err(format!("unexpected '{}'", c)),
+                };
+                if token != Token::Not {
+                    chars.next();
+                }
+                tokens.push(token);
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => value.push(escaped),
+                            None => return Err("unterminated string".to_string()),
+                        },
+                        Some(ch) if ch == c => break,
+                        Some(ch) => value.push(ch),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if !(ch.is_alphanumeric() || matches!(ch, '_' | '-' | '.' | '[' | ']' | ',')) {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            other => return Err(format!("unexpected '{}'", other)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.tokens.get(self.pos) == Some(token) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat(&Token::Not) {
+            let operand = self.unary()?;
+            if !operand.is_boolean() {
+                return Err("'!' needs a comparison or a boolean".to_string());
+            }
+            return Ok(Expr::Not(Box::new(operand)));
+        }
+        let left = self.primary()?;
+        if self.eat(&Token::Eq) {
+            return Ok(Expr::Eq(Box::new(left), Box::new(self.primary()?)));
+        }
+        if self.eat(&Token::Ne) {
+            return Ok(Expr::Ne(Box::new(left), Box::new(self.primary()?)));
+        }
+        Ok(left)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let expr = self.or()?;
+                if !self.eat(&Token::RParen) {
+                    return Err("missing ')'".to_string());
+                }
+                Ok(expr)
+            }
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::Str(s))),
+            Some(Token::Word(word)) => Ok(match word.as_str() {
+                "true" => Expr::Literal(Value::Bool(true)),
+                "false" => Expr::Literal(Value::Bool(false)),
+                "null" => Expr::Literal(Value::Null),
+                w if w.starts_with(|c: char| c.is_ascii_digit()) => Expr::Literal(Value::Str(word)),
+                _ => Expr::Reference(Reference::parse(&word)?),
+            }),
+            Some(token) => Err(format!("unexpected {}", token)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+    use crate::executor::condition::{evaluate, parse, Context};
+    use crate::executor::scheduler::{PhaseReport, PhaseStatus};
+    use std::collections::HashMap;
+
+    fn check(source: &str, params: &[(&str, &str)]) -> bool {
+        let params: HashMap<String, String> = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let labels = HashMap::from([("tier".to_string(), "web".to_string())]);
+        let phases = vec![PhaseReport {
+            id: "preflight".to_string(),
+            status: PhaseStatus::Succeeded,
+            outputs: HashMap::from([("replicas".to_string(), "3".to_string())]),
+        }];
+        let ctx = Context {
+            params: &params,
+            labels: &labels,
+            phases: &phases,
+        };
+        evaluate(source, &ctx).unwrap()
+    }
+
+    #[test]
+    fn test_evaluate_comparisons_and_logic() {
+        let source = r#"phases.preflight.status == "succeeded" && params.env != "prod""#;
+        assert!(check(source, &[("env", "staging")]));
+        assert!(!check(source, &[("env", "prod")]));
+        assert!(check(source, &[]));
+
+        assert!(check("labels.tier == 'web' || false", &[]));
+        assert!(check("phases.preflight.outputs.replicas == 3", &[]));
+        assert!(check("!(params.region == null)", &[("region", "eu-west")]));
+        assert!(check("phases.other.status == null && params.quote == \"a \\\"b\\\"\"", &[("quote", "a \"b\"")]));
+        assert!(!check("true && !true", &[]));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_expressions() {
+        for source in [
+            "",
+            "params.env",
+            "params.env && true",
+            "!params.env",
+            "params.env = 'prod'",
+            "(params.env == 'prod'",
+            "params.env == 'prod' extra",
+            "phases.preflight == 'x'",
+            "env == 'prod'",
+            "params.env == 'unterminated",
+            "params.env == 'prod' # comment",
+        ] {
+            assert!(parse(source).is_err(), "{} should not parse", source);
+        }
+    }
+
+    #[test]
+    fn test_phases_lists_referenced_phases() {
+        let expr = parse("phases.a.status == 'failed' || phases.b.outputs.x == phases.a.outputs.y").unwrap();
+        assert_eq!(expr.phases(), vec!["a", "b"]);
+        let expr = parse("phases.deploy[eu,blue].status == 'succeeded'").unwrap();
+        assert_eq!(expr.phases(), vec!["deploy[eu,blue]"]);
+    }
+}
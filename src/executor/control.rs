@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// What an operator has asked a running plan to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlState {
+    Running,
+    /// Start no further phases; phases already running finish normally
+    Paused,
+    /// Stop the plan and interrupt the phases that are running
+    Cancelled,
+}
+
+/// An operator's answer to an approval gate
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalDecision {
+    pub approved: bool,
+    #[serde(default)]
+    pub approver: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// Handle through which a plan is paused, resumed or cancelled, and its
+/// approval gates decided, while [`run_plan`](crate::executor::run_plan)
+/// executes it. Clones share state.
+#[derive(Debug, Clone)]
+pub struct RunControl {
+    state: Arc<watch::Sender<ControlState>>,
+    /// Decisions on approval gates, keyed by phase id
+    decisions: Arc<watch::Sender<HashMap<String, ApprovalDecision>>>,
+}
+
+impl Default for RunControl {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(watch::Sender::new(ControlState::Running)),
+            decisions: Arc::new(watch::Sender::new(HashMap::new())),
+        }
+    }
+}
+
+impl RunControl {
+    pub fn state(&self) -> ControlState {
+        *self.state.borrow()
+    }
+
+    /// Pause a running plan; returns false if it is paused or cancelled
+    pub fn pause(&self) -> bool {
+        self.transition(ControlState::Running, ControlState::Paused)
+    }
+
+    /// Resume a paused plan; returns false if it is not paused
+    pub fn resume(&self) -> bool {
+        self.transition(ControlState::Paused, ControlState::Running)
+    }
+
+    /// Cancel the plan; returns false if it was already cancelled
+    pub fn cancel(&self) -> bool {
+        self.state.send_if_modified(|state| {
+            let changed = *state != ControlState::Cancelled;
+            *state = ControlState::Cancelled;
+            changed
+        })
+    }
+
+    /// Decide the approval gate `phase_id`; returns false if it was
+    /// already decided
+    pub fn decide(&self, phase_id: &str, decision: ApprovalDecision) -> bool {
+        self.decisions.send_if_modified(|decisions| {
+            if decisions.contains_key(phase_id) {
+                return false;
+            }
+            decisions.insert(phase_id.to_string(), decision);
+            true
+        })
+    }
+
+    /// Wait until the approval gate `phase_id` is decided
+    pub(crate) async fn decision(&self, phase_id: &str) -> ApprovalDecision {
+        let mut decisions = self.decisions.subscribe();
+        let decided = decisions
+            .wait_for(|d| d.contains_key(phase_id))
+            .await
+            .expect("approval decisions closed");
+        decided[phase_id].clone()
+    }
+
+    pub(crate) fn subscribe(&self) -> watch::Receiver<ControlState> {
+        self.state.subscribe()
+    }
+
+    fn transition(&self, from: ControlState, to: ControlState) -> bool {
+        self.state.send_if_modified(|state| {
+            if *state != from {
+                return false;
+            }
+            *state = to;
+            true
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
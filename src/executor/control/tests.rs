@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+    use crate::executor::control::{ApprovalDecision, ControlState, RunControl};
+
+    #[test]
+    fn test_pause_and_resume() {
+        let control = RunControl::default();
+        assert_eq!(control.state(), ControlState::Running);
+        assert!(!control.resume());
+
+        assert!(control.pause());
+        assert!(!control.pause());
+        assert_eq!(control.state(), ControlState::Paused);
+
+        assert!(control.resume());
+        assert_eq!(control.state(), ControlState::Running);
+    }
+
+    #[test]
+    fn test_cancel_is_final() {
+        let control = RunControl::default();
+        assert!(control.pause());
+        assert!(control.cancel());
+        assert!(!control.cancel());
+        assert!(!control.resume());
+        assert!(!control.pause());
+        assert_eq!(control.state(), ControlState::Cancelled);
+    }
+
+    #[test]
+    fn test_clones_share_state() {
+        let control = RunControl::default();
+        let handle = control.clone();
+        handle.cancel();
+        assert_eq!(control.state(), ControlState::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_approval_decided_once() {
+        let control = RunControl::default();
+        let approve = ApprovalDecision {
+            approved: true,
+            approver: Some("alice".to_string()),
+            comment: None,
+        };
+        assert!(control.decide("gate", approve.clone()));
+        assert!(!control.decide("gate", ApprovalDecision::default()));
+        assert_eq!(control.clone().decision("gate").await, approve);
+    }
+}
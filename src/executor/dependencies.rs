@@ -0,0 +1,76 @@
+use crate::model::Phase;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+lazy_static::lazy_static! {
+    /// Phases known to have completed, per tenant: by this process, in an
+    /// earlier run restored from Redis, or as reported by a NATS runtime peer
+    static ref COMPLETED: watch::Sender<HashMap<String, HashSet<String>>> = watch::channel(HashMap::new()).0;
+}
+
+fn tenant() -> String {
+    std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string())
+}
+
+fn completed_in(registry: &HashMap<String, HashSet<String>>, tenant: &str, id: &str) -> bool {
+    registry.get(tenant).is_some_and(|completed| completed.contains(id))
+}
+
+/// Record that a phase has completed for the current tenant, waking anything
+/// waiting on it
+pub fn mark_completed(id: &str) {
+    let tenant = tenant();
+    COMPLETED.send_if_modified(|registry| registry.entry(tenant).or_default().insert(id.to_string()));
+}
+
+pub fn is_completed(id: &str) -> bool {
+    completed_in(&COMPLETED.borrow(), &tenant(), id)
+}
+
+/// Ids listed in `spec.wait_for.phases`
+pub fn dependencies(phase: &Phase) -> &[String] {
+    phase
+        .spec
+        .wait_for
+        .as_ref()
+        .map(|w| w.phases.as_slice())
+        .unwrap_or(&[])
+}
+
+/// How long the phase may wait for its dependencies (`spec.wait_for.timeout`)
+pub fn wait_timeout(phase: &Phase) -> Option<Duration> {
+    phase
+        .spec
+        .wait_for
+        .as_ref()
+        .and_then(|w| w.timeout.as_deref())
+        .and_then(|t| humantime::parse_duration(t).ok())
+}
+
+/// Wait until every id in `ids` has completed. Returns the ids still
+/// outstanding if `deadline` passes first.
+pub async fn wait_for(ids: &[String], deadline: Option<Instant>) -> Result<(), Vec<String>> {
+    let tenant = tenant();
+    let mut rx = COMPLETED.subscribe();
+    let all_done = rx.wait_for(|registry| ids.iter().all(|id| completed_in(registry, &tenant, id)));
+
+    let finished = match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, all_done)
+            .await
+            .map(|r| r.is_ok())
+            .unwrap_or(false),
+        None => all_done.await.is_ok(),
+    };
+    if finished {
+        return Ok(());
+    }
+
+    let registry = COMPLETED.borrow();
+    Err(ids.iter().filter(|id| !completed_in(&registry, &tenant, id)).cloned().collect())
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod tests {
+    use crate::executor::dependencies::{is_completed, mark_completed, wait_for};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[tokio::test]
+    async fn test_wait_for_wakes_when_dependency_completes() {
+        let ids = vec!["deps-test-late".to_string()];
+        tokio::spawn(async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            mark_completed("deps-test-late");
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        assert_eq!(wait_for(&ids, Some(deadline)).await, Ok(()));
+        assert!(is_completed("deps-test-late"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_reports_outstanding_dependencies_at_deadline() {
+        mark_completed("deps-test-done");
+        let ids = vec!["deps-test-done".to_string(), "deps-test-never".to_string()];
+
+        let deadline = Instant::now() + Duration::from_millis(50);
+        assert_eq!(wait_for(&ids, Some(deadline)).await, Err(vec!["deps-test-never".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_nothing_returns_immediately() {
+        assert_eq!(wait_for(&[], Some(Instant::now())).await, Ok(()));
+    }
+}
@@ -1,28 +1,235 @@
+pub mod approval;
+pub mod command;
+pub mod http;
+pub mod simulated;
+
 use crate::model::Phase;
-use std::process::Command;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::process::Command;
+
+/// Driver used when a phase does not name one
+pub const DEFAULT_DRIVER: &str = "python";
+
+/// Per-attempt information handed to a driver
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionContext {
+    pub plan_id: Option<String>,
+    pub attempt: u32,
+    pub max_attempts: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutcomeStatus {
+    Succeeded,
+    Failed,
+    TimedOut,
+}
+
+/// Result of a single driver invocation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhaseOutcome {
+    pub status: OutcomeStatus,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub http_status: Option<u16>,
+    /// Process stdout, or the response body for HTTP requests
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default)]
+    pub stderr: String,
+    /// The driver dropped the start of stdout to respect the output cap
+    #[serde(default)]
+    pub stdout_truncated: bool,
+    #[serde(default)]
+    pub stderr_truncated: bool,
+}
+
+impl PhaseOutcome {
+    pub fn success() -> Self {
+        Self {
+            status: OutcomeStatus::Succeeded,
+            message: None,
+            exit_code: None,
+            http_status: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            stdout_truncated: false,
+            stderr_truncated: false,
+        }
+    }
+
+    pub fn failure(message: impl Into<String>) -> Self {
+        Self {
+            status: OutcomeStatus::Failed,
+            message: Some(message.into()),
+            ..Self::success()
+        }
+    }
+
+    pub fn timed_out(message: impl Into<String>) -> Self {
+        Self {
+            status: OutcomeStatus::TimedOut,
+            message: Some(message.into()),
+            ..Self::success()
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status == OutcomeStatus::Succeeded
+    }
+}
+
+/// Executes the work described by a phase
+#[async_trait]
+pub trait PhaseDriver: Send + Sync {
+    async fn execute(&self, phase: &Phase, ctx: &ExecutionContext) -> PhaseOutcome;
+
+    /// Check that a phase carries the configuration this driver needs.
+    /// Called when a plan is submitted, before anything runs.
+    fn validate(&self, _phase: &Phase) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Describe what `execute` would do without doing it, for dry runs
+    fn describe(&self, phase: &Phase, _ctx: &ExecutionContext) -> String {
+        format!("execute '{}'", phase.spec.description)
+    }
+}
+
+/// Named collection of drivers that phases select via `spec.driver`
+#[derive(Clone, Default)]
+pub struct DriverRegistry {
+    drivers: HashMap<String, Arc<dyn PhaseDriver>>,
+}
+
+impl DriverRegistry {
+    /// Registry with the built-in `python`, `command`, `http`, `approval`, `noop` and `simulated` drivers
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::default();
+        registry.register(DEFAULT_DRIVER, Arc::new(PythonDriver));
+        registry.register(command::COMMAND_DRIVER, Arc::new(command::CommandDriver));
+        registry.register(http::HTTP_DRIVER, Arc::new(http::HttpDriver::default()));
+        registry.register(approval::APPROVAL_DRIVER, Arc::new(approval::ApprovalDriver));
+        registry.register("noop", Arc::new(simulated::NoopDriver));
+        registry.register("simulated", Arc::new(simulated::SimulatedDriver::default()));
+        registry
+    }
+
+    pub fn register(&mut self, name: &str, driver: Arc<dyn PhaseDriver>) {
+        self.drivers.insert(name.to_string(), driver);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn PhaseDriver>> {
+        self.drivers.get(name).cloned()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.drivers.contains_key(name)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_REGISTRY: RwLock<DriverRegistry> = RwLock::new(DriverRegistry::with_builtins());
+}
+
+/// Register a driver in the process-wide registry, replacing any driver of the same name
+pub fn register_driver(name: &str, driver: Arc<dyn PhaseDriver>) {
+    GLOBAL_REGISTRY.write().unwrap().register(name, driver);
+}
+
+/// Look up a driver in the process-wide registry
+pub fn get_driver(name: &str) -> Option<Arc<dyn PhaseDriver>> {
+    GLOBAL_REGISTRY.read().unwrap().get(name)
+}
+
+pub fn has_driver(name: &str) -> bool {
+    GLOBAL_REGISTRY.read().unwrap().contains(name)
+}
+
+/// Name of the driver selected by a phase. Phases that declare a
+/// `command`, `http` or `approval` spec without naming a driver use the
+/// matching driver.
+pub fn driver_name(phase: &Phase) -> &str {
+    if let Some(name) = &phase.spec.driver {
+        name
+    } else if phase.spec.approval.is_some() {
+        approval::APPROVAL_DRIVER
+    } else if phase.spec.command.is_some() {
+        command::COMMAND_DRIVER
+    } else if phase.spec.http.is_some() {
+        http::HTTP_DRIVER
+    } else {
+        DEFAULT_DRIVER
+    }
+}
+
+/// The original Planter behaviour: run a placeholder python3 script
+pub struct PythonDriver;
+
+#[async_trait]
+impl PhaseDriver for PythonDriver {
+    async fn execute(&self, phase: &Phase, _ctx: &ExecutionContext) -> PhaseOutcome {
+        run_script(phase).await
+    }
+
+    fn describe(&self, phase: &Phase, _ctx: &ExecutionContext) -> String {
+        format!("run the placeholder python3 script for '{}'", phase.spec.description)
+    }
+}
 
 pub async fn execute(phase: &Phase) -> Result<(), String> {
+    let outcome = run_script(phase).await;
+    match outcome.message {
+        Some(message) if !outcome.is_success() => Err(message),
+        _ => Ok(()),
+    }
+}
+
+/// Run the placeholder script, capturing its output and exit status
+async fn run_script(phase: &Phase) -> PhaseOutcome {
     let desc = &phase.spec.description;
     println!("(Simulating Python execution for '{}')", desc);
 
     // Replace this with real logic — for now we simulate success
     // Use a safer command that doesn't involve shell escaping issues
-    let output = Command::new("python3")
+    let output = match Command::new("python3")
         .arg("-c")
         .arg("print('Executing phase')")
         .output()
-        .map_err(|e| e.to_string())?;
+        .await
+    {
+        Ok(output) => output,
+        Err(e) => return PhaseOutcome::failure(e.to_string()),
+    };
 
-    if !output.status.success() {
-        return Err(format!(
-            "Script failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    println!("{}", stdout);
+    let outcome = if output.status.success() {
+        PhaseOutcome::success()
+    } else {
+        PhaseOutcome::failure(format!("Script failed: {}", stderr))
+    };
+    PhaseOutcome {
+        exit_code: output.status.code(),
+        stdout,
+        stderr,
+        ..outcome
     }
-
-    println!("{}", String::from_utf8_lossy(&output.stdout));
-    Ok(())
 }
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests;
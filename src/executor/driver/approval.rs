@@ -0,0 +1,32 @@
+use crate::executor::driver::{ExecutionContext, PhaseDriver, PhaseOutcome};
+use crate::model::Phase;
+use async_trait::async_trait;
+
+pub const APPROVAL_DRIVER: &str = "approval";
+
+/// Approval gates are decided by an operator rather than executed: the
+/// executor parks them until `POST /runs/:id/phases/:phase/approve` or
+/// `reject` (see [`crate::executor::approval`]). This driver only validates
+/// and describes them.
+pub struct ApprovalDriver;
+
+#[async_trait]
+impl PhaseDriver for ApprovalDriver {
+    async fn execute(&self, phase: &Phase, _ctx: &ExecutionContext) -> PhaseOutcome {
+        PhaseOutcome::failure(format!("Approval gate {} can only be decided within a run", phase.id))
+    }
+
+    fn validate(&self, phase: &Phase) -> Result<(), String> {
+        if phase.spec.command.is_some() || phase.spec.http.is_some() {
+            return Err("an approval gate does not run a command or http request".to_string());
+        }
+        Ok(())
+    }
+
+    fn describe(&self, phase: &Phase, _ctx: &ExecutionContext) -> String {
+        match phase.spec.approval.as_ref().and_then(|a| a.message.as_deref()) {
+            Some(message) => format!("wait for approval: {}", message),
+            None => format!("wait for approval of '{}'", phase.spec.description),
+        }
+    }
+}
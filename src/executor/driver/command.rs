@@ -0,0 +1,112 @@
+use crate::executor::attempt::{max_output_bytes, read_tail};
+use crate::executor::driver::{ExecutionContext, OutcomeStatus, PhaseDriver, PhaseOutcome};
+use crate::model::{CommandSpec, Phase};
+use async_trait::async_trait;
+use std::process::Stdio;
+use tokio::io::{AsyncRead, AsyncWriteExt};
+use tokio::process::Command;
+
+pub const COMMAND_DRIVER: &str = "command";
+
+/// Runs the process declared in `spec.command`. A zero exit status is
+/// success; anything else is reported as a failed attempt. Only the last
+/// `PLANTER_MAX_OUTPUT_BYTES` of stdout and stderr are kept.
+pub struct CommandDriver;
+
+#[async_trait]
+impl PhaseDriver for CommandDriver {
+    async fn execute(&self, phase: &Phase, _ctx: &ExecutionContext) -> PhaseOutcome {
+        let Some(spec) = &phase.spec.command else {
+            return PhaseOutcome::failure(format!("Phase {} has no command to run", phase.id));
+        };
+
+        match run_command(spec).await {
+            Ok(outcome) => outcome,
+            Err(e) => PhaseOutcome::failure(format!("Failed to run '{}': {}", spec.program, e)),
+        }
+    }
+
+    fn validate(&self, phase: &Phase) -> Result<(), String> {
+        match &phase.spec.command {
+            Some(spec) if spec.program.trim().is_empty() => {
+                Err("command.program must not be empty".to_string())
+            }
+            Some(_) => Ok(()),
+            None => Err("the command driver requires a 'command' spec".to_string()),
+        }
+    }
+
+    fn describe(&self, phase: &Phase, _ctx: &ExecutionContext) -> String {
+        let Some(spec) = &phase.spec.command else {
+            return "fail: no command to run".to_string();
+        };
+        let mut line = std::iter::once(spec.program.as_str())
+            .chain(spec.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if let Some(dir) = &spec.working_dir {
+            line.push_str(&format!(" (in {})", dir));
+        }
+        format!("run `{}`", line)
+    }
+}
+
+async fn run_command(spec: &CommandSpec) -> std::io::Result<PhaseOutcome> {
+    let mut cmd = Command::new(&spec.program);
+    cmd.args(&spec.args)
+        .envs(&spec.env)
+        .stdin(if spec.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(dir) = &spec.working_dir {
+        cmd.current_dir(dir);
+    }
+
+    let mut child = cmd.spawn()?;
+    if let (Some(input), Some(mut stdin)) = (spec.stdin.clone(), child.stdin.take()) {
+        // Feed stdin concurrently so a child filling its stdout pipe cannot
+        // deadlock us; dropping the handle closes the pipe (EOF).
+        tokio::spawn(async move {
+            let _ = stdin.write_all(input.as_bytes()).await;
+        });
+    }
+
+    let max = max_output_bytes();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let (exit, (stdout, stdout_truncated), (stderr, stderr_truncated)) = tokio::try_join!(
+        child.wait(),
+        capture(stdout, max),
+        capture(stderr, max),
+    )?;
+    let exit_code = exit.code();
+    let status = if exit.success() {
+        OutcomeStatus::Succeeded
+    } else {
+        OutcomeStatus::Failed
+    };
+    let message = match (status, exit_code) {
+        (OutcomeStatus::Succeeded, _) => None,
+        (_, Some(code)) => Some(format!("'{}' exited with status {}", spec.program, code)),
+        (_, None) => Some(format!("'{}' was terminated by a signal", spec.program)),
+    };
+
+    Ok(PhaseOutcome {
+        status,
+        message,
+        exit_code,
+        stdout,
+        stderr,
+        stdout_truncated,
+        stderr_truncated,
+        ..PhaseOutcome::success()
+    })
+}
+
+async fn capture(stream: Option<impl AsyncRead + Unpin>, max: usize) -> std::io::Result<(String, bool)> {
+    match stream {
+        Some(stream) => read_tail(stream, max).await,
+        None => Ok((String::new(), false)),
+    }
+}
@@ -0,0 +1,101 @@
+use crate::executor::attempt::{max_output_bytes, OutputTail};
+use crate::executor::driver::{ExecutionContext, OutcomeStatus, PhaseDriver, PhaseOutcome};
+use crate::executor::template::{phase_vars, render, render_json};
+use crate::model::{HttpSpec, Phase};
+use async_trait::async_trait;
+use reqwest::Method;
+
+pub const HTTP_DRIVER: &str = "http";
+
+/// Sends the request declared in `spec.http` and succeeds when the response
+/// status is in `success_codes` (any 2xx by default). `${phase.id}`,
+/// `${plan.id}` and `${attempt}` are substituted in the URL, headers and body.
+/// Only the last `PLANTER_MAX_OUTPUT_BYTES` of the response body are kept.
+pub struct HttpDriver {
+    client: reqwest::Client,
+}
+
+impl Default for HttpDriver {
+    fn default() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl PhaseDriver for HttpDriver {
+    async fn execute(&self, phase: &Phase, ctx: &ExecutionContext) -> PhaseOutcome {
+        let Some(spec) = &phase.spec.http else {
+            return PhaseOutcome::failure(format!("Phase {} has no http request to send", phase.id));
+        };
+
+        let vars = phase_vars(phase, ctx);
+        let method = match parse_method(spec) {
+            Ok(method) => method,
+            Err(e) => return PhaseOutcome::failure(e),
+        };
+        let url = render(&spec.url, &vars);
+
+        let mut request = self.client.request(method.clone(), &url);
+        for (name, value) in &spec.headers {
+            request = request.header(name, render(value, &vars));
+        }
+        if let Some(body) = &spec.body {
+            request = request.json(&render_json(body, &vars));
+        }
+
+        let mut response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => return PhaseOutcome::failure(format!("{} {} failed: {}", method, url, e)),
+        };
+
+        let status = response.status();
+        let mut body = OutputTail::new(max_output_bytes());
+        while let Ok(Some(chunk)) = response.chunk().await {
+            body.push(&chunk);
+        }
+        let (body, stdout_truncated) = body.finish();
+        let accepted = if spec.success_codes.is_empty() {
+            status.is_success()
+        } else {
+            spec.success_codes.contains(&status.as_u16())
+        };
+
+        PhaseOutcome {
+            status: if accepted { OutcomeStatus::Succeeded } else { OutcomeStatus::Failed },
+            message: Some(format!("{} {} returned {}", method, url, status)),
+            http_status: Some(status.as_u16()),
+            stdout: body,
+            stdout_truncated,
+            ..PhaseOutcome::success()
+        }
+    }
+
+    fn validate(&self, phase: &Phase) -> Result<(), String> {
+        let Some(spec) = &phase.spec.http else {
+            return Err("the http driver requires an 'http' spec".to_string());
+        };
+        if spec.url.trim().is_empty() {
+            return Err("http.url must not be empty".to_string());
+        }
+        parse_method(spec)?;
+        if let Some(code) = spec.success_codes.iter().find(|c| !(100..=599).contains(*c)) {
+            return Err(format!("http.success_codes contains invalid status {}", code));
+        }
+        Ok(())
+    }
+
+    fn describe(&self, phase: &Phase, ctx: &ExecutionContext) -> String {
+        let Some(spec) = &phase.spec.http else {
+            return "fail: no http request to send".to_string();
+        };
+        let method = parse_method(spec).map(|m| m.to_string()).unwrap_or_else(|e| e);
+        format!("send {} {}", method, render(&spec.url, &phase_vars(phase, ctx)))
+    }
+}
+
+fn parse_method(spec: &HttpSpec) -> Result<Method, String> {
+    let name = spec.method.as_deref().unwrap_or("POST").to_uppercase();
+    Method::from_bytes(name.as_bytes()).map_err(|_| format!("invalid http.method '{}'", name))
+}
@@ -0,0 +1,59 @@
+use crate::executor::driver::{ExecutionContext, PhaseDriver, PhaseOutcome};
+use crate::model::Phase;
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Driver that succeeds immediately without doing any work
+pub struct NoopDriver;
+
+#[async_trait]
+impl PhaseDriver for NoopDriver {
+    async fn execute(&self, _phase: &Phase, _ctx: &ExecutionContext) -> PhaseOutcome {
+        PhaseOutcome::success()
+    }
+
+    fn describe(&self, _phase: &Phase, _ctx: &ExecutionContext) -> String {
+        "do nothing".to_string()
+    }
+}
+
+/// Driver that pretends to execute a phase, optionally taking some time and
+/// failing the first few attempts. Useful for rehearsing plans and in tests.
+#[derive(Debug, Clone, Default)]
+pub struct SimulatedDriver {
+    delay: Duration,
+    failing_attempts: u32,
+}
+
+impl SimulatedDriver {
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Fail attempts `1..=attempts` and succeed afterwards
+    pub fn failing_attempts(mut self, attempts: u32) -> Self {
+        self.failing_attempts = attempts;
+        self
+    }
+
+    pub fn always_failing(self) -> Self {
+        self.failing_attempts(u32::MAX)
+    }
+}
+
+#[async_trait]
+impl PhaseDriver for SimulatedDriver {
+    async fn execute(&self, phase: &Phase, ctx: &ExecutionContext) -> PhaseOutcome {
+        println!("(Simulating execution of '{}', attempt {})", phase.spec.description, ctx.attempt);
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+
+        if ctx.attempt <= self.failing_attempts {
+            PhaseOutcome::failure(format!("Simulated failure on attempt {}", ctx.attempt))
+        } else {
+            PhaseOutcome::success().with_message("Simulated execution")
+        }
+    }
+}
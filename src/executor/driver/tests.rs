@@ -1,9 +1,17 @@
 #[cfg(test)]
 mod tests {
-    use super::*;
     use crate::model::*;
-    use crate::executor::driver::execute;
+    use crate::executor::driver::{
+        driver_name, execute, get_driver, register_driver, DriverRegistry, ExecutionContext,
+        PhaseDriver, PhaseOutcome, DEFAULT_DRIVER,
+    };
+    use crate::executor::driver::simulated::SimulatedDriver;
+    use crate::executor::driver::command::CommandDriver;
+    use crate::executor::driver::http::HttpDriver;
+    use axum::{extract::Json as JsonBody, http::{HeaderMap, StatusCode}, routing::{get, post}, Router};
+    use async_trait::async_trait;
     use std::collections::HashMap;
+    use std::sync::Arc;
 
     fn create_test_phase(id: &str, description: &str) -> Phase {
         Phase {
@@ -18,11 +26,7 @@ mod tests {
                         labels
                     },
                 },
-                instance_mode: None,
-                wait_for: None,
-                retry: None,
-                on_failure: None,
-                on_success: None,
+                ..Default::default()
             },
         }
     }
@@ -84,4 +88,264 @@ mod tests {
             }
         }
     }
+
+    struct EchoDriver;
+
+    #[async_trait]
+    impl PhaseDriver for EchoDriver {
+        async fn execute(&self, phase: &Phase, ctx: &ExecutionContext) -> PhaseOutcome {
+            PhaseOutcome::success().with_message(format!("{}#{}", phase.id, ctx.attempt))
+        }
+    }
+
+    fn ctx(attempt: u32) -> ExecutionContext {
+        ExecutionContext {
+            attempt,
+            max_attempts: 3,
+            ..ExecutionContext::default()
+        }
+    }
+
+    #[test]
+    fn test_driver_name_defaults_to_python() {
+        let mut phase = create_test_phase("named", "Driver name");
+        assert_eq!(driver_name(&phase), DEFAULT_DRIVER);
+
+        phase.spec.driver = Some("noop".to_string());
+        assert_eq!(driver_name(&phase), "noop");
+    }
+
+    #[test]
+    fn test_registry_builtins() {
+        let registry = DriverRegistry::with_builtins();
+        assert!(registry.contains("python"));
+        assert!(registry.contains("noop"));
+        assert!(registry.contains("simulated"));
+        assert!(!registry.contains("missing"));
+    }
+
+    #[tokio::test]
+    async fn test_register_custom_driver() {
+        register_driver("test-echo", Arc::new(EchoDriver));
+        let phase = create_test_phase("echo", "Echo driver");
+
+        let driver = get_driver("test-echo").expect("driver registered");
+        let outcome = driver.execute(&phase, &ctx(2)).await;
+        assert!(outcome.is_success());
+        assert_eq!(outcome.message.as_deref(), Some("echo#2"));
+    }
+
+    #[tokio::test]
+    async fn test_noop_driver_succeeds() {
+        let phase = create_test_phase("noop", "No-op driver");
+        let outcome = get_driver("noop").unwrap().execute(&phase, &ctx(1)).await;
+        assert!(outcome.is_success());
+    }
+
+    #[tokio::test]
+    async fn test_simulated_driver_failing_attempts() {
+        let driver = SimulatedDriver::default().failing_attempts(2);
+        let phase = create_test_phase("sim", "Simulated driver");
+
+        assert!(!driver.execute(&phase, &ctx(1)).await.is_success());
+        assert!(!driver.execute(&phase, &ctx(2)).await.is_success());
+        assert!(driver.execute(&phase, &ctx(3)).await.is_success());
+    }
+
+    fn command_phase(id: &str, program: &str, args: &[&str]) -> Phase {
+        let mut phase = create_test_phase(id, "Command phase");
+        phase.spec.command = Some(CommandSpec {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            env: HashMap::new(),
+            working_dir: None,
+            stdin: None,
+        });
+        phase
+    }
+
+    #[test]
+    fn test_command_phase_selects_command_driver() {
+        let phase = command_phase("cmd", "true", &[]);
+        assert_eq!(driver_name(&phase), "command");
+    }
+
+    #[tokio::test]
+    async fn test_command_driver_captures_output() {
+        let phase = command_phase("cmd", "sh", &["-c", "echo out; echo err >&2"]);
+
+        let outcome = CommandDriver.execute(&phase, &ctx(1)).await;
+        assert!(outcome.is_success());
+        assert_eq!(outcome.exit_code, Some(0));
+        assert_eq!(outcome.stdout, "out\n");
+        assert_eq!(outcome.stderr, "err\n");
+    }
+
+    #[tokio::test]
+    async fn test_command_driver_caps_output() {
+        let phase = command_phase("cmd", "sh", &["-c", "head -c 200000 /dev/zero | tr '\\0' x; echo done"]);
+
+        let outcome = CommandDriver.execute(&phase, &ctx(1)).await;
+        assert!(outcome.is_success());
+        assert_eq!(outcome.stdout.len(), crate::executor::attempt::max_output_bytes());
+        assert!(outcome.stdout.ends_with("xdone\n"));
+        assert!(outcome.stdout_truncated);
+        assert!(!outcome.stderr_truncated);
+    }
+
+    #[tokio::test]
+    async fn test_command_driver_nonzero_exit_fails() {
+        let phase = command_phase("cmd", "sh", &["-c", "exit 3"]);
+
+        let outcome = CommandDriver.execute(&phase, &ctx(1)).await;
+        assert!(!outcome.is_success());
+        assert_eq!(outcome.exit_code, Some(3));
+        assert!(outcome.message.unwrap().contains("status 3"));
+    }
+
+    #[tokio::test]
+    async fn test_command_driver_env_cwd_and_stdin() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut phase = command_phase("cmd", "sh", &["-c", "echo \"$GREETING $(pwd)\"; cat"]);
+        let spec = phase.spec.command.as_mut().unwrap();
+        spec.env.insert("GREETING".to_string(), "hello".to_string());
+        spec.working_dir = Some(tmp.path().to_string_lossy().into_owned());
+        spec.stdin = Some("from stdin".to_string());
+
+        let outcome = CommandDriver.execute(&phase, &ctx(1)).await;
+        assert!(outcome.is_success());
+        let cwd = tmp.path().canonicalize().unwrap();
+        assert_eq!(
+            outcome.stdout,
+            format!("hello {}\nfrom stdin", cwd.to_string_lossy())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_command_driver_missing_program() {
+        let phase = command_phase("cmd", "/nonexistent/planter-test-binary", &[]);
+
+        let outcome = CommandDriver.execute(&phase, &ctx(1)).await;
+        assert!(!outcome.is_success());
+        assert!(outcome.exit_code.is_none());
+    }
+
+    #[test]
+    fn test_command_driver_validation() {
+        let phase = create_test_phase("cmd", "No command");
+        assert!(CommandDriver.validate(&phase).is_err());
+
+        let phase = command_phase("cmd", "  ", &[]);
+        assert!(CommandDriver.validate(&phase).is_err());
+
+        let phase = command_phase("cmd", "true", &[]);
+        assert!(CommandDriver.validate(&phase).is_ok());
+    }
+
+    /// Local stand-in for a remote service, returning its base URL
+    async fn spawn_stand_in() -> String {
+        let app = Router::new()
+            .route("/echo", post(|headers: HeaderMap, JsonBody(body): JsonBody<serde_json::Value>| async move {
+                let token = headers
+                    .get("x-token")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string();
+                axum::Json(serde_json::json!({ "token": token, "received": body }))
+            }))
+            .route("/accepted", post(|| async { StatusCode::ACCEPTED }))
+            .route("/broken", get(|| async { (StatusCode::INTERNAL_SERVER_ERROR, "boom") }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    fn http_phase(id: &str, url: String, method: Option<&str>) -> Phase {
+        let mut phase = create_test_phase(id, "HTTP phase");
+        phase.spec.http = Some(HttpSpec {
+            url,
+            method: method.map(|m| m.to_string()),
+            headers: HashMap::new(),
+            body: None,
+            success_codes: vec![],
+        });
+        phase
+    }
+
+    #[test]
+    fn test_http_phase_selects_http_driver() {
+        let phase = http_phase("hook", "http://localhost/".to_string(), None);
+        assert_eq!(driver_name(&phase), "http");
+    }
+
+    #[tokio::test]
+    async fn test_http_driver_sends_templated_request() {
+        let base = spawn_stand_in().await;
+        let mut phase = http_phase("hook", format!("{}/echo", base), None);
+        let spec = phase.spec.http.as_mut().unwrap();
+        spec.headers.insert("x-token".to_string(), "attempt-${attempt}".to_string());
+        spec.body = Some(serde_json::json!({ "phase": "${phase.id}", "plan": "${plan.id}", "count": 2 }));
+
+        let ctx = ExecutionContext {
+            plan_id: Some("plan-9".to_string()),
+            ..ctx(2)
+        };
+        let outcome = HttpDriver::default().execute(&phase, &ctx).await;
+        assert!(outcome.is_success(), "{:?}", outcome);
+        assert_eq!(outcome.http_status, Some(200));
+
+        let body: serde_json::Value = serde_json::from_str(&outcome.stdout).unwrap();
+        assert_eq!(body["token"], "attempt-2");
+        assert_eq!(body["received"], serde_json::json!({ "phase": "hook", "plan": "plan-9", "count": 2 }));
+    }
+
+    #[tokio::test]
+    async fn test_http_driver_error_status_fails() {
+        let base = spawn_stand_in().await;
+        let phase = http_phase("hook", format!("{}/broken", base), Some("get"));
+
+        let outcome = HttpDriver::default().execute(&phase, &ctx(1)).await;
+        assert!(!outcome.is_success());
+        assert_eq!(outcome.http_status, Some(500));
+        assert_eq!(outcome.stdout, "boom");
+    }
+
+    #[tokio::test]
+    async fn test_http_driver_custom_success_codes() {
+        let base = spawn_stand_in().await;
+        let mut phase = http_phase("hook", format!("{}/accepted", base), None);
+
+        let outcome = HttpDriver::default().execute(&phase, &ctx(1)).await;
+        assert!(outcome.is_success());
+
+        phase.spec.http.as_mut().unwrap().success_codes = vec![200];
+        let outcome = HttpDriver::default().execute(&phase, &ctx(1)).await;
+        assert!(!outcome.is_success());
+        assert_eq!(outcome.http_status, Some(202));
+    }
+
+    #[tokio::test]
+    async fn test_http_driver_connection_error_fails() {
+        let phase = http_phase("hook", "http://127.0.0.1:1/unreachable".to_string(), None);
+
+        let outcome = HttpDriver::default().execute(&phase, &ctx(1)).await;
+        assert!(!outcome.is_success());
+        assert!(outcome.http_status.is_none());
+    }
+
+    #[test]
+    fn test_http_driver_validation() {
+        let driver = HttpDriver::default();
+        assert!(driver.validate(&create_test_phase("hook", "No http")).is_err());
+        assert!(driver.validate(&http_phase("hook", "".to_string(), None)).is_err());
+        assert!(driver.validate(&http_phase("hook", "http://x/".to_string(), Some("NOT A METHOD"))).is_err());
+
+        let mut phase = http_phase("hook", "http://x/".to_string(), Some("put"));
+        assert!(driver.validate(&phase).is_ok());
+        phase.spec.http.as_mut().unwrap().success_codes = vec![999];
+        assert!(driver.validate(&phase).is_err());
+    }
 }
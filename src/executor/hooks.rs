@@ -1,5 +1,49 @@
 use crate::model::{Handler, Phase};
 
+/// What the executor does once a phase's handler has run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandlerAction {
+    /// Let dependents run as if the phase had succeeded
+    Continue,
+    /// Fail the plan: start no further phases
+    Raise,
+    /// Cancel every remaining phase, including running ones
+    Abort,
+    /// Mark the phase's dependents skipped
+    Skip,
+}
+
+impl HandlerAction {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "continue" => Ok(HandlerAction::Continue),
+            "raise" => Ok(HandlerAction::Raise),
+            "abort" => Ok(HandlerAction::Abort),
+            "skip" => Ok(HandlerAction::Skip),
+            other => Err(format!(
+                "unknown action '{}' (expected continue, raise, abort or skip)",
+                other
+            )),
+        }
+    }
+}
+
+/// Action after the phase succeeds; `continue` unless `onSuccess` says otherwise
+pub fn success_action(phase: &Phase) -> HandlerAction {
+    handler_action(phase.spec.on_success.as_ref()).unwrap_or(HandlerAction::Continue)
+}
+
+/// Action after the phase fails; `skip` unless `onFailure` says otherwise
+pub fn failure_action(phase: &Phase) -> HandlerAction {
+    handler_action(phase.spec.on_failure.as_ref()).unwrap_or(HandlerAction::Skip)
+}
+
+fn handler_action(handler: Option<&Handler>) -> Option<HandlerAction> {
+    handler
+        .and_then(|h| h.action.as_deref())
+        .and_then(|a| HandlerAction::parse(a).ok())
+}
+
 pub async fn handle_success(phase: &Phase) {
     if let Some(handler) = &phase.spec.on_success {
         run_handler("Success", handler).await;
@@ -31,4 +75,5 @@ async fn run_handler(label: &str, handler: &Handler) {
 }
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests;
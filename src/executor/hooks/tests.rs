@@ -1,8 +1,7 @@
 #[cfg(test)]
 mod tests {
-    use super::*;
     use crate::model::*;
-    use crate::executor::hooks::{handle_success, handle_failure};
+    use crate::executor::hooks::{failure_action, handle_success, handle_failure, success_action, HandlerAction};
     use std::collections::HashMap;
 
     fn create_test_phase_with_handlers(id: &str) -> Phase {
@@ -18,9 +17,6 @@ mod tests {
                         labels
                     },
                 },
-                instance_mode: None,
-                wait_for: None,
-                retry: None,
                 on_failure: Some(Handler {
                     action: Some("log".to_string()),
                     spec: Some(HandlerSpec {
@@ -48,6 +44,7 @@ mod tests {
                         }),
                     }),
                 }),
+                ..Default::default()
             },
         }
     }
@@ -65,11 +62,7 @@ mod tests {
                         labels
                     },
                 },
-                instance_mode: None,
-                wait_for: None,
-                retry: None,
-                on_failure: None,
-                on_success: None,
+                ..Default::default()
             },
         }
     }
@@ -114,21 +107,31 @@ mod tests {
             id: "minimal".to_string(),
             spec: PhaseSpec {
                 description: "Minimal test".to_string(),
-                selector: Selector {
-                    match_labels: HashMap::new(),
-                },
-                instance_mode: None,
-                wait_for: None,
-                retry: None,
                 on_failure: Some(Handler {
                     action: Some("continue".to_string()),
                     spec: None, // No spec
                 }),
-                on_success: None,
+                ..Default::default()
             },
         };
         
         // Should handle missing spec gracefully
         handle_failure(&phase).await;
     }
+
+    #[test]
+    fn test_parse_handler_actions() {
+        assert_eq!(HandlerAction::parse("continue"), Ok(HandlerAction::Continue));
+        assert_eq!(HandlerAction::parse("raise"), Ok(HandlerAction::Raise));
+        assert_eq!(HandlerAction::parse("abort"), Ok(HandlerAction::Abort));
+        assert_eq!(HandlerAction::parse("skip"), Ok(HandlerAction::Skip));
+        assert!(HandlerAction::parse("retry").is_err());
+    }
+
+    #[test]
+    fn test_default_actions() {
+        let phase = create_test_phase_no_handlers("defaults");
+        assert_eq!(success_action(&phase), HandlerAction::Continue);
+        assert_eq!(failure_action(&phase), HandlerAction::Skip);
+    }
 }
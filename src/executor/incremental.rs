@@ -0,0 +1,56 @@
+use crate::diff::DiffResult;
+use crate::executor::dependencies;
+use crate::model::Phase;
+use std::collections::{HashMap, HashSet};
+
+/// Phases of `incoming` an incremental apply can leave alone: unchanged
+/// since the applied plan, among the `completed` phases of the last
+/// applied run, and not downstream of any phase that has to run.
+pub fn unchanged_phases(diff: &[DiffResult], incoming: &[Phase], completed: &[String]) -> Vec<String> {
+    let changed: HashSet<&str> = diff
+        .iter()
+        .filter_map(|change| match change {
+            DiffResult::Add(phase) | DiffResult::Update { new: phase, .. } => Some(phase.id.as_str()),
+            DiffResult::Delete(_) => None,
+        })
+        .collect();
+
+    let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+    for phase in incoming {
+        for dep in dependencies::dependencies(phase) {
+            dependents.entry(dep.as_str()).or_default().push(phase.id.as_str());
+        }
+    }
+
+    let mut to_apply: HashSet<&str> = HashSet::new();
+    let mut stack: Vec<&str> = incoming
+        .iter()
+        .map(|p| p.id.as_str())
+        .filter(|id| changed.contains(id) || !completed.iter().any(|c| c == id))
+        .collect();
+    while let Some(id) = stack.pop() {
+        if to_apply.insert(id) {
+            stack.extend(dependents.get(id).into_iter().flatten().copied());
+        }
+    }
+
+    incoming
+        .iter()
+        .filter(|p| !to_apply.contains(p.id.as_str()))
+        .map(|p| p.id.clone())
+        .collect()
+}
+
+/// Phases removed from the plan, as they were last applied
+pub fn deleted_phases(diff: &[DiffResult]) -> Vec<&Phase> {
+    diff.iter()
+        .filter_map(|change| match change {
+            DiffResult::Delete(phase) => Some(phase),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
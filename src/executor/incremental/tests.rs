@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod tests {
+    use crate::diff::diff_plans;
+    use crate::executor::incremental::{deleted_phases, unchanged_phases};
+    use crate::model::*;
+
+    fn create_test_phase(id: &str, wait_for: &[&str], description: &str) -> Phase {
+        Phase {
+            kind: "Phase".to_string(),
+            id: id.to_string(),
+            spec: PhaseSpec {
+                description: description.to_string(),
+                wait_for: Some(WaitFor {
+                    phases: wait_for.iter().map(|s| s.to_string()).collect(),
+                    timeout: None,
+                }),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_changed_phases_and_dependents_are_applied() {
+        let completed = ids(&["inc-network", "inc-database", "inc-app", "inc-docs"]);
+        let applied = vec![
+            create_test_phase("inc-network", &[], "network"),
+            create_test_phase("inc-database", &["inc-network"], "database"),
+            create_test_phase("inc-app", &["inc-database"], "app"),
+            create_test_phase("inc-docs", &[], "docs"),
+        ];
+        let mut incoming = applied.clone();
+        incoming[1].spec.description = "database v2".to_string();
+
+        let diff = diff_plans(&applied, &incoming);
+        assert_eq!(unchanged_phases(&diff, &incoming, &completed), vec!["inc-network", "inc-docs"]);
+    }
+
+    #[test]
+    fn test_unchanged_phase_that_never_completed_is_applied() {
+        let completed = ids(&["inc-ok", "inc-after"]);
+        let applied = vec![
+            create_test_phase("inc-ok", &[], "ok"),
+            create_test_phase("inc-failed-before", &[], "failed"),
+            create_test_phase("inc-after", &["inc-failed-before"], "after"),
+        ];
+
+        let diff = diff_plans(&applied, &applied);
+        assert!(diff.is_empty());
+        assert_eq!(unchanged_phases(&diff, &applied, &completed), vec!["inc-ok"]);
+    }
+
+    #[test]
+    fn test_phase_completed_only_in_an_older_run_is_applied() {
+        crate::executor::dependencies::mark_completed("inc-older");
+        let applied = vec![create_test_phase("inc-older", &[], "older")];
+
+        let diff = diff_plans(&applied, &applied);
+        assert!(unchanged_phases(&diff, &applied, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_deleted_phases() {
+        let applied = vec![
+            create_test_phase("inc-keep", &[], "keep"),
+            create_test_phase("inc-removed", &[], "removed"),
+        ];
+        let incoming = vec![applied[0].clone()];
+
+        let diff = diff_plans(&applied, &incoming);
+        let deleted: Vec<&str> = deleted_phases(&diff).iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(deleted, vec!["inc-removed"]);
+    }
+}
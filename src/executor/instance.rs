@@ -0,0 +1,65 @@
+use crate::executor::dependencies::dependencies;
+use crate::model::Phase;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// When a phase of a plan is executed (`spec.instance_mode`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InstanceMode {
+    /// Runs as part of the plan
+    #[default]
+    Immediate,
+    /// Runs only when a phase that runs waits for it, or when requested
+    OnUse,
+}
+
+impl InstanceMode {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "immediate" => Ok(InstanceMode::Immediate),
+            "onUse" => Ok(InstanceMode::OnUse),
+            other => Err(format!("unknown instance mode '{}' (expected immediate or onUse)", other)),
+        }
+    }
+
+    /// Mode of `phase`; `immediate` unless it declares otherwise
+    pub fn of(phase: &Phase) -> Self {
+        phase
+            .spec
+            .instance_mode
+            .as_deref()
+            .and_then(|m| Self::parse(m).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// `onUse` phases of a plan that nothing needs: neither requested nor
+/// waited for, directly or through other phases, by a phase that runs
+pub fn unused_phases(phases: &[Phase], requested: &[String]) -> Vec<String> {
+    let by_id: HashMap<&str, &Phase> = phases.iter().map(|p| (p.id.as_str(), p)).collect();
+    let mut used: HashSet<&str> = HashSet::new();
+    let mut stack: Vec<&str> = phases
+        .iter()
+        .filter(|p| InstanceMode::of(p) == InstanceMode::Immediate || requested.contains(&p.id))
+        .map(|p| p.id.as_str())
+        .collect();
+    while let Some(id) = stack.pop() {
+        if !used.insert(id) {
+            continue;
+        }
+        if let Some(phase) = by_id.get(id) {
+            stack.extend(dependencies(phase).iter().map(String::as_str));
+        }
+    }
+
+    phases
+        .iter()
+        .filter(|p| !used.contains(p.id.as_str()))
+        .map(|p| p.id.clone())
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use crate::executor::instance::{unused_phases, InstanceMode};
+    use crate::model::*;
+
+    fn create_test_phase(id: &str, wait_for: &[&str], mode: Option<&str>) -> Phase {
+        Phase {
+            kind: "Phase".to_string(),
+            id: id.to_string(),
+            spec: PhaseSpec {
+                description: format!("Test phase {}", id),
+                instance_mode: mode.map(|m| m.to_string()),
+                wait_for: Some(WaitFor {
+                    phases: wait_for.iter().map(|s| s.to_string()).collect(),
+                    timeout: None,
+                }),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_instance_mode() {
+        assert_eq!(InstanceMode::parse("immediate"), Ok(InstanceMode::Immediate));
+        assert_eq!(InstanceMode::parse("onUse"), Ok(InstanceMode::OnUse));
+        assert!(InstanceMode::parse("parallel").is_err());
+        assert_eq!(InstanceMode::of(&create_test_phase("a", &[], None)), InstanceMode::Immediate);
+        assert_eq!(serde_json::to_value(InstanceMode::OnUse).unwrap(), "onUse");
+    }
+
+    #[test]
+    fn test_on_use_phases_run_only_when_needed() {
+        let phases = vec![
+            create_test_phase("cache", &[], Some("onUse")),
+            create_test_phase("warm-cache", &["cache"], Some("onUse")),
+            create_test_phase("db", &[], Some("onUse")),
+            create_test_phase("app", &["db"], Some("immediate")),
+            create_test_phase("debug-shell", &[], Some("onUse")),
+        ];
+
+        assert_eq!(unused_phases(&phases, &[]), vec!["cache", "warm-cache", "debug-shell"]);
+        assert_eq!(unused_phases(&phases, &["warm-cache".to_string()]), vec!["debug-shell"]);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::executor::template;
+use crate::executor::validate::ValidationError;
+use crate::model::Phase;
+use std::collections::{BTreeMap, HashMap};
+
+/// Expand every phase with a `spec.matrix` into one instance per
+/// combination of its values.
+///
+/// An instance's id is the phase id followed by its values in brackets
+/// (`deploy[eu-west]`, or `deploy[blue,eu-west]` for several parameters,
+/// ordered by parameter name). `${matrix.<name>}` is substituted throughout
+/// the instance's spec. A `wait_for` entry naming a matrix phase is replaced
+/// by all of its instances, so dependents wait for every one of them.
+/// Phases without a matrix are returned unchanged, which makes expanding an
+/// already expanded plan a no-op.
+pub fn expand(phases: &[Phase]) -> Result<Vec<Phase>, ValidationError> {
+    let mut instances: HashMap<&str, Vec<String>> = HashMap::new();
+    let mut expanded = Vec::with_capacity(phases.len());
+    for phase in phases {
+        match &phase.spec.matrix {
+            Some(matrix) => {
+                let combinations = combinations(matrix).map_err(|message| ValidationError::InvalidField {
+                    phase: phase.id.clone(),
+                    field: "matrix".to_string(),
+                    message,
+                })?;
+                let ids = instances.entry(phase.id.as_str()).or_default();
+                for values in combinations {
+                    let instance = instance(phase, &values);
+                    ids.push(instance.id.clone());
+                    expanded.push(instance);
+                }
+            }
+            None => expanded.push(phase.clone()),
+        }
+    }
+
+    if !instances.is_empty() {
+        for phase in &mut expanded {
+            if let Some(wait_for) = &mut phase.spec.wait_for {
+                wait_for.phases = wait_for
+                    .phases
+                    .iter()
+                    .flat_map(|id| instances.get(id.as_str()).cloned().unwrap_or_else(|| vec![id.clone()]))
+                    .collect();
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+/// Every combination of the matrix's values, keyed by parameter name
+fn combinations(matrix: &BTreeMap<String, Vec<String>>) -> Result<Vec<BTreeMap<&str, &str>>, String> {
+    if matrix.is_empty() {
+        return Err("must name at least one parameter".to_string());
+    }
+    let mut combinations = vec![BTreeMap::new()];
+    for (name, values) in matrix {
+        if values.is_empty() {
+            return Err(format!("parameter '{}' has no values", name));
+        }
+        for (i, value) in values.iter().enumerate() {
+            if value.is_empty() || value.contains(['[', ']', ',']) {
+                return Err(format!("parameter '{}' has invalid value '{}'", name, value));
+            }
+            if values[..i].contains(value) {
+                return Err(format!("parameter '{}' repeats value '{}'", name, value));
+            }
+        }
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination| {
+                values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.insert(name.as_str(), value.as_str());
+                    combination
+                })
+            })
+            .collect();
+    }
+    Ok(combinations)
+}
+
+fn instance(phase: &Phase, values: &BTreeMap<&str, &str>) -> Phase {
+    let vars: HashMap<String, String> = values
+        .iter()
+        .map(|(name, value)| (format!("matrix.{}", name), value.to_string()))
+        .collect();
+    let mut spec = phase.spec.clone();
+    spec.matrix = None;
+    let spec = serde_json::to_value(&spec)
+        .ok()
+        .and_then(|value| serde_json::from_value(template::render_json(&value, &vars)).ok())
+        .unwrap_or(spec);
+    let values: Vec<&str> = values.values().copied().collect();
+    Phase {
+        kind: phase.kind.clone(),
+        id: format!("{}[{}]", phase.id, values.join(",")),
+        spec,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
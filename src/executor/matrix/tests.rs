@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use crate::executor::matrix::expand;
+    use crate::model::*;
+    use std::collections::{BTreeMap, HashMap};
+
+    fn create_test_phase(id: &str, wait_for: &[&str], matrix: &[(&str, &[&str])]) -> Phase {
+        Phase {
+            kind: "Phase".to_string(),
+            id: id.to_string(),
+            spec: PhaseSpec {
+                description: "deploy to ${matrix.region}".to_string(),
+                command: Some(CommandSpec {
+                    program: "deploy".to_string(),
+                    args: vec!["--region=${matrix.region}".to_string(), "${phase.id}".to_string()],
+                    env: HashMap::new(),
+                    working_dir: None,
+                    stdin: None,
+                }),
+                wait_for: Some(WaitFor {
+                    phases: wait_for.iter().map(|s| s.to_string()).collect(),
+                    timeout: None,
+                }),
+                matrix: (!matrix.is_empty()).then(|| {
+                    matrix
+                        .iter()
+                        .map(|(name, values)| (name.to_string(), values.iter().map(|v| v.to_string()).collect()))
+                        .collect()
+                }),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn ids(phases: &[Phase]) -> Vec<&str> {
+        phases.iter().map(|p| p.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_expand_instances_and_dependents() {
+        let phases = vec![
+            create_test_phase("build", &[], &[]),
+            create_test_phase("deploy", &["build"], &[("region", &["eu-west", "us-east"])]),
+            create_test_phase("verify", &["deploy", "build"], &[]),
+        ];
+        let expanded = expand(&phases).unwrap();
+
+        assert_eq!(ids(&expanded), vec!["build", "deploy[eu-west]", "deploy[us-east]", "verify"]);
+        let eu = &expanded[1];
+        assert_eq!(eu.spec.matrix, None);
+        assert_eq!(eu.spec.description, "deploy to eu-west");
+        assert_eq!(eu.spec.command.as_ref().unwrap().args, vec!["--region=eu-west", "${phase.id}"]);
+        assert_eq!(eu.spec.wait_for.as_ref().unwrap().phases, vec!["build"]);
+        assert_eq!(
+            expanded[3].spec.wait_for.as_ref().unwrap().phases,
+            vec!["deploy[eu-west]", "deploy[us-east]", "build"]
+        );
+        assert_eq!(expand(&expanded).unwrap(), expanded);
+    }
+
+    #[test]
+    fn test_expand_combines_parameters() {
+        let phases = vec![create_test_phase(
+            "shard",
+            &[],
+            &[("slot", &["blue", "green"]), ("region", &["eu", "us"])],
+        )];
+        assert_eq!(
+            ids(&expand(&phases).unwrap()),
+            vec!["shard[eu,blue]", "shard[eu,green]", "shard[us,blue]", "shard[us,green]"]
+        );
+    }
+
+    #[test]
+    fn test_expand_rejects_invalid_matrix() {
+        for matrix in [
+            vec![("region", &[][..])],
+            vec![("region", &["eu", "eu"][..])],
+            vec![("region", &["eu[1]"][..])],
+            vec![("region", &[""][..])],
+        ] {
+            let phases = vec![create_test_phase("deploy", &[], &matrix)];
+            assert!(expand(&phases).is_err(), "{:?} should be rejected", matrix);
+        }
+
+        let mut phase = create_test_phase("deploy", &[], &[]);
+        phase.spec.matrix = Some(BTreeMap::new());
+        assert!(expand(&[phase]).is_err());
+    }
+}
@@ -1,17 +1,620 @@
+pub mod control;
+pub mod approval;
+pub mod attempt;
+pub mod condition;
+pub mod dependencies;
 pub mod driver;
 pub mod runner;
 pub mod hooks;
+pub mod incremental;
+pub mod instance;
+pub mod matrix;
+pub mod outputs;
+pub mod retry;
+pub mod rollback;
+pub mod scheduler;
+pub mod secrets;
+pub mod template;
+pub mod validate;
 
+use crate::log::{Event, LoggingService};
+use control::{ControlState, RunControl};
 use crate::model::Phase;
-use crate::state::tracker::store_applied_plan;
+use retry::{Clock, TokioClock};
+use crate::diff::diff_plans;
+use crate::state::tracker::{
+    load_applied_plan, load_completed_phases, load_phase_outputs, store_applied_plan, store_completed_phases,
+    store_phase_outputs,
+};
 use crate::state::redis::RedisClient;
+use outputs::PhaseOutputs;
+use runner::PhaseError;
+use hooks::HandlerAction;
+use scheduler::{PhaseReport, PhaseStatus, PlanReport, PlanStatus, Scheduler};
+use validate::{validate_plan, validate_requested, ValidationError};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{watch, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
 
-pub async fn execute_plan(client: &RedisClient, phases: &[Phase]) {
-    for phase in phases {
-        if let Err(e) = runner::run_phase(client, phase).await {
-            eprintln!("Phase {} failed: {}", phase.id, e);
+const DEFAULT_GLOBAL_CONCURRENCY: usize = 16;
+
+lazy_static::lazy_static! {
+    /// Process-wide cap on phases running at once, shared by all plans
+    static ref GLOBAL_SLOTS: Arc<Semaphore> = Arc::new(Semaphore::new(global_concurrency()));
+}
+
+/// Global concurrency limit from `PLANTER_MAX_CONCURRENCY` (default 16)
+pub fn global_concurrency() -> usize {
+    std::env::var("PLANTER_MAX_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_GLOBAL_CONCURRENCY)
+}
+
+/// Receives progress while a plan runs, e.g. to back a status API
+pub trait ExecutionObserver: Send + Sync {
+    /// Current status of every phase; called whenever any of them changes
+    fn phases_updated(&self, _phases: &[PhaseReport]) {}
+
+    /// Result of a single driver attempt
+    fn attempt_finished(&self, _record: &attempt::AttemptRecord) {}
+}
+
+/// Per-plan execution settings
+#[derive(Clone)]
+pub struct ExecutionOptions {
+    /// Maximum number of phases from this plan running at once.
+    /// `1` executes phases sequentially.
+    pub max_concurrency: usize,
+    /// Plan identifier passed through to drivers and logs
+    pub plan_id: Option<String>,
+    /// Where attempt results are recorded
+    pub logging_service: Option<LoggingService>,
+    /// Timer used for the delays between retries
+    pub clock: Arc<dyn Clock>,
+    /// Describe each phase instead of executing it; nothing is recorded
+    pub dry_run: bool,
+    /// Notified of phase and attempt progress
+    pub observer: Option<Arc<dyn ExecutionObserver>>,
+    /// Lets an operator pause, resume or cancel the plan while it runs
+    pub control: Option<RunControl>,
+    /// Phases of this plan that already succeeded, e.g. before a restart;
+    /// they are not run again
+    pub completed_phases: Vec<String>,
+    /// Phases an incremental apply carries over from the applied plan;
+    /// they are reported `unchanged` without running
+    pub unchanged_phases: Vec<String>,
+    /// Outputs published by `completed_phases` and `unchanged_phases` when
+    /// they ran, keyed by phase id
+    pub outputs: HashMap<String, PhaseOutputs>,
+    /// `onUse` phases to run even if no phase waits for them
+    pub requested: Vec<String>,
+    /// Plan parameters `when` conditions can refer to
+    pub params: HashMap<String, String>,
+    /// Only run phases that changed since the applied plan (and their
+    /// dependents), and tear down phases removed from it
+    pub incremental: bool,
+}
+
+impl Default for ExecutionOptions {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 1,
+            plan_id: None,
+            logging_service: None,
+            clock: Arc::new(TokioClock),
+            dry_run: false,
+            observer: None,
+            control: None,
+            completed_phases: Vec::new(),
+            unchanged_phases: Vec::new(),
+            outputs: HashMap::new(),
+            requested: Vec::new(),
+            params: HashMap::new(),
+            incremental: false,
+        }
+    }
+}
+
+impl ExecutionOptions {
+    pub fn parallel(max_concurrency: usize) -> Self {
+        Self {
+            max_concurrency: max_concurrency.max(1),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_plan_id(mut self, plan_id: impl Into<String>) -> Self {
+        self.plan_id = Some(plan_id.into());
+        self
+    }
+
+    pub fn with_logging(mut self, logging_service: LoggingService) -> Self {
+        self.logging_service = Some(logging_service);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_observer(mut self, observer: Arc<dyn ExecutionObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    pub fn with_control(mut self, control: RunControl) -> Self {
+        self.control = Some(control);
+        self
+    }
+
+    pub fn with_completed_phases(mut self, ids: Vec<String>) -> Self {
+        self.completed_phases = ids;
+        self
+    }
+
+    pub fn with_outputs(mut self, outputs: HashMap<String, PhaseOutputs>) -> Self {
+        self.outputs = outputs;
+        self
+    }
+
+    pub fn with_requested(mut self, ids: Vec<String>) -> Self {
+        self.requested = ids;
+        self
+    }
+
+    pub fn with_params(mut self, params: HashMap<String, String>) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn with_incremental(mut self, incremental: bool) -> Self {
+        self.incremental = incremental;
+        self
+    }
+
+    fn notify_phases(&self, scheduler: &Scheduler) {
+        if let Some(observer) = &self.observer {
+            observer.phases_updated(&scheduler.report().phases);
+        }
+    }
+}
+
+/// Execute a plan and record it as applied. Phases completed by earlier
+/// runs satisfy dependencies on them. The phases this apply leaves
+/// completed replace that record, and the outputs they published are
+/// stored. A dry run records nothing.
+///
+/// An incremental apply diffs the plan against the applied one: phases
+/// that are unchanged and completed in the last applied run are not run
+/// again (see [`incremental::unchanged_phases`]), are reported `unchanged`
+/// and provide the outputs they last published, and once the plan has
+/// succeeded, deleted phases are torn down through their `onRollback`
+/// steps.
+pub async fn execute_plan(
+    client: &RedisClient,
+    phases: &[Phase],
+    options: &ExecutionOptions,
+) -> Result<PlanReport, ValidationError> {
+    let phases = &matrix::expand(phases)?;
+    restore_completed_phases(client).await;
+    if !options.incremental {
+        return finish_plan(client, phases, run_plan(phases, options).await?, options).await;
+    }
+
+    let applied = load_applied_plan(client).await.unwrap_or_default();
+    let applied = matrix::expand(&applied).unwrap_or(applied);
+    let diff = diff_plans(&applied, phases);
+    let mut options = options.clone();
+    let completed = load_completed_phases(client).await;
+    let unchanged = incremental::unchanged_phases(&diff, phases, &completed);
+    let mut published = load_phase_outputs(client).await;
+    for id in &unchanged {
+        if let Some(outputs) = published.remove(id) {
+            options.outputs.entry(id.clone()).or_insert(outputs);
         }
     }
+    options.unchanged_phases = unchanged;
+    let mut report = run_plan(phases, &options).await?;
+    if report.succeeded() {
+        rollback::tear_down(&applied, &incremental::deleted_phases(&diff), &mut report, &options).await;
+    }
+    finish_plan(client, phases, report, &options).await
+}
 
+/// Record an executed plan as applied, unless it was a dry run
+async fn finish_plan(
+    client: &RedisClient,
+    phases: &[Phase],
+    report: PlanReport,
+    options: &ExecutionOptions,
+) -> Result<PlanReport, ValidationError> {
+    if options.dry_run {
+        return Ok(report);
+    }
     store_applied_plan(client, phases).await;
+    let ids: Vec<String> = report
+        .phases
+        .iter()
+        .filter(|p| matches!(p.status, PhaseStatus::Succeeded | PhaseStatus::Unchanged))
+        .map(|p| p.id.clone())
+        .collect();
+    let outputs: HashMap<String, PhaseOutputs> = report
+        .phases
+        .iter()
+        .filter(|p| p.status == PhaseStatus::Succeeded)
+        .map(|p| (p.id.clone(), p.outputs.clone()))
+        .collect();
+    store_completed_phases(client, &ids).await;
+    store_phase_outputs(client, &outputs).await;
+    Ok(report)
 }
+
+/// Load phases completed by earlier runs into the dependency registry
+pub async fn restore_completed_phases(client: &RedisClient) {
+    for id in load_completed_phases(client).await {
+        dependencies::mark_completed(&id);
+    }
+}
+
+/// Run a plan in dependency order. The plan is validated before any phase
+/// runs; ready phases are dispatched onto tokio tasks up to the plan's
+/// `max_concurrency` and the global limit. A phase whose `wait_for.timeout`
+/// passes before its dependencies complete fails with a dependency timeout.
+/// What happens after a phase finishes is decided by its `onSuccess` /
+/// `onFailure` action (see [`hooks::HandlerAction`]); by default dependents
+/// of a failed phase are skipped. When a `raise` or `abort` stops the plan,
+/// succeeded phases are compensated through their `onRollback` steps.
+///
+/// Phases run according to their `instance_mode`: `onUse` phases that are
+/// neither in `options.requested` nor needed by a phase that runs are
+/// reported `unused` (see [`instance::unused_phases`]).
+///
+/// Phases with a `matrix` are first expanded into their instances (see
+/// [`matrix::expand`]), each of which is scheduled, retried and logged on
+/// its own.
+///
+/// A phase whose `when` condition is false when it becomes ready is
+/// skipped without running; its dependents proceed as if it had succeeded.
+///
+/// Just before a phase starts, its `${phases.<id>.outputs.<name>}`
+/// references are replaced with the outputs published by finished phases
+/// (see [`outputs`]); a phase referencing an output that was never
+/// published fails without running.
+///
+/// Approval gates are reported `awaiting_approval` until they are decided
+/// through `options.control` (see [`approval::wait`]); they do not count
+/// against the concurrency limits, and are abandoned once the plan stops.
+///
+/// `options.control` is consulted between phases: while paused no further
+/// phase starts, and a cancel interrupts the running phases and skips the
+/// rest without rolling anything back.
+///
+/// With `options.dry_run` the same ordering and actions are followed, but
+/// every phase is described rather than executed and assumed to succeed;
+/// the report's `trace` lists what would have run.
+pub async fn run_plan(phases: &[Phase], options: &ExecutionOptions) -> Result<PlanReport, ValidationError> {
+    let phases = &matrix::expand(phases)?;
+    validate_plan(phases)?;
+    validate_requested(phases, &options.requested)?;
+    let mut scheduler = Scheduler::new(phases)?;
+    let max_concurrency = options.max_concurrency.max(1);
+    let started = Instant::now();
+    let mut running = JoinSet::new();
+    let mut in_flight: HashMap<tokio::task::Id, Phase> = HashMap::new();
+    // Approval gates waiting for a decision; they take no concurrency slot
+    let mut gates: HashMap<tokio::task::Id, tokio::task::AbortHandle> = HashMap::new();
+    let plan_ids: HashSet<&str> = phases.iter().map(|p| p.id.as_str()).collect();
+    let mut trace = Vec::new();
+    let mut control = options.control.as_ref().map(RunControl::subscribe);
+
+    for phase in phases.iter().filter(|p| options.unchanged_phases.contains(&p.id)) {
+        println!("Phase {} is unchanged", phase.id);
+        if let Some(outputs) = options.outputs.get(&phase.id) {
+            scheduler.set_outputs(&phase.id, outputs.clone());
+        }
+        scheduler.finish(&phase.id, PhaseStatus::Unchanged, true);
+    }
+    for phase in phases.iter().filter(|p| options.completed_phases.contains(&p.id)) {
+        println!("Phase {} already completed", phase.id);
+        if let Some(outputs) = options.outputs.get(&phase.id) {
+            scheduler.set_outputs(&phase.id, outputs.clone());
+        }
+        settle(&mut scheduler, &mut running, phase, PhaseStatus::Succeeded, options);
+    }
+    for id in instance::unused_phases(phases, &options.requested) {
+        if scheduler.status(&id) == Some(PhaseStatus::Pending) {
+            println!("Phase {} is onUse and not needed", id);
+            scheduler.mark_unused(&id);
+        }
+    }
+
+    loop {
+        let state = control
+            .as_mut()
+            .map_or(ControlState::Running, |c| *c.borrow_and_update());
+        if state == ControlState::Cancelled && !scheduler.is_halted() {
+            println!("Cancelling plan");
+            running.abort_all();
+            for stopped in scheduler.halt(PlanStatus::Cancelled) {
+                eprintln!("Not starting phase {}: plan cancelled", stopped);
+            }
+        }
+        if scheduler.is_halted() {
+            // Nothing would run after a gate of a stopped plan
+            for (_, gate) in gates.drain() {
+                gate.abort();
+            }
+        }
+        let paused = state == ControlState::Paused;
+
+        while !paused && running.len() - gates.len() < max_concurrency {
+            let Some(phase) = scheduler.next_ready() else {
+                break;
+            };
+            if let Some(source) = &phase.spec.when {
+                let ctx = condition::Context {
+                    params: &options.params,
+                    labels: &phase.spec.selector.match_labels,
+                    phases: &scheduler.report().phases,
+                };
+                match condition::evaluate(source, &ctx) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        skip_phase(&mut scheduler, &mut trace, &phase, source, options).await;
+                        continue;
+                    }
+                    Err(e) => {
+                        eprintln!("Phase {} failed: invalid when condition: {}", phase.id, e);
+                        hooks::handle_failure(&phase).await;
+                        settle(&mut scheduler, &mut running, &phase, PhaseStatus::Failed, options);
+                        continue;
+                    }
+                }
+            }
+            let phase = outputs::resolve(&phase, scheduler.outputs());
+            let unresolved = outputs::references(&phase);
+            if !unresolved.is_empty() && !options.dry_run {
+                let names: Vec<String> = unresolved
+                    .iter()
+                    .map(|(id, name)| outputs::reference(id, name))
+                    .collect();
+                eprintln!("Phase {} failed: unresolved output references {}", phase.id, names.join(", "));
+                hooks::handle_failure(&phase).await;
+                settle(&mut scheduler, &mut running, &phase, PhaseStatus::Failed, options);
+                continue;
+            }
+            let gate = approval::is_gate(&phase) && !options.dry_run;
+            let task_phase = phase.clone();
+            let options = options.clone();
+            let wait_deadline = wait_deadline(&phase, started);
+            let external: Vec<String> = dependencies::dependencies(&phase)
+                .iter()
+                .filter(|dep| !plan_ids.contains(dep.as_str()))
+                .cloned()
+                .collect();
+            if options.dry_run {
+                trace.push(runner::describe_phase(&phase, &options, &external));
+            }
+            let handle = running.spawn(async move {
+                if options.dry_run {
+                    return Ok(PhaseOutputs::new());
+                }
+                // Phases waiting on external dependencies do not hold a
+                // global slot, so they cannot starve phases ready to run
+                runner::wait_for_external(&task_phase, &external, wait_deadline).await?;
+                // Waiting for a global slot happens in the task so the loop
+                // keeps handling control changes and finished phases
+                let _permit = if gate {
+                    None
+                } else {
+                    Some(
+                        GLOBAL_SLOTS
+                            .clone()
+                            .acquire_owned()
+                            .await
+                            .expect("global concurrency semaphore closed"),
+                    )
+                };
+                runner::execute_phase(&task_phase, &options).await
+            });
+            let task_id = handle.id();
+            if gate {
+                scheduler.await_approval(&phase.id);
+                gates.insert(task_id, handle);
+            }
+            in_flight.insert(task_id, phase);
+        }
+        options.notify_phases(&scheduler);
+
+        let next_deadline = scheduler.pending().filter_map(|p| wait_deadline(p, started)).min();
+        // A resume or cancel can only change anything while phases are
+        // running or waiting for the plan to be resumed
+        let watch_control = control.is_some() && !scheduler.is_halted() && (paused || !running.is_empty());
+        let joined = tokio::select! {
+            joined = running.join_next_with_id(), if !running.is_empty() => joined,
+            _ = tokio::time::sleep_until(next_deadline.unwrap_or_else(Instant::now)), if next_deadline.is_some() => {
+                expire_dependency_waits(&mut scheduler, &mut running, started, options).await;
+                options.notify_phases(&scheduler);
+                continue;
+            }
+            _ = control_changed(&mut control), if watch_control => continue,
+            else => break,
+        };
+        let Some(joined) = joined else {
+            continue;
+        };
+        let (task_id, result) = match joined {
+            Ok((task_id, result)) => (task_id, result),
+            Err(e) if e.is_cancelled() => {
+                gates.remove(&e.id());
+                if let Some(phase) = in_flight.remove(&e.id()) {
+                    eprintln!("Phase {} cancelled", phase.id);
+                    scheduler.finish(&phase.id, PhaseStatus::Cancelled, false);
+                }
+                options.notify_phases(&scheduler);
+                continue;
+            }
+            Err(e) => (e.id(), Err(PhaseError::Failed(format!("phase task aborted: {}", e)))),
+        };
+        gates.remove(&task_id);
+        let Some(phase) = in_flight.remove(&task_id) else {
+            continue;
+        };
+
+        let status = match result {
+            Ok(outputs) => {
+                scheduler.set_outputs(&phase.id, outputs);
+                PhaseStatus::Succeeded
+            }
+            Err(e) => {
+                eprintln!("Phase {} failed: {}", phase.id, e);
+                match e {
+                    PhaseError::DependencyTimeout { .. } => PhaseStatus::DependencyTimeout,
+                    PhaseError::Failed(_) => PhaseStatus::Failed,
+                }
+            }
+        };
+        settle(&mut scheduler, &mut running, &phase, status, options);
+    }
+
+    let mut report = scheduler.report();
+    report.trace = trace;
+    if scheduler.is_halted() && !matches!(report.status, PlanStatus::Succeeded | PlanStatus::Cancelled) {
+        rollback::roll_back(phases, &mut report, options).await;
+    }
+    if let Some(observer) = &options.observer {
+        observer.phases_updated(&report.phases);
+    }
+    Ok(report)
+}
+
+/// Record a finished phase and carry out its `onSuccess` / `onFailure` action
+fn settle<T: 'static>(
+    scheduler: &mut Scheduler,
+    running: &mut JoinSet<T>,
+    phase: &Phase,
+    status: PhaseStatus,
+    options: &ExecutionOptions,
+) {
+    let action = if status == PhaseStatus::Succeeded {
+        if !options.dry_run {
+            dependencies::mark_completed(&phase.id);
+        }
+        hooks::success_action(phase)
+    } else {
+        hooks::failure_action(phase)
+    };
+
+    let (proceed, halt) = match action {
+        HandlerAction::Continue => (true, None),
+        HandlerAction::Skip => (false, None),
+        HandlerAction::Raise => (false, Some(PlanStatus::Failed)),
+        HandlerAction::Abort => (false, Some(PlanStatus::Aborted)),
+    };
+
+    for skipped in scheduler.finish(&phase.id, status, proceed) {
+        eprintln!("Skipping phase {}: upstream phase {} finished as {:?}", skipped, phase.id, status);
+    }
+    if let Some(outcome) = halt {
+        if outcome == PlanStatus::Aborted {
+            running.abort_all();
+        }
+        for stopped in scheduler.halt(outcome) {
+            eprintln!("Not starting phase {}: plan stopped by phase {}", stopped, phase.id);
+        }
+    }
+}
+
+/// Skip a phase whose `when` condition is false, letting its dependents run
+async fn skip_phase(
+    scheduler: &mut Scheduler,
+    trace: &mut Vec<scheduler::TraceStep>,
+    phase: &Phase,
+    condition: &str,
+    options: &ExecutionOptions,
+) {
+    let reason = format!("when condition is false: {}", condition);
+    println!("Skipping phase {}: {}", phase.id, reason);
+    scheduler.finish(&phase.id, PhaseStatus::Skipped, true);
+    if options.dry_run {
+        trace.push(scheduler::TraceStep {
+            id: phase.id.clone(),
+            driver: driver::driver_name(phase).to_string(),
+            action: format!("skip: {}", reason),
+            waiting_for: Vec::new(),
+        });
+        return;
+    }
+    if let Some(logging_service) = &options.logging_service {
+        let _ = logging_service
+            .log_event_with_context(
+                Event::PhaseSkipped {
+                    id: phase.id.clone(),
+                    reason,
+                },
+                options.plan_id.clone(),
+                Some(phase.id.clone()),
+                HashMap::new(),
+            )
+            .await;
+    }
+}
+
+/// Resolves when the operator changes the plan's control state
+async fn control_changed(control: &mut Option<watch::Receiver<ControlState>>) {
+    if let Some(control) = control {
+        let _ = control.changed().await;
+    }
+}
+
+/// When a phase with dependencies must stop waiting for them
+fn wait_deadline(phase: &Phase, started: Instant) -> Option<Instant> {
+    if dependencies::dependencies(phase).is_empty() {
+        return None;
+    }
+    dependencies::wait_timeout(phase).map(|t| started + t)
+}
+
+/// Fail pending phases whose dependencies did not complete in time
+async fn expire_dependency_waits<T: 'static>(
+    scheduler: &mut Scheduler,
+    running: &mut JoinSet<T>,
+    started: Instant,
+    options: &ExecutionOptions,
+) {
+    let now = Instant::now();
+    let expired: Vec<Phase> = scheduler
+        .pending()
+        .filter(|p| wait_deadline(p, started).is_some_and(|d| d <= now))
+        .cloned()
+        .collect();
+
+    for phase in expired {
+        // An earlier phase in this batch may have stopped the plan
+        if scheduler.status(&phase.id) != Some(PhaseStatus::Pending) {
+            continue;
+        }
+        let waiting_for: Vec<String> = dependencies::dependencies(&phase)
+            .iter()
+            .filter(|dep| !dependencies::is_completed(dep))
+            .cloned()
+            .collect();
+        eprintln!("Phase {} failed: {}", phase.id, PhaseError::DependencyTimeout { waiting_for });
+        hooks::handle_failure(&phase).await;
+        settle(scheduler, running, &phase, PhaseStatus::DependencyTimeout, options);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -0,0 +1,131 @@
+use crate::executor::driver::PhaseOutcome;
+use crate::executor::template::{placeholders, render_json};
+use crate::model::Phase;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Named values published by one phase
+pub type PhaseOutputs = HashMap<String, String>;
+
+/// Outputs announced on stdout: every line holding a JSON object with an
+/// `outputs` object, e.g. `{"outputs": {"cluster_id": "c-42"}}`. Later
+/// lines override earlier ones.
+pub fn parse_stdout(stdout: &str) -> PhaseOutputs {
+    let mut outputs = PhaseOutputs::new();
+    for line in stdout.lines().map(str::trim).filter(|l| l.starts_with('{')) {
+        if let Ok(Value::Object(mut object)) = serde_json::from_str(line) {
+            if let Some(Value::Object(values)) = object.remove("outputs") {
+                outputs.extend(from_object(values));
+            }
+        }
+    }
+    outputs
+}
+
+/// Outputs of a successful attempt: those on its stdout, then those in the
+/// `spec.outputs.file` it wrote, which take precedence. A relative file is
+/// resolved against `command.working_dir` when one is set, like the
+/// command that wrote it.
+pub async fn collect(phase: &Phase, outcome: &PhaseOutcome) -> Result<PhaseOutputs, String> {
+    let mut outputs = parse_stdout(&outcome.stdout);
+    let Some(path) = phase.spec.outputs.as_ref().and_then(|o| o.file.as_deref()) else {
+        return Ok(outputs);
+    };
+    let file = match phase.spec.command.as_ref().and_then(|c| c.working_dir.as_deref()) {
+        Some(dir) => Path::new(dir).join(path),
+        None => PathBuf::from(path),
+    };
+
+    let data = tokio::fs::read_to_string(&file)
+        .await
+        .map_err(|e| format!("Failed to read outputs file '{}' of phase {}: {}", file.display(), phase.id, e))?;
+    match serde_json::from_str(&data) {
+        Ok(Value::Object(values)) => {
+            outputs.extend(from_object(values));
+            Ok(outputs)
+        }
+        _ => Err(format!("Outputs file '{}' of phase {} is not a JSON object", file.display(), phase.id)),
+    }
+}
+
+/// Output references in the body of `phase` (everything but its
+/// `onRollback` step), as `(phase id, output name)` pairs
+pub fn references(phase: &Phase) -> Vec<(String, String)> {
+    let mut spec = phase.spec.clone();
+    spec.on_rollback = None;
+    let mut refs = Vec::new();
+    if let Ok(value) = serde_json::to_value(&spec) {
+        collect_references(&value, &mut refs);
+    }
+    refs
+}
+
+/// `phase` with its references to outputs in `available` (keyed by phase
+/// id) substituted. References to unknown outputs are left in place.
+pub fn resolve(phase: &Phase, available: &HashMap<String, PhaseOutputs>) -> Phase {
+    if available.is_empty() {
+        return phase.clone();
+    }
+    let vars: HashMap<String, String> = available
+        .iter()
+        .flat_map(|(id, outputs)| {
+            outputs
+                .iter()
+                .map(move |(name, value)| (reference(id, name), value.clone()))
+        })
+        .collect();
+
+    let Ok(spec) = serde_json::to_value(&phase.spec) else {
+        return phase.clone();
+    };
+    match serde_json::from_value(render_json(&spec, &vars)) {
+        Ok(spec) => Phase {
+            spec,
+            ..phase.clone()
+        },
+        Err(_) => phase.clone(),
+    }
+}
+
+/// The placeholder name under which output `name` of phase `id` is referenced
+pub fn reference(id: &str, name: &str) -> String {
+    format!("phases.{}.outputs.{}", id, name)
+}
+
+fn collect_references(value: &Value, refs: &mut Vec<(String, String)>) {
+    match value {
+        Value::String(s) => {
+            for name in placeholders(s) {
+                let Some((id, output)) = name
+                    .strip_prefix("phases.")
+                    .and_then(|rest| rest.split_once(".outputs."))
+                else {
+                    continue;
+                };
+                let found = (id.to_string(), output.to_string());
+                if !id.is_empty() && !output.is_empty() && !refs.contains(&found) {
+                    refs.push(found);
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_references(v, refs)),
+        Value::Object(map) => map.values().for_each(|v| collect_references(v, refs)),
+        _ => {}
+    }
+}
+
+/// String values are kept as they are; anything else as its JSON text
+fn from_object(values: Map<String, Value>) -> impl Iterator<Item = (String, String)> {
+    values.into_iter().map(|(name, value)| {
+        let value = match value {
+            Value::String(s) => s,
+            other => other.to_string(),
+        };
+        (name, value)
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
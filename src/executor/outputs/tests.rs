@@ -0,0 +1,125 @@
+#[cfg(test)]
+mod tests {
+    use crate::executor::driver::PhaseOutcome;
+    use crate::executor::outputs::{collect, parse_stdout, references, resolve, PhaseOutputs};
+    use crate::model::*;
+    use std::collections::HashMap;
+
+    fn command_phase(id: &str, args: &[&str]) -> Phase {
+        Phase {
+            kind: "Phase".to_string(),
+            id: id.to_string(),
+            spec: PhaseSpec {
+                description: format!("phase {}", id),
+                command: Some(CommandSpec {
+                    program: "echo".to_string(),
+                    args: args.iter().map(|a| a.to_string()).collect(),
+                    env: HashMap::new(),
+                    working_dir: None,
+                    stdin: None,
+                }),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn outcome(stdout: &str) -> PhaseOutcome {
+        PhaseOutcome {
+            stdout: stdout.to_string(),
+            ..PhaseOutcome::success()
+        }
+    }
+
+    #[test]
+    fn test_parse_stdout_reads_output_lines() {
+        let stdout = "creating cluster\n\
+            {\"outputs\": {\"cluster_id\": \"c-1\", \"nodes\": 3}}\n\
+            {\"other\": true}\n\
+            {\"outputs\": {\"cluster_id\": \"c-42\"}}\n";
+        let outputs = parse_stdout(stdout);
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs["cluster_id"], "c-42");
+        assert_eq!(outputs["nodes"], "3");
+        assert!(parse_stdout("{not json\nplain").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_collect_prefers_outputs_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("outputs.json");
+        std::fs::write(&path, r#"{"cluster_id": "from-file", "region": "eu-west"}"#).unwrap();
+        let mut phase = command_phase("outputs-file", &[]);
+        phase.spec.outputs = Some(OutputSpec {
+            file: Some(path.to_string_lossy().into_owned()),
+        });
+
+        let outputs = collect(&phase, &outcome(r#"{"outputs": {"cluster_id": "from-stdout", "zone": "a"}}"#))
+            .await
+            .unwrap();
+        assert_eq!(outputs["cluster_id"], "from-file");
+        assert_eq!(outputs["region"], "eu-west");
+        assert_eq!(outputs["zone"], "a");
+
+        std::fs::write(&path, "[1, 2]").unwrap();
+        assert!(collect(&phase, &outcome("")).await.is_err());
+        phase.spec.outputs = Some(OutputSpec {
+            file: Some(tmp.path().join("missing.json").to_string_lossy().into_owned()),
+        });
+        assert!(collect(&phase, &outcome("")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_collect_resolves_relative_file_against_working_dir() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("outputs.json"), r#"{"cluster_id": "in-working-dir"}"#).unwrap();
+        let mut phase = command_phase("outputs-relative", &[]);
+        phase.spec.command.as_mut().unwrap().working_dir = Some(tmp.path().to_string_lossy().into_owned());
+        phase.spec.outputs = Some(OutputSpec {
+            file: Some("outputs.json".to_string()),
+        });
+
+        let outputs = collect(&phase, &outcome("")).await.unwrap();
+        assert_eq!(outputs["cluster_id"], "in-working-dir");
+    }
+
+    #[test]
+    fn test_references_skip_rollback_step() {
+        let mut phase = command_phase(
+            "outputs-refs",
+            &["${phases.init.outputs.cluster_id}", "${ phases.init.outputs.cluster_id }", "${phase.id}"],
+        );
+        phase.spec.on_rollback = Some(Rollback {
+            driver: None,
+            command: phase.spec.command.clone().map(|mut c| {
+                c.args = vec!["${phases.outputs-refs.outputs.token}".to_string()];
+                c
+            }),
+            http: None,
+            timeout: None,
+        });
+        assert_eq!(
+            references(&phase),
+            vec![("init".to_string(), "cluster_id".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_resolve_substitutes_known_outputs() {
+        let phase = command_phase(
+            "outputs-resolve",
+            &["--cluster=${phases.init.outputs.cluster_id}", "${phases.init.outputs.missing}", "${attempt}"],
+        );
+        let mut available = HashMap::new();
+        available.insert(
+            "init".to_string(),
+            PhaseOutputs::from([("cluster_id".to_string(), "c-42".to_string())]),
+        );
+
+        let resolved = resolve(&phase, &available);
+        assert_eq!(
+            resolved.spec.command.unwrap().args,
+            vec!["--cluster=c-42", "${phases.init.outputs.missing}", "${attempt}"]
+        );
+        assert_eq!(resolve(&phase, &HashMap::new()), phase);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::executor::driver::{OutcomeStatus, PhaseOutcome};
+use crate::model::Retry;
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// A failure kind listed in `retry.retry_on`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryCondition {
+    /// The attempt hit its timeout
+    Timeout,
+    /// Any failure that was not a timeout
+    Failure,
+    /// The command exited with this status
+    ExitCode(i32),
+    /// The http request returned this status
+    HttpStatus(u16),
+}
+
+impl RetryCondition {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        if let Some(code) = value.strip_prefix("exit:") {
+            return code
+                .parse()
+                .map(RetryCondition::ExitCode)
+                .map_err(|_| format!("invalid exit code in '{}'", value));
+        }
+        if let Some(status) = value.strip_prefix("http:") {
+            return status
+                .parse()
+                .map(RetryCondition::HttpStatus)
+                .map_err(|_| format!("invalid http status in '{}'", value));
+        }
+        match value {
+            "timeout" => Ok(RetryCondition::Timeout),
+            "failure" => Ok(RetryCondition::Failure),
+            other => Err(format!("unknown retry_on condition '{}'", other)),
+        }
+    }
+
+    fn matches(&self, outcome: &PhaseOutcome) -> bool {
+        match self {
+            RetryCondition::Timeout => outcome.status == OutcomeStatus::TimedOut,
+            RetryCondition::Failure => outcome.status == OutcomeStatus::Failed,
+            RetryCondition::ExitCode(code) => outcome.exit_code == Some(*code),
+            RetryCondition::HttpStatus(status) => outcome.http_status == Some(*status),
+        }
+    }
+}
+
+/// Parsed form of a phase's `retry` block
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub multiplier: f64,
+    pub max_delay: Option<Duration>,
+    pub jitter: f64,
+    pub retry_on: Vec<RetryCondition>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_delay: Duration::ZERO,
+            multiplier: 1.0,
+            max_delay: None,
+            jitter: 0.0,
+            retry_on: Vec::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_spec(spec: Option<&Retry>) -> Result<Self, String> {
+        let Some(spec) = spec else {
+            return Ok(Self::default());
+        };
+
+        let multiplier = spec.backoff_multiplier.unwrap_or(1.0);
+        if !multiplier.is_finite() || multiplier < 1.0 {
+            return Err(format!("backoff_multiplier must be at least 1.0, got {}", multiplier));
+        }
+        let jitter = spec.jitter.unwrap_or(0.0);
+        if !(0.0..=1.0).contains(&jitter) {
+            return Err(format!("jitter must be between 0.0 and 1.0, got {}", jitter));
+        }
+
+        Ok(Self {
+            max_attempts: spec.max_attempts.unwrap_or(1).max(1),
+            initial_delay: parse_duration("initial_delay", spec.initial_delay.as_deref())?
+                .unwrap_or(Duration::ZERO),
+            multiplier,
+            max_delay: parse_duration("max_delay", spec.max_delay.as_deref())?,
+            jitter,
+            retry_on: spec
+                .retry_on
+                .iter()
+                .map(|c| RetryCondition::parse(c))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Whether a failed outcome may be retried. An empty `retry_on` retries
+    /// every failure.
+    pub fn should_retry(&self, outcome: &PhaseOutcome) -> bool {
+        !outcome.is_success()
+            && (self.retry_on.is_empty() || self.retry_on.iter().any(|c| c.matches(outcome)))
+    }
+
+    /// Delay before the attempt following `attempt` (1-based). `sample` is a
+    /// random value in `[-1.0, 1.0]` scaled by the jitter fraction.
+    pub fn delay_for(&self, attempt: u32, sample: f64) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let mut secs = self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent);
+        if let Some(max) = self.max_delay {
+            secs = secs.min(max.as_secs_f64());
+        }
+        secs *= 1.0 + self.jitter * sample.clamp(-1.0, 1.0);
+        Duration::try_from_secs_f64(secs.max(0.0)).unwrap_or(Duration::MAX)
+    }
+
+    /// `delay_for` with a random jitter sample
+    pub fn next_delay(&self, attempt: u32) -> Duration {
+        let sample = if self.jitter > 0.0 {
+            rand::random::<f64>() * 2.0 - 1.0
+        } else {
+            0.0
+        };
+        self.delay_for(attempt, sample)
+    }
+}
+
+fn parse_duration(field: &str, value: Option<&str>) -> Result<Option<Duration>, String> {
+    value
+        .map(|v| humantime::parse_duration(v).map_err(|e| format!("{}: {}", field, e)))
+        .transpose()
+}
+
+/// Source of the waits between retries, replaceable in tests
+#[async_trait]
+pub trait Clock: Send + Sync {
+    async fn sleep(&self, duration: Duration);
+}
+
+/// Sleeps on the tokio timer
+pub struct TokioClock;
+
+#[async_trait]
+impl Clock for TokioClock {
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
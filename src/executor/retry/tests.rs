@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod tests {
+    use crate::executor::driver::PhaseOutcome;
+    use crate::executor::retry::{RetryCondition, RetryPolicy};
+    use crate::model::Retry;
+    use std::time::Duration;
+
+    fn policy(retry: Retry) -> RetryPolicy {
+        RetryPolicy::from_spec(Some(&retry)).unwrap()
+    }
+
+    #[test]
+    fn test_default_policy_has_no_delay() {
+        let policy = RetryPolicy::from_spec(None).unwrap();
+        assert_eq!(policy.max_attempts, 1);
+        assert_eq!(policy.delay_for(1, 0.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_fixed_delay() {
+        let policy = policy(Retry {
+            max_attempts: Some(3),
+            initial_delay: Some("2s".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(policy.delay_for(1, 0.0), Duration::from_secs(2));
+        assert_eq!(policy.delay_for(4, 0.0), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_exponential_delay_is_capped() {
+        let policy = policy(Retry {
+            initial_delay: Some("100ms".to_string()),
+            backoff_multiplier: Some(3.0),
+            max_delay: Some("1s".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(policy.delay_for(1, 0.0), Duration::from_millis(100));
+        assert_eq!(policy.delay_for(2, 0.0), Duration::from_millis(300));
+        assert_eq!(policy.delay_for(3, 0.0), Duration::from_millis(900));
+        assert_eq!(policy.delay_for(4, 0.0), Duration::from_secs(1));
+        assert_eq!(policy.delay_for(1000, 0.0), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_jitter_scales_delay() {
+        let policy = policy(Retry {
+            initial_delay: Some("1s".to_string()),
+            jitter: Some(0.5),
+            ..Default::default()
+        });
+        assert_eq!(policy.delay_for(1, 1.0), Duration::from_millis(1500));
+        assert_eq!(policy.delay_for(1, -1.0), Duration::from_millis(500));
+        for _ in 0..20 {
+            let delay = policy.next_delay(1);
+            assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_millis(1500));
+        }
+    }
+
+    #[test]
+    fn test_retry_on_conditions() {
+        assert_eq!(RetryCondition::parse("timeout"), Ok(RetryCondition::Timeout));
+        assert_eq!(RetryCondition::parse("exit:75"), Ok(RetryCondition::ExitCode(75)));
+        assert_eq!(RetryCondition::parse("http:503"), Ok(RetryCondition::HttpStatus(503)));
+        assert!(RetryCondition::parse("http:abc").is_err());
+        assert!(RetryCondition::parse("later").is_err());
+
+        let policy = policy(Retry {
+            retry_on: vec!["timeout".to_string(), "http:503".to_string()],
+            ..Default::default()
+        });
+        assert!(policy.should_retry(&PhaseOutcome::timed_out("slow")));
+        assert!(policy.should_retry(&PhaseOutcome {
+            http_status: Some(503),
+            ..PhaseOutcome::failure("unavailable")
+        }));
+        assert!(!policy.should_retry(&PhaseOutcome {
+            http_status: Some(400),
+            ..PhaseOutcome::failure("bad request")
+        }));
+        assert!(!policy.should_retry(&PhaseOutcome::success()));
+    }
+
+    #[test]
+    fn test_invalid_settings_are_rejected() {
+        let bad = [
+            Retry { initial_delay: Some("soon".to_string()), ..Default::default() },
+            Retry { backoff_multiplier: Some(0.5), ..Default::default() },
+            Retry { jitter: Some(1.5), ..Default::default() },
+            Retry { retry_on: vec!["exit:x".to_string()], ..Default::default() },
+        ];
+        for retry in bad {
+            assert!(RetryPolicy::from_spec(Some(&retry)).is_err(), "{:?}", retry);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use crate::executor::driver::{self, ExecutionContext, PhaseOutcome};
+use crate::executor::outputs;
+use crate::executor::runner;
+use crate::executor::scheduler::{topological_order, PhaseReport, PhaseStatus, PlanReport, PlanStatus};
+use crate::executor::secrets::Secrets;
+use crate::executor::ExecutionOptions;
+use crate::log::Event;
+use crate::model::Phase;
+use std::collections::{HashMap, HashSet};
+use tokio::time::timeout;
+
+/// A phase whose body is the `onRollback` step of `phase`, so it can be
+/// validated and executed by the regular drivers
+pub fn compensation_phase(phase: &Phase) -> Option<Phase> {
+    let rollback = phase.spec.on_rollback.as_ref()?;
+    let mut compensation = phase.clone();
+    let spec = &mut compensation.spec;
+    spec.driver = rollback.driver.clone();
+    spec.command = rollback.command.clone();
+    spec.http = rollback.http.clone();
+    spec.timeout = rollback.timeout.clone();
+    spec.attempt_timeout = None;
+    spec.wait_for = None;
+    spec.retry = None;
+    spec.on_failure = None;
+    spec.on_success = None;
+    spec.on_rollback = None;
+    Some(compensation)
+}
+
+/// Run the compensation of every succeeded phase, dependents before the
+/// phases they depend on. Output references in the `onRollback` steps are
+/// resolved from the outputs in `report`. Each phase is marked `rolled_back` or
+/// `rollback_failed`; a failed compensation does not stop the others. In a
+/// dry run the rollback is only described in the trace.
+pub async fn roll_back(phases: &[Phase], report: &mut PlanReport, options: &ExecutionOptions) {
+    let by_id: HashMap<&str, &Phase> = phases.iter().map(|p| (p.id.as_str(), p)).collect();
+    let order = topological_order(phases).unwrap_or_default();
+    let targets: Vec<&Phase> = order
+        .iter()
+        .rev()
+        .filter(|id| report.status_of(id) == Some(PhaseStatus::Succeeded))
+        .filter_map(|id| by_id.get(id.as_str()).copied())
+        .filter(|p| p.spec.on_rollback.is_some())
+        .collect();
+    if targets.is_empty() {
+        return;
+    }
+    if options.dry_run {
+        for compensation in targets.into_iter().filter_map(compensation_phase) {
+            let mut step = runner::describe_phase(&compensation, options, &[]);
+            step.action = format!("roll back: {}", step.action);
+            report.trace.push(step);
+        }
+        return;
+    }
+
+    let available = report.outputs();
+    println!("Rolling back {} phases", targets.len());
+    log(
+        options,
+        Event::RollbackStarted {
+            phases: targets.iter().map(|p| p.id.clone()).collect(),
+        },
+        None,
+        HashMap::new(),
+    )
+    .await;
+
+    for phase in targets {
+        let Some(compensation) = compensation_phase(phase) else {
+            continue;
+        };
+        let compensation = outputs::resolve(&compensation, &available);
+        let driver_name = driver::driver_name(&compensation).to_string();
+        let outcome = compensate(&compensation, &driver_name, options).await;
+        let success = outcome.is_success();
+        if success {
+            println!("Rolled back phase {}", phase.id);
+        } else {
+            eprintln!(
+                "Rollback of phase {} failed: {}",
+                phase.id,
+                outcome.message.as_deref().unwrap_or("driver reported failure")
+            );
+        }
+
+        let mut context = HashMap::new();
+        context.insert("driver".to_string(), driver_name);
+        if let Some(message) = &outcome.message {
+            context.insert("message".to_string(), message.clone());
+        }
+        log(
+            options,
+            Event::PhaseRolledBack {
+                id: phase.id.clone(),
+                success,
+            },
+            Some(phase.id.clone()),
+            context,
+        )
+        .await;
+
+        report.set_status(
+            &phase.id,
+            if success {
+                PhaseStatus::RolledBack
+            } else {
+                PhaseStatus::RollbackFailed
+            },
+        );
+    }
+}
+
+/// Run the `onRollback` step of every phase removed from the plan,
+/// dependents before the phases they depend on in `applied` (the plan the
+/// phases were last applied with). Results are added to
+/// `report.teardown`; a failed teardown fails the plan. Deleted phases
+/// without an `onRollback` step are simply dropped. In a dry run the
+/// teardown is only described in the trace.
+pub async fn tear_down(applied: &[Phase], deleted: &[&Phase], report: &mut PlanReport, options: &ExecutionOptions) {
+    let deleted_ids: HashSet<&str> = deleted.iter().map(|p| p.id.as_str()).collect();
+    let by_id: HashMap<&str, &Phase> = applied.iter().map(|p| (p.id.as_str(), p)).collect();
+    let order = topological_order(applied).unwrap_or_else(|_| applied.iter().map(|p| p.id.clone()).collect());
+    let targets: Vec<&Phase> = order
+        .iter()
+        .rev()
+        .filter(|id| deleted_ids.contains(id.as_str()))
+        .filter_map(|id| by_id.get(id.as_str()).copied())
+        .filter(|p| p.spec.on_rollback.is_some())
+        .collect();
+
+    for phase in targets {
+        let Some(compensation) = compensation_phase(phase) else {
+            continue;
+        };
+        if options.dry_run {
+            let mut step = runner::describe_phase(&compensation, options, &[]);
+            step.action = format!("tear down: {}", step.action);
+            report.trace.push(step);
+            continue;
+        }
+
+        let driver_name = driver::driver_name(&compensation).to_string();
+        let outcome = compensate(&compensation, &driver_name, options).await;
+        let success = outcome.is_success();
+        if success {
+            println!("Tore down deleted phase {}", phase.id);
+        } else {
+            eprintln!(
+                "Teardown of deleted phase {} failed: {}",
+                phase.id,
+                outcome.message.as_deref().unwrap_or("driver reported failure")
+            );
+            report.status = PlanStatus::Failed;
+        }
+
+        let mut context = HashMap::new();
+        context.insert("driver".to_string(), driver_name);
+        if let Some(message) = &outcome.message {
+            context.insert("message".to_string(), message.clone());
+        }
+        log(
+            options,
+            Event::PhaseTornDown {
+                id: phase.id.clone(),
+                success,
+            },
+            Some(phase.id.clone()),
+            context,
+        )
+        .await;
+
+        report.teardown.push(PhaseReport {
+            id: phase.id.clone(),
+            status: if success {
+                PhaseStatus::TornDown
+            } else {
+                PhaseStatus::TeardownFailed
+            },
+            outputs: Default::default(),
+        });
+    }
+}
+
+async fn compensate(compensation: &Phase, driver_name: &str, options: &ExecutionOptions) -> PhaseOutcome {
+    let Some(phase_driver) = driver::get_driver(driver_name) else {
+        return PhaseOutcome::failure(format!("Unknown driver '{}'", driver_name));
+    };
+    let secrets = match Secrets::load(compensation) {
+        Ok(secrets) => secrets,
+        Err(e) => return PhaseOutcome::failure(e),
+    };
+    let compensation = &secrets.inject(compensation);
+    let ctx = ExecutionContext {
+        plan_id: options.plan_id.clone(),
+        attempt: 1,
+        max_attempts: 1,
+    };
+
+    let limit = compensation
+        .spec
+        .timeout
+        .as_deref()
+        .and_then(|t| humantime::parse_duration(t).ok());
+    let outcome = match limit {
+        Some(limit) => timeout(limit, phase_driver.execute(compensation, &ctx))
+            .await
+            .unwrap_or_else(|_| PhaseOutcome::timed_out(format!("rollback timed out after {:?}", limit))),
+        None => phase_driver.execute(compensation, &ctx).await,
+    };
+    secrets.redact_outcome(outcome)
+}
+
+async fn log(options: &ExecutionOptions, event: Event, phase_id: Option<String>, context: HashMap<String, String>) {
+    if let Some(logging_service) = &options.logging_service {
+        let _ = logging_service
+            .log_event_with_context(event, options.plan_id.clone(), phase_id, context)
+            .await;
+    }
+}
@@ -1,50 +1,237 @@
-use crate::executor::driver;
+use crate::executor::approval;
+use crate::executor::attempt::{max_output_bytes, AttemptRecord};
+use crate::executor::dependencies;
+use crate::executor::driver::{self, ExecutionContext, PhaseOutcome};
 use crate::executor::hooks;
+use crate::executor::outputs::{self, PhaseOutputs};
+use crate::executor::retry::RetryPolicy;
+use crate::executor::scheduler::TraceStep;
+use crate::executor::secrets::Secrets;
+use crate::executor::ExecutionOptions;
+use crate::log::Event;
 use crate::model::Phase;
-use crate::state::redis::RedisClient;
-use tokio::time::sleep;
+use chrono::Utc;
+use std::fmt;
+use std::time::Duration;
+use tokio::time::{timeout, Instant};
 
-pub async fn run_phase(_client: &RedisClient, phase: &Phase) -> Result<(), String> {
-    println!("Running phase: {}", phase.id);
+/// Why a phase did not succeed
+#[derive(Debug, Clone, PartialEq)]
+pub enum PhaseError {
+    /// The driver failed (after any retries)
+    Failed(String),
+    /// `wait_for.timeout` passed while these dependencies were outstanding
+    DependencyTimeout { waiting_for: Vec<String> },
+}
 
-    // Handle waitFor timeout
-    if let Some(wait) = &phase.spec.wait_for {
-        if let Some(timeout_str) = &wait.timeout {
-            if let Ok(dur) = humantime::parse_duration(timeout_str) {
-                println!("Waiting {:?} before executing {}", dur, phase.id);
-                sleep(dur).await;
+impl fmt::Display for PhaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhaseError::Failed(message) => write!(f, "{}", message),
+            PhaseError::DependencyTimeout { waiting_for } => {
+                write!(f, "dependency timeout waiting for {}", waiting_for.join(", "))
             }
         }
     }
+}
+
+/// Run a phase once its dependencies have completed. Dependencies from the
+/// same plan are settled by the scheduler before the phase is dispatched;
+/// `external` ones (earlier runs, NATS peers) are waited on here until
+/// `wait_deadline`. Approval gates then wait for their decision instead of
+/// running a driver. Returns the outputs the phase published.
+pub async fn run_phase(
+    phase: &Phase,
+    options: &ExecutionOptions,
+    external: &[String],
+    wait_deadline: Option<Instant>,
+) -> Result<PhaseOutputs, PhaseError> {
+    wait_for_external(phase, external, wait_deadline).await?;
+    execute_phase(phase, options).await
+}
+
+/// Wait until the `external` dependencies of a phase have completed, failing
+/// it if `wait_deadline` passes first
+pub async fn wait_for_external(
+    phase: &Phase,
+    external: &[String],
+    wait_deadline: Option<Instant>,
+) -> Result<(), PhaseError> {
+    if let Err(waiting_for) = dependencies::wait_for(external, wait_deadline).await {
+        let err = PhaseError::DependencyTimeout { waiting_for };
+        eprintln!("Phase {} failed: {}", phase.id, err);
+        hooks::handle_failure(phase).await;
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Run a phase whose dependencies have all completed
+pub async fn execute_phase(phase: &Phase, options: &ExecutionOptions) -> Result<PhaseOutputs, PhaseError> {
+    if approval::is_gate(phase) {
+        return approval::wait(phase, options).await.map_err(PhaseError::Failed);
+    }
+    run_attempts(phase, options).await.map_err(PhaseError::Failed)
+}
+
+/// Dry-run counterpart of [`run_phase`]: describe what the phase would do
+/// without waiting on dependencies or invoking the driver
+pub fn describe_phase(phase: &Phase, options: &ExecutionOptions, external: &[String]) -> TraceStep {
+    let driver_name = driver::driver_name(phase);
+    let ctx = ExecutionContext {
+        plan_id: options.plan_id.clone(),
+        attempt: 1,
+        max_attempts: RetryPolicy::from_spec(phase.spec.retry.as_ref())
+            .map(|p| p.max_attempts)
+            .unwrap_or(1),
+    };
+    let action = match driver::get_driver(driver_name) {
+        Some(phase_driver) => phase_driver.describe(phase, &ctx),
+        None => format!("fail: unknown driver '{}'", driver_name),
+    };
+
+    TraceStep {
+        id: phase.id.clone(),
+        driver: driver_name.to_string(),
+        action,
+        waiting_for: external
+            .iter()
+            .filter(|dep| !dependencies::is_completed(dep))
+            .cloned()
+            .collect(),
+    }
+}
+
+async fn run_attempts(phase: &Phase, options: &ExecutionOptions) -> Result<PhaseOutputs, String> {
+    println!("Running phase: {}", phase.id);
+
+    let driver_name = driver::driver_name(phase);
+    let Some(phase_driver) = driver::get_driver(driver_name) else {
+        let err = format!("Unknown driver '{}' for phase {}", driver_name, phase.id);
+        hooks::handle_failure(phase).await;
+        return Err(err);
+    };
 
+    // Secrets are read for this execution only; the driver gets them
+    // injected, and what it reports back is redacted
+    let secrets = match Secrets::load(phase) {
+        Ok(secrets) => secrets,
+        Err(err) => {
+            hooks::handle_failure(phase).await;
+            return Err(err);
+        }
+    };
+    let injected = secrets.inject(phase);
+
+    let phase_timeout = parse_timeout(phase.spec.timeout.as_deref());
+    let attempt_timeout = parse_timeout(phase.spec.attempt_timeout.as_deref());
+    let deadline = phase_timeout.map(|t| Instant::now() + t);
+
+    let policy = RetryPolicy::from_spec(phase.spec.retry.as_ref()).unwrap_or_default();
+    let max_attempts = policy.max_attempts;
     let mut attempts = 0;
-    let max_attempts = phase
-        .spec
-        .retry
-        .as_ref()
-        .and_then(|r| r.max_attempts)
-        .unwrap_or(1);
 
     while attempts < max_attempts {
         attempts += 1;
         println!("Attempt {} of {} for phase {}", attempts, max_attempts, phase.id);
 
-        let result = driver::execute(&phase).await;
+        let ctx = ExecutionContext {
+            plan_id: options.plan_id.clone(),
+            attempt: attempts,
+            max_attempts,
+        };
 
-        match result {
-            Ok(_) => {
-                hooks::handle_success(&phase).await;
-                return Ok(());
-            }
-            Err(err) => {
-                eprintln!("Phase {} attempt {} failed: {}", phase.id, attempts, err);
-                if attempts == max_attempts {
-                    hooks::handle_failure(&phase).await;
-                    return Err(err);
+        // The attempt may run until its own limit or the phase deadline,
+        // whichever comes first. Dropping the driver future on expiry
+        // cancels it (and kills any child process it spawned).
+        let remaining = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+        let limit = match (attempt_timeout, remaining) {
+            (Some(a), Some(r)) => Some(a.min(r)),
+            (a, r) => a.or(r),
+        };
+        let started_at = Utc::now();
+        let outcome = match limit {
+            Some(limit) => match timeout(limit, phase_driver.execute(&injected, &ctx)).await {
+                Ok(outcome) => outcome,
+                Err(_) => PhaseOutcome::timed_out(format!(
+                    "Phase {} attempt {} timed out after {:?}",
+                    phase.id, attempts, limit
+                )),
+            },
+            None => phase_driver.execute(&injected, &ctx).await,
+        };
+        let outcome = secrets.redact_outcome(outcome);
+        let record = AttemptRecord::new(&phase.id, attempts, &outcome, started_at, Utc::now(), max_output_bytes());
+        record_attempt(options, driver_name, &record).await;
+
+        if outcome.is_success() {
+            return match outputs::collect(phase, &outcome).await {
+                Ok(outputs) => {
+                    hooks::handle_success(phase).await;
+                    Ok(secrets.redact_outputs(outputs))
                 }
+                Err(e) => {
+                    hooks::handle_failure(phase).await;
+                    Err(e)
+                }
+            };
+        }
+
+        let retryable = policy.should_retry(&outcome);
+        let err = outcome.message.unwrap_or_else(|| "driver reported failure".to_string());
+        eprintln!("Phase {} attempt {} failed: {}", phase.id, attempts, err);
+        let delay = policy.next_delay(attempts);
+        // Don't start a retry the phase deadline would cut short anyway
+        let out_of_time = deadline.is_some_and(|d| Instant::now() + delay >= d);
+        if attempts == max_attempts || out_of_time || !retryable {
+            hooks::handle_failure(phase).await;
+            if out_of_time && retryable && attempts < max_attempts {
+                return Err(format!(
+                    "Phase {} exceeded its timeout of {:?} after {} attempts",
+                    phase.id,
+                    phase_timeout.unwrap_or_default(),
+                    attempts
+                ));
             }
+            return Err(err);
+        }
+
+        if !delay.is_zero() {
+            println!("Retrying phase {} in {:?}", phase.id, delay);
+            options.clock.sleep(delay).await;
         }
     }
 
     Err(format!("Phase {} failed after {} attempts", phase.id, attempts))
 }
+
+fn parse_timeout(value: Option<&str>) -> Option<Duration> {
+    value.and_then(|v| humantime::parse_duration(v).ok())
+}
+
+/// Record the result of a single attempt in the log store and with the
+/// observer
+pub(crate) async fn record_attempt(options: &ExecutionOptions, driver_name: &str, record: &AttemptRecord) {
+    if let Some(observer) = &options.observer {
+        observer.attempt_finished(record);
+    }
+    let Some(logging_service) = &options.logging_service else {
+        return;
+    };
+
+    let mut context = record.to_context();
+    context.insert("driver".to_string(), driver_name.to_string());
+
+    let _ = logging_service
+        .log_event_with_context(
+            Event::PhaseAttempt {
+                id: record.phase_id.clone(),
+                attempt: record.attempt,
+                success: record.is_success(),
+            },
+            options.plan_id.clone(),
+            Some(record.phase_id.clone()),
+            context,
+        )
+        .await;
+}
@@ -0,0 +1,427 @@
+use crate::executor::dependencies::{self, dependencies};
+use crate::executor::outputs::PhaseOutputs;
+use crate::model::Phase;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Reasons a plan cannot be scheduled
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleError {
+    DuplicatePhase(String),
+    UnknownDependency { phase: String, dependency: String },
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::DuplicatePhase(id) => {
+                write!(f, "phase '{}' is declared more than once", id)
+            }
+            ScheduleError::UnknownDependency { phase, dependency } => {
+                write!(f, "phase '{}' waits for unknown phase '{}'", phase, dependency)
+            }
+            ScheduleError::Cycle(path) => {
+                write!(f, "dependency cycle detected: {}", path.join(" -> "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+/// Execution status of a single phase within a plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhaseStatus {
+    Pending,
+    Running,
+    /// An approval gate waiting for an operator's decision
+    AwaitingApproval,
+    Succeeded,
+    Failed,
+    /// `wait_for.timeout` passed before its dependencies completed
+    DependencyTimeout,
+    Skipped,
+    /// Stopped or never started because the plan was aborted
+    Cancelled,
+    /// Succeeded, then undone by its `onRollback` step
+    RolledBack,
+    /// Succeeded, but its `onRollback` step failed
+    RollbackFailed,
+    /// Removed from the plan and undone by its `onRollback` step
+    TornDown,
+    /// Removed from the plan, but its `onRollback` step failed
+    TeardownFailed,
+    /// An `onUse` phase that nothing needed, so it never ran
+    Unused,
+    /// Carried over from the applied plan by an incremental apply without
+    /// running; it is not rolled back with the phases this plan ran
+    Unchanged,
+}
+
+impl PhaseStatus {
+    pub fn is_failure(self) -> bool {
+        matches!(self, PhaseStatus::Failed | PhaseStatus::DependencyTimeout)
+    }
+}
+
+/// Overall result of a plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanStatus {
+    Succeeded,
+    Failed,
+    Aborted,
+    /// Stopped by an operator
+    Cancelled,
+}
+
+/// Final status of every phase after a plan has been executed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanReport {
+    pub status: PlanStatus,
+    pub phases: Vec<PhaseReport>,
+    /// What each phase would have done, in dispatch order (dry runs only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trace: Vec<TraceStep>,
+    /// Phases removed from the plan and torn down (incremental applies only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub teardown: Vec<PhaseReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseReport {
+    pub id: String,
+    pub status: PhaseStatus,
+    /// Values the phase published for downstream phases
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub outputs: PhaseOutputs,
+}
+
+/// One phase of a dry-run execution trace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceStep {
+    pub id: String,
+    pub driver: String,
+    /// Description of the work the driver would perform
+    pub action: String,
+    /// External dependencies that have not completed yet
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub waiting_for: Vec<String>,
+}
+
+impl PlanReport {
+    pub fn status_of(&self, id: &str) -> Option<PhaseStatus> {
+        self.phases.iter().find(|p| p.id == id).map(|p| p.status)
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.status == PlanStatus::Succeeded
+    }
+
+    pub fn set_status(&mut self, id: &str, status: PhaseStatus) {
+        if let Some(phase) = self.phases.iter_mut().find(|p| p.id == id) {
+            phase.status = status;
+        }
+    }
+
+    /// Outputs of every phase that published any, keyed by phase id
+    pub fn outputs(&self) -> HashMap<String, PhaseOutputs> {
+        self.phases
+            .iter()
+            .filter(|p| !p.outputs.is_empty())
+            .map(|p| (p.id.clone(), p.outputs.clone()))
+            .collect()
+    }
+}
+
+/// Validate the dependency graph of a plan and return phase ids in a
+/// deterministic topological order (ties broken by submission order).
+pub fn topological_order(phases: &[Phase]) -> Result<Vec<String>, ScheduleError> {
+    let mut scheduler = Scheduler::new(phases)?;
+    let mut order = Vec::with_capacity(phases.len());
+    while let Some(phase) = scheduler.next_ready() {
+        scheduler.complete(&phase.id, true);
+        order.push(phase.id);
+    }
+    Ok(order)
+}
+
+/// Dependency-aware scheduler built from `spec.wait_for.phases`.
+///
+/// The scheduler hands out phases whose dependencies have all succeeded and
+/// marks every transitive dependent of a failed phase as skipped.
+/// Dependencies outside the plan are external: they are accepted when they
+/// have already completed or when the phase sets `wait_for.timeout`, and
+/// are waited on by the runner rather than the scheduler.
+pub struct Scheduler {
+    phases: Vec<Phase>,
+    index: HashMap<String, usize>,
+    dependents: Vec<Vec<usize>>,
+    unmet: Vec<usize>,
+    status: Vec<PhaseStatus>,
+    /// Outputs published by finished phases, keyed by phase id
+    outputs: HashMap<String, PhaseOutputs>,
+    /// A failure was not tolerated with `continue`
+    failed: bool,
+    halted: Option<PlanStatus>,
+}
+
+impl Scheduler {
+    pub fn new(phases: &[Phase]) -> Result<Self, ScheduleError> {
+        let mut index = HashMap::new();
+        for (i, phase) in phases.iter().enumerate() {
+            if index.insert(phase.id.clone(), i).is_some() {
+                return Err(ScheduleError::DuplicatePhase(phase.id.clone()));
+            }
+        }
+
+        let mut dependents = vec![Vec::new(); phases.len()];
+        let mut unmet = vec![0; phases.len()];
+        for (i, phase) in phases.iter().enumerate() {
+            let mut seen = HashSet::new();
+            for dep in dependencies(phase) {
+                let Some(&d) = index.get(dep) else {
+                    // Only a phase that declares how long to wait may depend
+                    // on ids outside the plan
+                    if dependencies::wait_timeout(phase).is_some() {
+                        continue;
+                    }
+                    return Err(ScheduleError::UnknownDependency {
+                        phase: phase.id.clone(),
+                        dependency: dep.clone(),
+                    });
+                };
+                if seen.insert(d) {
+                    dependents[d].push(i);
+                    unmet[i] += 1;
+                }
+            }
+        }
+
+        let scheduler = Self {
+            phases: phases.to_vec(),
+            index,
+            dependents,
+            unmet,
+            status: vec![PhaseStatus::Pending; phases.len()],
+            outputs: HashMap::new(),
+            failed: false,
+            halted: None,
+        };
+
+        if let Some(cycle) = scheduler.find_cycle() {
+            return Err(ScheduleError::Cycle(cycle));
+        }
+
+        Ok(scheduler)
+    }
+
+    /// Take the next phase whose dependencies have all succeeded, marking it running
+    pub fn next_ready(&mut self) -> Option<Phase> {
+        let i = (0..self.phases.len())
+            .find(|&i| self.status[i] == PhaseStatus::Pending && self.unmet[i] == 0)?;
+        self.status[i] = PhaseStatus::Running;
+        Some(self.phases[i].clone())
+    }
+
+    /// Record the result of a running phase. On failure every downstream
+    /// phase is skipped; returns the ids of phases skipped by this call.
+    pub fn complete(&mut self, id: &str, success: bool) -> Vec<String> {
+        if success {
+            self.finish(id, PhaseStatus::Succeeded, true)
+        } else {
+            self.finish(id, PhaseStatus::Failed, false)
+        }
+    }
+
+    /// Record the final status of a phase. When `proceed` is set its
+    /// dependents may run (even after a failure); otherwise every downstream
+    /// phase is skipped. Returns the ids of phases skipped by this call.
+    pub fn finish(&mut self, id: &str, status: PhaseStatus, proceed: bool) -> Vec<String> {
+        let Some(&i) = self.index.get(id) else {
+            return Vec::new();
+        };
+        self.status[i] = status;
+        if status.is_failure() && !proceed {
+            self.failed = true;
+        }
+
+        if proceed {
+            for &d in &self.dependents[i] {
+                self.unmet[d] = self.unmet[d].saturating_sub(1);
+            }
+            return Vec::new();
+        }
+
+        let mut skipped = Vec::new();
+        let mut stack = self.dependents[i].clone();
+        while let Some(d) = stack.pop() {
+            if self.status[d] == PhaseStatus::Pending {
+                self.status[d] = PhaseStatus::Skipped;
+                skipped.push(self.phases[d].id.clone());
+                stack.extend(self.dependents[d].iter().copied());
+            }
+        }
+        skipped
+    }
+
+    /// Stop the plan with `outcome`: no pending phase will start. Pending
+    /// phases are skipped, or cancelled when the plan is aborted or
+    /// cancelled; returns their ids. The first halt decides the plan status.
+    pub fn halt(&mut self, outcome: PlanStatus) -> Vec<String> {
+        self.halted.get_or_insert(outcome);
+        let status = match outcome {
+            PlanStatus::Aborted | PlanStatus::Cancelled => PhaseStatus::Cancelled,
+            _ => PhaseStatus::Skipped,
+        };
+
+        let mut stopped = Vec::new();
+        for (phase, s) in self.phases.iter().zip(self.status.iter_mut()) {
+            if *s == PhaseStatus::Pending {
+                *s = status;
+                stopped.push(phase.id.clone());
+            }
+        }
+        stopped
+    }
+
+    /// Mark a running approval gate as waiting for its decision
+    pub fn await_approval(&mut self, id: &str) {
+        if let Some(&i) = self.index.get(id) {
+            if self.status[i] == PhaseStatus::Running {
+                self.status[i] = PhaseStatus::AwaitingApproval;
+            }
+        }
+    }
+
+    /// Leave a pending phase out of the plan without affecting its dependents
+    pub fn mark_unused(&mut self, id: &str) {
+        if let Some(&i) = self.index.get(id) {
+            if self.status[i] == PhaseStatus::Pending {
+                self.status[i] = PhaseStatus::Unused;
+            }
+        }
+    }
+
+    /// Record the outputs a phase published
+    pub fn set_outputs(&mut self, id: &str, outputs: PhaseOutputs) {
+        if self.index.contains_key(id) && !outputs.is_empty() {
+            self.outputs.insert(id.to_string(), outputs);
+        }
+    }
+
+    /// Outputs published so far, keyed by phase id
+    pub fn outputs(&self) -> &HashMap<String, PhaseOutputs> {
+        &self.outputs
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted.is_some()
+    }
+
+    /// Phases that have not started yet
+    pub fn pending(&self) -> impl Iterator<Item = &Phase> {
+        self.phases
+            .iter()
+            .zip(&self.status)
+            .filter(|(_, status)| **status == PhaseStatus::Pending)
+            .map(|(phase, _)| phase)
+    }
+
+    pub fn status(&self, id: &str) -> Option<PhaseStatus> {
+        self.index.get(id).map(|&i| self.status[i])
+    }
+
+    /// True once no phase is pending, running or awaiting approval
+    pub fn is_finished(&self) -> bool {
+        self.status
+            .iter()
+            .all(|s| !matches!(s, PhaseStatus::Pending | PhaseStatus::Running | PhaseStatus::AwaitingApproval))
+    }
+
+    pub fn report(&self) -> PlanReport {
+        let status = self.halted.unwrap_or(if self.failed {
+            PlanStatus::Failed
+        } else {
+            PlanStatus::Succeeded
+        });
+        PlanReport {
+            status,
+            phases: self
+                .phases
+                .iter()
+                .zip(&self.status)
+                .map(|(phase, status)| PhaseReport {
+                    id: phase.id.clone(),
+                    status: *status,
+                    outputs: self.outputs.get(&phase.id).cloned().unwrap_or_default(),
+                })
+                .collect(),
+            trace: Vec::new(),
+            teardown: Vec::new(),
+        }
+    }
+
+    /// Depth-first search for a dependency cycle, returned as a closed path
+    fn find_cycle(&self) -> Option<Vec<String>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            New,
+            Active,
+            Done,
+        }
+
+        fn visit(
+            scheduler: &Scheduler,
+            node: usize,
+            marks: &mut [Mark],
+            path: &mut Vec<usize>,
+        ) -> Option<Vec<String>> {
+            marks[node] = Mark::Active;
+            path.push(node);
+            for dep in dependencies(&scheduler.phases[node]) {
+                let Some(&d) = scheduler.index.get(dep) else {
+                    continue;
+                };
+                match marks[d] {
+                    Mark::Active => {
+                        let start = path.iter().position(|&p| p == d).unwrap_or(0);
+                        let mut cycle: Vec<String> = path[start..]
+                            .iter()
+                            .map(|&p| scheduler.phases[p].id.clone())
+                            .collect();
+                        cycle.push(scheduler.phases[d].id.clone());
+                        return Some(cycle);
+                    }
+                    Mark::New => {
+                        if let Some(cycle) = visit(scheduler, d, marks, path) {
+                            return Some(cycle);
+                        }
+                    }
+                    Mark::Done => {}
+                }
+            }
+            path.pop();
+            marks[node] = Mark::Done;
+            None
+        }
+
+        let mut marks = vec![Mark::New; self.phases.len()];
+        for node in 0..self.phases.len() {
+            if marks[node] == Mark::New {
+                let mut path = Vec::new();
+                if let Some(cycle) = visit(self, node, &mut marks, &mut path) {
+                    return Some(cycle);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -0,0 +1,184 @@
+#[cfg(test)]
+mod tests {
+    use crate::model::*;
+    use crate::executor::scheduler::{topological_order, PhaseStatus, ScheduleError, Scheduler};
+
+    fn create_test_phase(id: &str, wait_for: &[&str]) -> Phase {
+        Phase {
+            kind: "Phase".to_string(),
+            id: id.to_string(),
+            spec: PhaseSpec {
+                description: format!("Test phase {}", id),
+                wait_for: if wait_for.is_empty() {
+                    None
+                } else {
+                    Some(WaitFor {
+                        phases: wait_for.iter().map(|s| s.to_string()).collect(),
+                        timeout: None,
+                    })
+                },
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_order_respects_wait_for() {
+        let phases = vec![
+            create_test_phase("deployment", &["initialization"]),
+            create_test_phase("initialization", &["preflight"]),
+            create_test_phase("preflight", &[]),
+        ];
+
+        let order = topological_order(&phases).unwrap();
+        assert_eq!(order, vec!["preflight", "initialization", "deployment"]);
+    }
+
+    #[test]
+    fn test_independent_phases_keep_submission_order() {
+        let phases = vec![
+            create_test_phase("b", &[]),
+            create_test_phase("a", &[]),
+            create_test_phase("c", &["a"]),
+        ];
+
+        let order = topological_order(&phases).unwrap();
+        assert_eq!(order, vec!["b", "a", "c"]);
+    }
+
+    #[test]
+    fn test_unknown_dependency_rejected() {
+        let phases = vec![create_test_phase("deploy", &["missing"])];
+
+        let err = topological_order(&phases).unwrap_err();
+        assert_eq!(
+            err,
+            ScheduleError::UnknownDependency {
+                phase: "deploy".to_string(),
+                dependency: "missing".to_string(),
+            }
+        );
+        assert!(err.to_string().contains("unknown phase 'missing'"));
+    }
+
+    #[test]
+    fn test_cycle_rejected() {
+        let phases = vec![
+            create_test_phase("a", &["c"]),
+            create_test_phase("b", &["a"]),
+            create_test_phase("c", &["b"]),
+        ];
+
+        match topological_order(&phases).unwrap_err() {
+            ScheduleError::Cycle(path) => {
+                assert_eq!(path.first(), path.last());
+                assert_eq!(path.len(), 4);
+            }
+            other => panic!("Expected cycle error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_self_dependency_rejected() {
+        let phases = vec![create_test_phase("a", &["a"])];
+
+        let err = topological_order(&phases).unwrap_err();
+        assert_eq!(err, ScheduleError::Cycle(vec!["a".to_string(), "a".to_string()]));
+    }
+
+    #[test]
+    fn test_duplicate_phase_rejected() {
+        let phases = vec![create_test_phase("a", &[]), create_test_phase("a", &[])];
+
+        let err = topological_order(&phases).unwrap_err();
+        assert_eq!(err, ScheduleError::DuplicatePhase("a".to_string()));
+    }
+
+    #[test]
+    fn test_failure_skips_transitive_dependents() {
+        let phases = vec![
+            create_test_phase("preflight", &[]),
+            create_test_phase("init", &["preflight"]),
+            create_test_phase("deploy", &["init"]),
+            create_test_phase("docs", &[]),
+        ];
+        let mut scheduler = Scheduler::new(&phases).unwrap();
+
+        let first = scheduler.next_ready().unwrap();
+        assert_eq!(first.id, "preflight");
+        let mut skipped = scheduler.complete("preflight", false);
+        skipped.sort();
+        assert_eq!(skipped, vec!["deploy", "init"]);
+
+        let next = scheduler.next_ready().unwrap();
+        assert_eq!(next.id, "docs");
+        scheduler.complete("docs", true);
+
+        assert!(scheduler.next_ready().is_none());
+        assert!(scheduler.is_finished());
+
+        let report = scheduler.report();
+        assert_eq!(report.status_of("preflight"), Some(PhaseStatus::Failed));
+        assert_eq!(report.status_of("init"), Some(PhaseStatus::Skipped));
+        assert_eq!(report.status_of("deploy"), Some(PhaseStatus::Skipped));
+        assert_eq!(report.status_of("docs"), Some(PhaseStatus::Succeeded));
+        assert!(!report.succeeded());
+    }
+
+    #[test]
+    fn test_phase_not_ready_until_all_dependencies_succeed() {
+        let phases = vec![
+            create_test_phase("a", &[]),
+            create_test_phase("b", &[]),
+            create_test_phase("c", &["a", "b"]),
+        ];
+        let mut scheduler = Scheduler::new(&phases).unwrap();
+
+        assert_eq!(scheduler.next_ready().unwrap().id, "a");
+        assert_eq!(scheduler.next_ready().unwrap().id, "b");
+        assert!(scheduler.next_ready().is_none());
+
+        scheduler.complete("a", true);
+        assert!(scheduler.next_ready().is_none());
+        assert_eq!(scheduler.status("c"), Some(PhaseStatus::Pending));
+
+        scheduler.complete("b", true);
+        assert_eq!(scheduler.next_ready().unwrap().id, "c");
+    }
+
+    #[test]
+    fn test_external_dependency_allowed_with_wait_timeout() {
+        let mut deploy = create_test_phase("deploy", &["scheduler-test-external"]);
+        deploy.spec.wait_for.as_mut().unwrap().timeout = Some("1s".to_string());
+        let phases = vec![deploy];
+
+        let mut scheduler = Scheduler::new(&phases).unwrap();
+        assert_eq!(scheduler.next_ready().unwrap().id, "deploy");
+    }
+
+    #[test]
+    fn test_external_dependency_rejected_without_wait_timeout_even_if_completed() {
+        crate::executor::dependencies::mark_completed("scheduler-test-earlier-run");
+        let phases = vec![create_test_phase("deploy", &["scheduler-test-earlier-run"])];
+
+        assert!(matches!(
+            Scheduler::new(&phases),
+            Err(ScheduleError::UnknownDependency { .. })
+        ));
+    }
+
+    #[test]
+    fn test_finish_pending_phase_with_status() {
+        let phases = vec![
+            create_test_phase("a", &[]),
+            create_test_phase("b", &["a"]),
+            create_test_phase("c", &["b"]),
+        ];
+        let mut scheduler = Scheduler::new(&phases).unwrap();
+        scheduler.next_ready();
+
+        assert_eq!(scheduler.finish("b", PhaseStatus::DependencyTimeout, false), vec!["c"]);
+        assert_eq!(scheduler.status("b"), Some(PhaseStatus::DependencyTimeout));
+        assert_eq!(scheduler.pending().count(), 0);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::config::secrets_dir;
+use crate::executor::driver::PhaseOutcome;
+use crate::executor::outputs::PhaseOutputs;
+use crate::executor::template::render_json;
+use crate::model::{Phase, SecretRef};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Component;
+use std::path::Path;
+
+/// What a secret value is replaced with in captured output
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Check the secret references of `phase` without reading any secret
+pub fn check(phase: &Phase) -> Result<(), String> {
+    for (name, secret) in phase.spec.secrets.iter().flatten() {
+        let valid_name = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && name.chars().next().is_some_and(|c| !c.is_ascii_digit());
+        if !valid_name {
+            return Err(format!("'{}' is not a valid secret name", name));
+        }
+        match (secret.file.as_deref(), secret.env.as_deref()) {
+            (Some(file), None) => {
+                let path = Path::new(file);
+                let inside = path.components().all(|c| matches!(c, Component::Normal(_)));
+                if file.is_empty() || !inside {
+                    return Err(format!(
+                        "secret '{}' must name a file inside the secrets directory, not '{}'",
+                        name, file
+                    ));
+                }
+            }
+            (None, Some(env)) if !env.is_empty() => {}
+            _ => return Err(format!("secret '{}' needs exactly one of 'file' and 'env'", name)),
+        }
+    }
+    Ok(())
+}
+
+/// Secret values of a phase, read just before it executes. They are only
+/// ever held in memory: the phase in the plan keeps its references, and
+/// output captured from the driver is redacted before it is recorded.
+#[derive(Clone, Default)]
+pub struct Secrets {
+    values: BTreeMap<String, String>,
+}
+
+impl Secrets {
+    /// Read the secrets `phase` references
+    pub fn load(phase: &Phase) -> Result<Self, String> {
+        let mut values = BTreeMap::new();
+        for (name, secret) in phase.spec.secrets.iter().flatten() {
+            let value = read(secret)
+                .map_err(|e| format!("Secret '{}' of phase {} is unavailable: {}", name, phase.id, e))?;
+            values.insert(name.clone(), value);
+        }
+        Ok(Self { values })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// `phase` with `${secrets.<name>}` substituted throughout its spec and,
+    /// for commands, every secret in the environment under its name
+    pub fn inject(&self, phase: &Phase) -> Phase {
+        if self.is_empty() {
+            return phase.clone();
+        }
+        let vars: HashMap<String, String> = self
+            .values
+            .iter()
+            .map(|(name, value)| (format!("secrets.{}", name), value.clone()))
+            .collect();
+        let mut injected = phase.clone();
+        if let Some(spec) = serde_json::to_value(&phase.spec)
+            .ok()
+            .and_then(|spec| serde_json::from_value(render_json(&spec, &vars)).ok())
+        {
+            injected.spec = spec;
+        }
+        if let Some(command) = &mut injected.spec.command {
+            for (name, value) in &self.values {
+                command.env.entry(name.clone()).or_insert_with(|| value.clone());
+            }
+        }
+        injected
+    }
+
+    /// `text` with every secret value replaced by [`REDACTED`]
+    pub fn redact(&self, text: &str) -> String {
+        let mut values: Vec<&String> = self.values.values().filter(|v| !v.is_empty()).collect();
+        // Longest first, so a secret containing another is hidden whole
+        values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+        values
+            .into_iter()
+            .fold(text.to_string(), |text, value| text.replace(value.as_str(), REDACTED))
+    }
+
+    /// `outcome` with secret values redacted from its message and output
+    pub fn redact_outcome(&self, mut outcome: PhaseOutcome) -> PhaseOutcome {
+        if self.is_empty() {
+            return outcome;
+        }
+        outcome.message = outcome.message.map(|m| self.redact(&m));
+        outcome.stdout = self.redact_tail(&outcome.stdout, outcome.stdout_truncated);
+        outcome.stderr = self.redact_tail(&outcome.stderr, outcome.stderr_truncated);
+        outcome
+    }
+
+    /// Redact the output a driver kept after dropping its start. The cut
+    /// may have fallen inside a secret, leaving only its end at the start
+    /// of `tail`, so that is hidden as well.
+    fn redact_tail(&self, tail: &str, truncated: bool) -> String {
+        let cut = if truncated { self.leading_partial(tail) } else { 0 };
+        if cut == 0 {
+            return self.redact(tail);
+        }
+        format!("{}{}", REDACTED, self.redact(&tail[cut..]))
+    }
+
+    /// Length of the longest end of a secret value, short of the whole
+    /// value, that `text` starts with
+    fn leading_partial(&self, text: &str) -> usize {
+        self.values
+            .values()
+            .filter_map(|value| {
+                (1..value.len())
+                    .filter(|&start| value.is_char_boundary(start))
+                    .map(|start| &value[start..])
+                    .find(|end| text.starts_with(end))
+                    .map(str::len)
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// `outputs` with secret values redacted, so none is published
+    pub fn redact_outputs(&self, outputs: PhaseOutputs) -> PhaseOutputs {
+        if self.is_empty() {
+            return outputs;
+        }
+        outputs
+            .into_iter()
+            .map(|(name, value)| (name, self.redact(&value)))
+            .collect()
+    }
+}
+
+fn read(secret: &SecretRef) -> Result<String, String> {
+    match (&secret.file, &secret.env) {
+        (Some(file), _) => {
+            let path = secrets_dir().join(file);
+            let value = std::fs::read_to_string(&path)
+                .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+            Ok(value.trim_end_matches(['\n', '\r']).to_string())
+        }
+        (None, Some(env)) => {
+            std::env::var(env).map_err(|_| format!("environment variable {} is not set", env))
+        }
+        (None, None) => Err("no source given".to_string()),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
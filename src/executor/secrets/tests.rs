@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod tests {
+    use crate::executor::attempt::OutputTail;
+    use crate::executor::driver::PhaseOutcome;
+    use crate::executor::runner::run_phase;
+    use crate::executor::secrets::{check, Secrets, REDACTED};
+    use crate::executor::ExecutionOptions;
+    use crate::model::*;
+    use std::collections::{BTreeMap, HashMap};
+
+    fn create_test_phase(id: &str, args: &[&str], secrets: &[(&str, SecretRef)]) -> Phase {
+        Phase {
+            kind: "Phase".to_string(),
+            id: id.to_string(),
+            spec: PhaseSpec {
+                description: format!("Test phase {}", id),
+                command: Some(CommandSpec {
+                    program: "sh".to_string(),
+                    args: args.iter().map(|a| a.to_string()).collect(),
+                    env: HashMap::new(),
+                    working_dir: None,
+                    stdin: None,
+                }),
+                secrets: Some(
+                    secrets
+                        .iter()
+                        .map(|(name, secret)| (name.to_string(), secret.clone()))
+                        .collect::<BTreeMap<_, _>>(),
+                ),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn from_env(var: &str) -> SecretRef {
+        SecretRef {
+            file: None,
+            env: Some(var.to_string()),
+        }
+    }
+
+    fn from_file(file: &str) -> SecretRef {
+        SecretRef {
+            file: Some(file.to_string()),
+            env: None,
+        }
+    }
+
+    #[test]
+    fn test_check_secret_refs() {
+        assert!(check(&create_test_phase("ok", &[], &[("API_TOKEN", from_file("ci/token"))])).is_ok());
+        for (name, secret) in [
+            ("API-TOKEN", from_env("TOKEN")),
+            ("1TOKEN", from_env("TOKEN")),
+            ("TOKEN", SecretRef::default()),
+            ("TOKEN", SecretRef { file: Some("token".to_string()), env: Some("TOKEN".to_string()) }),
+            ("TOKEN", from_file("../token")),
+            ("TOKEN", from_file("/etc/passwd")),
+            ("TOKEN", from_file("")),
+        ] {
+            let phase = create_test_phase("bad", &[], &[(name, secret.clone())]);
+            assert!(check(&phase).is_err(), "{} {:?} should be rejected", name, secret);
+        }
+    }
+
+    #[test]
+    fn test_load_inject_and_redact() {
+        std::env::set_var("PLANTER_TEST_SECRET_INJECT", "s3cr3t");
+        let phase = create_test_phase(
+            "inject",
+            &["-c", "deploy --token=${secrets.TOKEN}"],
+            &[("TOKEN", from_env("PLANTER_TEST_SECRET_INJECT"))],
+        );
+        let secrets = Secrets::load(&phase).unwrap();
+        let injected = secrets.inject(&phase);
+        let command = injected.spec.command.as_ref().unwrap();
+        assert_eq!(command.args[1], "deploy --token=s3cr3t");
+        assert_eq!(command.env.get("TOKEN").map(String::as_str), Some("s3cr3t"));
+        // The phase itself keeps the reference
+        assert_eq!(phase.spec.command.as_ref().unwrap().args[1], "deploy --token=${secrets.TOKEN}");
+
+        let mut outcome = PhaseOutcome::failure("rejected token s3cr3t");
+        outcome.stdout = "s3cr3t\nok".to_string();
+        let outcome = secrets.redact_outcome(outcome);
+        assert_eq!(outcome.message.as_deref(), Some(format!("rejected token {}", REDACTED).as_str()));
+        assert_eq!(outcome.stdout, format!("{}\nok", REDACTED));
+    }
+
+    #[test]
+    fn test_redact_longest_value_first() {
+        std::env::set_var("PLANTER_TEST_SECRET_SHORT", "abc");
+        std::env::set_var("PLANTER_TEST_SECRET_LONG", "abcdef");
+        let phase = create_test_phase(
+            "redact",
+            &[],
+            &[
+                ("SHORT", from_env("PLANTER_TEST_SECRET_SHORT")),
+                ("LONG", from_env("PLANTER_TEST_SECRET_LONG")),
+            ],
+        );
+        let secrets = Secrets::load(&phase).unwrap();
+        assert_eq!(secrets.redact("abcdef abc"), format!("{} {}", REDACTED, REDACTED));
+    }
+
+    #[test]
+    fn test_redact_secret_split_by_truncation() {
+        std::env::set_var("PLANTER_TEST_SECRET_SPLIT", "s3cr3t-t0ken");
+        let phase = create_test_phase("split", &[], &[("TOKEN", from_env("PLANTER_TEST_SECRET_SPLIT"))]);
+        let secrets = Secrets::load(&phase).unwrap();
+
+        // The driver kept the last 25 bytes, cutting the first secret in two
+        let mut tail = OutputTail::new(25);
+        tail.push(b"token=s3cr3t-t0ken\nagain s3cr3t-t0ken\n");
+        let (stdout, truncated) = tail.finish();
+        assert_eq!(stdout, "t0ken\nagain s3cr3t-t0ken\n");
+
+        let mut outcome = PhaseOutcome::success();
+        outcome.stdout = stdout;
+        outcome.stdout_truncated = truncated;
+        let outcome = secrets.redact_outcome(outcome);
+        assert_eq!(outcome.stdout, format!("{}\nagain {}\n", REDACTED, REDACTED));
+
+        // Output that was not truncated starts where the stream did
+        let mut outcome = PhaseOutcome::success();
+        outcome.stderr = "t0ken\n".to_string();
+        assert_eq!(secrets.redact_outcome(outcome).stderr, "t0ken\n");
+    }
+
+    #[test]
+    fn test_load_reports_missing_secret_without_value() {
+        let phase = create_test_phase("missing", &[], &[("TOKEN", from_env("PLANTER_TEST_SECRET_UNSET"))]);
+        let err = Secrets::load(&phase).err().unwrap();
+        assert!(err.contains("TOKEN") && err.contains("PLANTER_TEST_SECRET_UNSET"), "{}", err);
+
+        let phase = create_test_phase("missing", &[], &[("TOKEN", from_file("planter-test-missing-secret"))]);
+        assert!(Secrets::load(&phase).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_phase_injects_and_redacts_secrets() {
+        std::env::set_var("PLANTER_TEST_SECRET_RUN", "hunter2");
+        let phase = create_test_phase(
+            "secret-run",
+            &[
+                "-c",
+                r#"printf '{"outputs": {"env": "%s", "arg": "%s"}}\n' "$TOKEN" "${secrets.TOKEN}""#,
+            ],
+            &[("TOKEN", from_env("PLANTER_TEST_SECRET_RUN"))],
+        );
+        let outputs = run_phase(&phase, &ExecutionOptions::default(), &[], None).await.unwrap();
+        assert_eq!(outputs.get("env").map(String::as_str), Some(REDACTED));
+        assert_eq!(outputs.get("arg").map(String::as_str), Some(REDACTED));
+
+        let phase = create_test_phase("secret-missing", &["-c", "true"], &[("TOKEN", from_env("PLANTER_TEST_SECRET_UNSET"))]);
+        assert!(run_phase(&phase, &ExecutionOptions::default(), &[], None).await.is_err());
+    }
+}
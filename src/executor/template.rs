@@ -0,0 +1,75 @@
+use crate::executor::driver::ExecutionContext;
+use crate::model::Phase;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Substitute `${name}` placeholders from `vars`. Unknown placeholders are
+/// left untouched so they are easy to spot in logs.
+pub fn render(template: &str, vars: &HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find('}') {
+            Some(end) => {
+                let name = after[..end].trim();
+                match vars.get(name) {
+                    Some(value) => out.push_str(value),
+                    None => out.push_str(&rest[start..start + 2 + end + 1]),
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Names of the `${name}` placeholders in `template`, in order
+pub fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("${") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            break;
+        };
+        names.push(after[..end].trim());
+        rest = &after[end + 1..];
+    }
+    names
+}
+
+/// Render every string inside a JSON value
+pub fn render_json(value: &Value, vars: &HashMap<String, String>) -> Value {
+    match value {
+        Value::String(s) => Value::String(render(s, vars)),
+        Value::Array(items) => Value::Array(items.iter().map(|v| render_json(v, vars)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), render_json(v, vars)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Variables available to every phase: `phase.id`, `plan.id` and `attempt`
+pub fn phase_vars(phase: &Phase, ctx: &ExecutionContext) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    vars.insert("phase.id".to_string(), phase.id.clone());
+    vars.insert("plan.id".to_string(), ctx.plan_id.clone().unwrap_or_default());
+    vars.insert("attempt".to_string(), ctx.attempt.to_string());
+    vars
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod tests {
+    use crate::executor::template::{placeholders, render, render_json};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn vars() -> HashMap<String, String> {
+        let mut vars = HashMap::new();
+        vars.insert("phase.id".to_string(), "deploy".to_string());
+        vars.insert("attempt".to_string(), "2".to_string());
+        vars
+    }
+
+    #[test]
+    fn test_render_substitutes_known_placeholders() {
+        assert_eq!(render("/phases/${phase.id}/try/${ attempt }", &vars()), "/phases/deploy/try/2");
+    }
+
+    #[test]
+    fn test_render_keeps_unknown_and_unterminated_placeholders() {
+        assert_eq!(render("${missing}-${phase.id}", &vars()), "${missing}-deploy");
+        assert_eq!(render("tail ${phase.id", &vars()), "tail ${phase.id");
+        assert_eq!(render("no placeholders", &vars()), "no placeholders");
+    }
+
+    #[test]
+    fn test_render_json_renders_nested_strings() {
+        let value = json!({ "id": "${phase.id}", "tags": ["a-${attempt}"], "count": 3 });
+        assert_eq!(
+            render_json(&value, &vars()),
+            json!({ "id": "deploy", "tags": ["a-2"], "count": 3 })
+        );
+    }
+
+    #[test]
+    fn test_placeholders_lists_terminated_names() {
+        assert_eq!(
+            placeholders("${ phase.id }/${phases.init.outputs.id}/${open"),
+            vec!["phase.id", "phases.init.outputs.id"]
+        );
+        assert!(placeholders("plain").is_empty());
+    }
+}
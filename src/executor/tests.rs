@@ -0,0 +1,1040 @@
+#[cfg(test)]
+mod tests {
+    use crate::model::*;
+    use crate::executor::{run_plan, ExecutionOptions};
+    use crate::executor::control::{ApprovalDecision, RunControl};
+    use crate::executor::driver::{register_driver, ExecutionContext, PhaseDriver, PhaseOutcome};
+    use crate::executor::driver::simulated::SimulatedDriver;
+    use crate::executor::retry::Clock;
+    use crate::executor::scheduler::{PhaseStatus, PlanStatus};
+    use crate::executor::validate::ValidationError;
+    use async_trait::async_trait;
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    fn create_test_phase(id: &str, wait_for: &[&str], driver: Option<&str>) -> Phase {
+        Phase {
+            kind: "Phase".to_string(),
+            id: id.to_string(),
+            spec: PhaseSpec {
+                description: format!("Test phase {}", id),
+                driver: driver.map(|d| d.to_string()),
+                wait_for: Some(WaitFor {
+                    phases: wait_for.iter().map(|s| s.to_string()).collect(),
+                    timeout: None,
+                }),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_sequential_by_default() {
+        let phases = vec![
+            create_test_phase("deploy", &["setup"], None),
+            create_test_phase("setup", &[], None),
+        ];
+
+        let report = run_plan(&phases, &ExecutionOptions::default()).await.unwrap();
+        assert!(report.succeeded());
+        assert_eq!(report.phases.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_parallel_diamond() {
+        let phases = vec![
+            create_test_phase("root", &[], None),
+            create_test_phase("left", &["root"], None),
+            create_test_phase("right", &["root"], None),
+            create_test_phase("join", &["left", "right"], None),
+        ];
+
+        let report = run_plan(&phases, &ExecutionOptions::parallel(4)).await.unwrap();
+        assert!(report.succeeded());
+        assert_eq!(report.status_of("join"), Some(PhaseStatus::Succeeded));
+    }
+
+    /// Records the order in which phases are executed
+    struct RecordingDriver {
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl PhaseDriver for RecordingDriver {
+        async fn execute(&self, phase: &Phase, ctx: &ExecutionContext) -> PhaseOutcome {
+            self.calls.lock().unwrap().push(format!("{}@{}", phase.id, ctx.plan_id.clone().unwrap_or_default()));
+            PhaseOutcome::success()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_dispatches_to_named_driver() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-recording", Arc::new(RecordingDriver { calls: calls.clone() }));
+
+        let phases = vec![
+            create_test_phase("deploy", &["setup"], Some("test-recording")),
+            create_test_phase("setup", &[], Some("test-recording")),
+            create_test_phase("notes", &[], Some("noop")),
+        ];
+
+        let options = ExecutionOptions::default().with_plan_id("plan-1");
+        let report = run_plan(&phases, &options).await.unwrap();
+        assert!(report.succeeded());
+        assert_eq!(*calls.lock().unwrap(), vec!["setup@plan-1", "deploy@plan-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_rejects_unknown_driver() {
+        let phases = vec![create_test_phase("deploy", &[], Some("does-not-exist"))];
+
+        let err = run_plan(&phases, &ExecutionOptions::default()).await.unwrap_err();
+        assert_eq!(
+            err,
+            ValidationError::UnknownDriver {
+                phase: "deploy".to_string(),
+                driver: "does-not-exist".to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_retries_failing_driver() {
+        register_driver("test-flaky", Arc::new(SimulatedDriver::default().failing_attempts(2)));
+
+        let mut phase = create_test_phase("flaky", &[], Some("test-flaky"));
+        phase.spec.retry = Some(Retry { max_attempts: Some(3), ..Default::default() });
+
+        let report = run_plan(&[phase], &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status_of("flaky"), Some(PhaseStatus::Succeeded));
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_failed_driver_skips_dependents() {
+        register_driver("test-broken", Arc::new(SimulatedDriver::default().always_failing()));
+
+        let phases = vec![
+            create_test_phase("setup", &[], Some("test-broken")),
+            create_test_phase("deploy", &["setup"], Some("noop")),
+        ];
+
+        let report = run_plan(&phases, &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status_of("setup"), Some(PhaseStatus::Failed));
+        assert_eq!(report.status_of("deploy"), Some(PhaseStatus::Skipped));
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_parallel_overlaps_independent_phases() {
+        register_driver("test-slow-300", Arc::new(SimulatedDriver::default().with_delay(Duration::from_millis(300))));
+        let phases: Vec<Phase> = (0..4)
+            .map(|i| create_test_phase(&format!("p{}", i), &[], Some("test-slow-300")))
+            .collect();
+
+        let started = Instant::now();
+        let report = run_plan(&phases, &ExecutionOptions::parallel(4)).await.unwrap();
+        assert!(report.succeeded());
+        // Sequential execution would take at least 1.2s
+        assert!(started.elapsed() < Duration::from_millis(1100));
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_respects_plan_concurrency_limit() {
+        register_driver("test-slow-200", Arc::new(SimulatedDriver::default().with_delay(Duration::from_millis(200))));
+        let phases: Vec<Phase> = (0..4)
+            .map(|i| create_test_phase(&format!("p{}", i), &[], Some("test-slow-200")))
+            .collect();
+
+        let started = Instant::now();
+        let report = run_plan(&phases, &ExecutionOptions::parallel(2)).await.unwrap();
+        assert!(report.succeeded());
+        // Two waves of two phases each
+        assert!(started.elapsed() >= Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_rejects_invalid_plan() {
+        let phases = vec![create_test_phase("deploy", &["missing"], None)];
+
+        assert!(run_plan(&phases, &ExecutionOptions::parallel(2)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_command_exit_code_feeds_retry() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let counter = tmp.path().join("attempts");
+
+        let mut phase = create_test_phase("cmd", &[], None);
+        phase.spec.command = Some(CommandSpec {
+            program: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                format!("echo attempt >> {}; exit 1", counter.display()),
+            ],
+            env: HashMap::new(),
+            working_dir: None,
+            stdin: None,
+        });
+        phase.spec.retry = Some(Retry { max_attempts: Some(3), ..Default::default() });
+
+        let report = run_plan(&[phase], &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status_of("cmd"), Some(PhaseStatus::Failed));
+        let attempts = std::fs::read_to_string(&counter).unwrap();
+        assert_eq!(attempts.lines().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_rejects_command_driver_without_command() {
+        let phases = vec![create_test_phase("cmd", &[], Some("command"))];
+
+        let err = run_plan(&phases, &ExecutionOptions::default()).await.unwrap_err();
+        assert!(matches!(err, ValidationError::InvalidDriverConfig { .. }));
+    }
+
+    /// Hangs on the first attempt and succeeds on later ones
+    struct HangsOnceDriver;
+
+    #[async_trait]
+    impl PhaseDriver for HangsOnceDriver {
+        async fn execute(&self, _phase: &Phase, ctx: &ExecutionContext) -> PhaseOutcome {
+            if ctx.attempt == 1 {
+                tokio::time::sleep(Duration::from_secs(30)).await;
+            }
+            PhaseOutcome::success()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_attempt_timeout_counts_as_failed_attempt() {
+        register_driver("test-hangs-once", Arc::new(HangsOnceDriver));
+
+        let mut phase = create_test_phase("hang", &[], Some("test-hangs-once"));
+        phase.spec.attempt_timeout = Some("100ms".to_string());
+        phase.spec.retry = Some(Retry { max_attempts: Some(2), ..Default::default() });
+
+        let started = Instant::now();
+        let report = run_plan(&[phase], &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status_of("hang"), Some(PhaseStatus::Succeeded));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_phase_timeout_stops_retries() {
+        register_driver("test-slow-fail", Arc::new(SimulatedDriver::default().with_delay(Duration::from_millis(200)).always_failing()));
+
+        let mut phase = create_test_phase("slow", &[], Some("test-slow-fail"));
+        phase.spec.timeout = Some("500ms".to_string());
+        phase.spec.retry = Some(Retry { max_attempts: Some(50), ..Default::default() });
+
+        let started = Instant::now();
+        let report = run_plan(&[phase], &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status_of("slow"), Some(PhaseStatus::Failed));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_child_process() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let pid_file = tmp.path().join("pid");
+
+        let mut phase = create_test_phase("hung-cmd", &[], None);
+        phase.spec.command = Some(CommandSpec {
+            program: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                format!("echo $$ > {}; exec sleep 30", pid_file.display()),
+            ],
+            env: HashMap::new(),
+            working_dir: None,
+            stdin: None,
+        });
+        phase.spec.timeout = Some("300ms".to_string());
+
+        let started = Instant::now();
+        let report = run_plan(&[phase], &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status_of("hung-cmd"), Some(PhaseStatus::Failed));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap().trim().to_string();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap_or_default();
+        // Either reaped already or left as a zombie; never still sleeping
+        assert!(stat.is_empty() || stat.contains(") Z "), "child still running: {}", stat);
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_rejects_invalid_timeout() {
+        let mut phase = create_test_phase("bad", &[], Some("noop"));
+        phase.spec.attempt_timeout = Some("soon".to_string());
+
+        let err = run_plan(&[phase], &ExecutionOptions::default()).await.unwrap_err();
+        assert!(matches!(err, ValidationError::InvalidField { ref field, .. } if field == "attempt_timeout"));
+    }
+
+    /// Records requested delays instead of sleeping
+    #[derive(Default)]
+    struct RecordingClock {
+        delays: Mutex<Vec<Duration>>,
+    }
+
+    #[async_trait]
+    impl Clock for RecordingClock {
+        async fn sleep(&self, duration: Duration) {
+            self.delays.lock().unwrap().push(duration);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_backoff_uses_clock() {
+        register_driver("test-always-fails", Arc::new(SimulatedDriver::default().always_failing()));
+        let clock = Arc::new(RecordingClock::default());
+
+        let mut phase = create_test_phase("backoff", &[], Some("test-always-fails"));
+        phase.spec.retry = Some(Retry {
+            max_attempts: Some(5),
+            initial_delay: Some("1s".to_string()),
+            backoff_multiplier: Some(2.0),
+            max_delay: Some("5s".to_string()),
+            ..Default::default()
+        });
+
+        let options = ExecutionOptions::default().with_clock(clock.clone());
+        let report = run_plan(&[phase], &options).await.unwrap();
+        assert_eq!(report.status_of("backoff"), Some(PhaseStatus::Failed));
+        assert_eq!(
+            *clock.delays.lock().unwrap(),
+            vec![
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+                Duration::from_secs(4),
+                Duration::from_secs(5),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_skips_unlisted_failures() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let counter = tmp.path().join("attempts");
+
+        let mut phase = create_test_phase("cmd", &[], None);
+        phase.spec.command = Some(CommandSpec {
+            program: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                format!("echo attempt >> {}; exit 2", counter.display()),
+            ],
+            env: HashMap::new(),
+            working_dir: None,
+            stdin: None,
+        });
+        phase.spec.retry = Some(Retry {
+            max_attempts: Some(3),
+            retry_on: vec!["exit:75".to_string(), "timeout".to_string()],
+            ..Default::default()
+        });
+
+        let report = run_plan(&[phase], &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status_of("cmd"), Some(PhaseStatus::Failed));
+        let attempts = std::fs::read_to_string(&counter).unwrap();
+        assert_eq!(attempts.lines().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_rejects_invalid_retry() {
+        let mut phase = create_test_phase("bad", &[], Some("noop"));
+        phase.spec.retry = Some(Retry {
+            retry_on: vec!["sometimes".to_string()],
+            ..Default::default()
+        });
+
+        let err = run_plan(&[phase], &ExecutionOptions::default()).await.unwrap_err();
+        assert!(matches!(err, ValidationError::InvalidField { ref field, .. } if field == "retry"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_timeout_no_longer_delays_phase() {
+        let mut deploy = create_test_phase("deploy", &["setup"], Some("noop"));
+        deploy.spec.wait_for.as_mut().unwrap().timeout = Some("10s".to_string());
+        let phases = vec![create_test_phase("setup", &[], Some("noop")), deploy];
+
+        let started = Instant::now();
+        let report = run_plan(&phases, &ExecutionOptions::default()).await.unwrap();
+        assert!(report.succeeded());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_slow_dependency_causes_dependency_timeout() {
+        register_driver("test-slow-500", Arc::new(SimulatedDriver::default().with_delay(Duration::from_millis(500))));
+
+        let mut deploy = create_test_phase("deploy", &["setup"], Some("noop"));
+        deploy.spec.wait_for.as_mut().unwrap().timeout = Some("100ms".to_string());
+        let phases = vec![
+            create_test_phase("setup", &[], Some("test-slow-500")),
+            deploy,
+            create_test_phase("verify", &["deploy"], Some("noop")),
+        ];
+
+        let report = run_plan(&phases, &ExecutionOptions::parallel(2)).await.unwrap();
+        assert_eq!(report.status_of("setup"), Some(PhaseStatus::Succeeded));
+        assert_eq!(report.status_of("deploy"), Some(PhaseStatus::DependencyTimeout));
+        assert_eq!(report.status_of("verify"), Some(PhaseStatus::Skipped));
+    }
+
+    #[tokio::test]
+    async fn test_external_dependency_completed_while_waiting() {
+        let mut deploy = create_test_phase("deploy", &["executor-test-peer-phase"], Some("noop"));
+        deploy.spec.wait_for.as_mut().unwrap().timeout = Some("5s".to_string());
+        tokio::spawn(async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            crate::executor::dependencies::mark_completed("executor-test-peer-phase");
+        });
+
+        let report = run_plan(&[deploy], &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status_of("deploy"), Some(PhaseStatus::Succeeded));
+    }
+
+    #[tokio::test]
+    async fn test_external_dependency_never_completes() {
+        let mut deploy = create_test_phase("deploy", &["executor-test-missing-peer"], Some("noop"));
+        deploy.spec.wait_for.as_mut().unwrap().timeout = Some("100ms".to_string());
+
+        let report = run_plan(&[deploy], &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status_of("deploy"), Some(PhaseStatus::DependencyTimeout));
+    }
+
+    #[tokio::test]
+    async fn test_external_dependency_wait_does_not_hold_global_slot() {
+        // Leave a single global slot free
+        let slots = crate::executor::global_concurrency() as u32 - 1;
+        let held = crate::executor::GLOBAL_SLOTS.clone().acquire_many_owned(slots).await.unwrap();
+        let mut waiting = create_test_phase("slot-waiting", &["executor-test-slot-peer"], Some("noop"));
+        waiting.spec.wait_for.as_mut().unwrap().timeout = Some("10s".to_string());
+        let waiting = tokio::spawn(async move { run_plan(&[waiting], &ExecutionOptions::default()).await });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let ready = vec![create_test_phase("slot-ready", &[], Some("noop"))];
+        let report = tokio::time::timeout(Duration::from_secs(5), run_plan(&ready, &ExecutionOptions::default())).await;
+        crate::executor::dependencies::mark_completed("executor-test-slot-peer");
+        drop(held);
+        let report = report.expect("the waiting phase held the free slot").unwrap();
+        assert_eq!(report.status_of("slot-ready"), Some(PhaseStatus::Succeeded));
+        let report = waiting.await.unwrap().unwrap();
+        assert_eq!(report.status_of("slot-waiting"), Some(PhaseStatus::Succeeded));
+    }
+
+    fn with_action(mut phase: Phase, on_failure: Option<&str>, on_success: Option<&str>) -> Phase {
+        let handler = |action: &str| Handler {
+            action: Some(action.to_string()),
+            spec: None,
+        };
+        phase.spec.on_failure = on_failure.map(handler);
+        phase.spec.on_success = on_success.map(handler);
+        phase
+    }
+
+    #[tokio::test]
+    async fn test_on_failure_continue_runs_dependents() {
+        register_driver("test-fails-continue", Arc::new(SimulatedDriver::default().always_failing()));
+        let phases = vec![
+            with_action(create_test_phase("optional", &[], Some("test-fails-continue")), Some("continue"), None),
+            create_test_phase("deploy", &["optional"], Some("noop")),
+        ];
+
+        let report = run_plan(&phases, &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status_of("optional"), Some(PhaseStatus::Failed));
+        assert_eq!(report.status_of("deploy"), Some(PhaseStatus::Succeeded));
+        assert_eq!(report.status, PlanStatus::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn test_on_failure_raise_fails_plan() {
+        register_driver("test-fails-raise", Arc::new(SimulatedDriver::default().always_failing()));
+        let phases = vec![
+            with_action(create_test_phase("check", &[], Some("test-fails-raise")), Some("raise"), None),
+            create_test_phase("unrelated", &[], Some("noop")),
+        ];
+
+        let report = run_plan(&phases, &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status, PlanStatus::Failed);
+        assert_eq!(report.status_of("check"), Some(PhaseStatus::Failed));
+        assert_eq!(report.status_of("unrelated"), Some(PhaseStatus::Skipped));
+    }
+
+    #[tokio::test]
+    async fn test_on_failure_abort_cancels_running_phases() {
+        register_driver("test-fails-abort", Arc::new(SimulatedDriver::default().always_failing()));
+        register_driver("test-slow-abort", Arc::new(SimulatedDriver::default().with_delay(Duration::from_secs(30))));
+        let phases = vec![
+            create_test_phase("long", &[], Some("test-slow-abort")),
+            with_action(create_test_phase("broken", &[], Some("test-fails-abort")), Some("abort"), None),
+            create_test_phase("later", &["broken"], Some("noop")),
+            create_test_phase("queued", &[], Some("noop")),
+        ];
+
+        let started = Instant::now();
+        let report = run_plan(&phases, &ExecutionOptions::parallel(2)).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(report.status, PlanStatus::Aborted);
+        assert_eq!(report.status_of("long"), Some(PhaseStatus::Cancelled));
+        assert_eq!(report.status_of("broken"), Some(PhaseStatus::Failed));
+        assert_eq!(report.status_of("later"), Some(PhaseStatus::Skipped));
+        assert_eq!(report.status_of("queued"), Some(PhaseStatus::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn test_on_success_skip_skips_dependents() {
+        let phases = vec![
+            with_action(create_test_phase("probe", &[], Some("noop")), None, Some("skip")),
+            create_test_phase("install", &["probe"], Some("noop")),
+        ];
+
+        let report = run_plan(&phases, &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status_of("probe"), Some(PhaseStatus::Succeeded));
+        assert_eq!(report.status_of("install"), Some(PhaseStatus::Skipped));
+        assert_eq!(report.status, PlanStatus::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_rejects_unknown_action() {
+        let phase = with_action(create_test_phase("bad", &[], Some("noop")), Some("shrug"), None);
+
+        let err = run_plan(&[phase], &ExecutionOptions::default()).await.unwrap_err();
+        assert!(matches!(err, ValidationError::InvalidField { ref field, .. } if field == "onFailure.action"));
+    }
+
+    fn with_rollback(mut phase: Phase, driver: &str) -> Phase {
+        phase.spec.on_rollback = Some(Rollback {
+            driver: Some(driver.to_string()),
+            command: None,
+            http: None,
+            timeout: None,
+        });
+        phase
+    }
+
+    #[tokio::test]
+    async fn test_raise_rolls_back_succeeded_phases_in_reverse_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-rollback-recording", Arc::new(RecordingDriver { calls: calls.clone() }));
+        register_driver("test-fails-rollback", Arc::new(SimulatedDriver::default().always_failing()));
+
+        let phases = vec![
+            with_rollback(create_test_phase("network", &[], Some("noop")), "test-rollback-recording"),
+            with_rollback(create_test_phase("database", &["network"], Some("noop")), "test-rollback-recording"),
+            create_test_phase("docs", &[], Some("noop")),
+            with_action(create_test_phase("app", &["database"], Some("test-fails-rollback")), Some("raise"), None),
+        ];
+
+        let options = ExecutionOptions::default().with_plan_id("rb");
+        let report = run_plan(&phases, &options).await.unwrap();
+        assert_eq!(report.status, PlanStatus::Failed);
+        assert_eq!(*calls.lock().unwrap(), vec!["database@rb", "network@rb"]);
+        assert_eq!(report.status_of("network"), Some(PhaseStatus::RolledBack));
+        assert_eq!(report.status_of("database"), Some(PhaseStatus::RolledBack));
+        assert_eq!(report.status_of("docs"), Some(PhaseStatus::Succeeded));
+        assert_eq!(report.status_of("app"), Some(PhaseStatus::Failed));
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_phases_are_not_rolled_back() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-unchanged-rollback", Arc::new(RecordingDriver { calls: calls.clone() }));
+        register_driver("test-unchanged-fails", Arc::new(SimulatedDriver::default().always_failing()));
+
+        let phases = vec![
+            with_rollback(create_test_phase("network", &[], Some("noop")), "test-unchanged-rollback"),
+            with_rollback(create_test_phase("database", &["network"], Some("noop")), "test-unchanged-rollback"),
+            with_action(create_test_phase("app", &["database"], Some("test-unchanged-fails")), Some("raise"), None),
+        ];
+
+        let mut options = ExecutionOptions::default().with_plan_id("unch");
+        options.unchanged_phases = vec!["network".to_string()];
+        let report = run_plan(&phases, &options).await.unwrap();
+        assert_eq!(report.status, PlanStatus::Failed);
+        assert_eq!(*calls.lock().unwrap(), vec!["database@unch"]);
+        assert_eq!(report.status_of("network"), Some(PhaseStatus::Unchanged));
+        assert_eq!(report.status_of("database"), Some(PhaseStatus::RolledBack));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_describes_rollback_without_executing() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-dry-rollback", Arc::new(RecordingDriver { calls: calls.clone() }));
+
+        let phases = vec![
+            with_rollback(create_test_phase("dry-network", &[], Some("test-dry-rollback")), "test-dry-rollback"),
+            with_action(create_test_phase("dry-app", &["dry-network"], Some("test-dry-rollback")), None, Some("abort")),
+        ];
+
+        let options = ExecutionOptions::default().with_dry_run(true);
+        let report = run_plan(&phases, &options).await.unwrap();
+        assert_eq!(report.status, PlanStatus::Aborted);
+        assert!(calls.lock().unwrap().is_empty());
+        assert_eq!(report.status_of("dry-network"), Some(PhaseStatus::Succeeded));
+        let last = report.trace.last().unwrap();
+        assert_eq!((last.id.as_str(), last.action.as_str()), ("dry-network", "roll back: execute 'Test phase dry-network'"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_compensation_is_reported() {
+        register_driver("test-fails-compensation", Arc::new(SimulatedDriver::default().always_failing()));
+
+        let phases = vec![
+            with_rollback(create_test_phase("setup", &[], Some("noop")), "test-fails-compensation"),
+            with_action(create_test_phase("deploy", &["setup"], Some("test-fails-compensation")), Some("abort"), None),
+        ];
+
+        let report = run_plan(&phases, &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status, PlanStatus::Aborted);
+        assert_eq!(report.status_of("setup"), Some(PhaseStatus::RollbackFailed));
+    }
+
+    #[tokio::test]
+    async fn test_default_failure_does_not_roll_back() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-rollback-unused", Arc::new(RecordingDriver { calls: calls.clone() }));
+        register_driver("test-fails-no-rollback", Arc::new(SimulatedDriver::default().always_failing()));
+
+        let phases = vec![
+            with_rollback(create_test_phase("setup", &[], Some("noop")), "test-rollback-unused"),
+            create_test_phase("deploy", &["setup"], Some("test-fails-no-rollback")),
+        ];
+
+        let report = run_plan(&phases, &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status, PlanStatus::Failed);
+        assert_eq!(report.status_of("setup"), Some(PhaseStatus::Succeeded));
+        assert!(calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_rejects_empty_rollback() {
+        let mut phase = create_test_phase("bad", &[], Some("noop"));
+        phase.spec.on_rollback = Some(Rollback {
+            driver: None,
+            command: None,
+            http: None,
+            timeout: None,
+        });
+
+        let err = run_plan(&[phase], &ExecutionOptions::default()).await.unwrap_err();
+        assert!(matches!(err, ValidationError::InvalidField { ref field, .. } if field == "onRollback"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_describes_without_executing() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-dry-run-recording", Arc::new(RecordingDriver { calls: calls.clone() }));
+
+        let mut deploy = create_test_phase("dry-deploy", &["dry-setup"], None);
+        deploy.spec.command = Some(CommandSpec {
+            program: "kubectl".to_string(),
+            args: vec!["apply".to_string(), "-f".to_string(), "app.yaml".to_string()],
+            env: HashMap::new(),
+            working_dir: None,
+            stdin: None,
+        });
+        let phases = vec![
+            deploy,
+            create_test_phase("dry-setup", &[], Some("test-dry-run-recording")),
+        ];
+
+        let options = ExecutionOptions::default().with_dry_run(true);
+        let report = run_plan(&phases, &options).await.unwrap();
+        assert!(report.succeeded());
+        assert!(calls.lock().unwrap().is_empty());
+        assert!(!crate::executor::dependencies::is_completed("dry-setup"));
+
+        let steps: Vec<(&str, &str)> = report.trace.iter().map(|s| (s.id.as_str(), s.action.as_str())).collect();
+        assert_eq!(
+            steps,
+            vec![
+                ("dry-setup", "execute 'Test phase dry-setup'"),
+                ("dry-deploy", "run `kubectl apply -f app.yaml`"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_paused_plan_starts_no_phases_until_resumed() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-pause-recording", Arc::new(RecordingDriver { calls: calls.clone() }));
+        let phases = vec![
+            create_test_phase("pause-setup", &[], Some("test-pause-recording")),
+            create_test_phase("pause-deploy", &["pause-setup"], Some("test-pause-recording")),
+        ];
+
+        let control = RunControl::default();
+        control.pause();
+        let options = ExecutionOptions::default().with_plan_id("p").with_control(control.clone());
+        let handle = tokio::spawn(async move { run_plan(&phases, &options).await });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(calls.lock().unwrap().is_empty());
+        assert!(!handle.is_finished());
+
+        control.resume();
+        let report = handle.await.unwrap().unwrap();
+        assert!(report.succeeded());
+        assert_eq!(*calls.lock().unwrap(), vec!["pause-setup@p", "pause-deploy@p"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_interrupts_running_phase_without_rollback() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-cancel-rollback", Arc::new(RecordingDriver { calls: calls.clone() }));
+        register_driver("test-cancel-slow", Arc::new(SimulatedDriver::default().with_delay(Duration::from_secs(30))));
+        let phases = vec![
+            with_rollback(create_test_phase("cancel-first", &[], Some("noop")), "test-cancel-rollback"),
+            create_test_phase("cancel-long", &["cancel-first"], Some("test-cancel-slow")),
+            create_test_phase("cancel-next", &["cancel-long"], Some("noop")),
+        ];
+
+        let control = RunControl::default();
+        let options = ExecutionOptions::default().with_control(control.clone());
+        let started = Instant::now();
+        let handle = tokio::spawn(async move { run_plan(&phases, &options).await });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        control.cancel();
+        let report = handle.await.unwrap().unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(report.status, PlanStatus::Cancelled);
+        assert_eq!(report.status_of("cancel-first"), Some(PhaseStatus::Succeeded));
+        assert_eq!(report.status_of("cancel-long"), Some(PhaseStatus::Cancelled));
+        assert_eq!(report.status_of("cancel-next"), Some(PhaseStatus::Cancelled));
+        assert!(calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_while_waiting_for_global_slot() {
+        let slots = crate::executor::global_concurrency() as u32;
+        let held = crate::executor::GLOBAL_SLOTS.clone().acquire_many_owned(slots).await.unwrap();
+        let phases = vec![create_test_phase("slot-wait", &[], Some("noop"))];
+
+        let control = RunControl::default();
+        let options = ExecutionOptions::default().with_control(control.clone());
+        let handle = tokio::spawn(async move { run_plan(&phases, &options).await });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        control.cancel();
+        let report = tokio::time::timeout(Duration::from_secs(5), handle).await;
+        drop(held);
+        let report = report.expect("cancel was not handled while waiting for a slot").unwrap().unwrap();
+        assert_eq!(report.status, PlanStatus::Cancelled);
+        assert_eq!(report.status_of("slot-wait"), Some(PhaseStatus::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn test_completed_phases_are_not_run_again() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-resume-recording", Arc::new(RecordingDriver { calls: calls.clone() }));
+        let phases = vec![
+            create_test_phase("resume-setup", &[], Some("test-resume-recording")),
+            create_test_phase("resume-deploy", &["resume-setup"], Some("test-resume-recording")),
+        ];
+
+        let options = ExecutionOptions::default()
+            .with_plan_id("r")
+            .with_completed_phases(vec!["resume-setup".to_string()]);
+        let report = run_plan(&phases, &options).await.unwrap();
+        assert!(report.succeeded());
+        assert_eq!(report.status_of("resume-setup"), Some(PhaseStatus::Succeeded));
+        assert_eq!(*calls.lock().unwrap(), vec!["resume-deploy@r"]);
+    }
+
+    #[tokio::test]
+    async fn test_tear_down_deleted_phases_in_reverse_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-teardown-recording", Arc::new(RecordingDriver { calls: calls.clone() }));
+        register_driver("test-teardown-broken", Arc::new(SimulatedDriver::default().always_failing()));
+        let applied = vec![
+            with_rollback(create_test_phase("td-queue", &[], Some("noop")), "test-teardown-recording"),
+            with_rollback(create_test_phase("td-worker", &["td-queue"], Some("noop")), "test-teardown-recording"),
+            create_test_phase("td-plain", &[], Some("noop")),
+            create_test_phase("td-kept", &[], Some("noop")),
+        ];
+        let deleted: Vec<&Phase> = applied[..3].iter().collect();
+
+        let mut report = run_plan(&applied[3..], &ExecutionOptions::default()).await.unwrap();
+        let options = ExecutionOptions::default().with_plan_id("td");
+        crate::executor::rollback::tear_down(&applied, &deleted, &mut report, &options).await;
+        assert!(report.succeeded());
+        assert_eq!(*calls.lock().unwrap(), vec!["td-worker@td", "td-queue@td"]);
+        let torn_down: Vec<(&str, PhaseStatus)> = report.teardown.iter().map(|p| (p.id.as_str(), p.status)).collect();
+        assert_eq!(
+            torn_down,
+            vec![("td-worker", PhaseStatus::TornDown), ("td-queue", PhaseStatus::TornDown)]
+        );
+
+        let broken = vec![with_rollback(create_test_phase("td-broken", &[], Some("noop")), "test-teardown-broken")];
+        let mut report = run_plan(&applied[3..], &ExecutionOptions::default()).await.unwrap();
+        crate::executor::rollback::tear_down(&broken, &[&broken[0]], &mut report, &options).await;
+        assert_eq!(report.status, PlanStatus::Failed);
+        assert_eq!(report.teardown[0].status, PhaseStatus::TeardownFailed);
+    }
+
+    fn echo_phase(id: &str, wait_for: &[&str], line: &str) -> Phase {
+        let mut phase = create_test_phase(id, wait_for, None);
+        phase.spec.command = Some(CommandSpec {
+            program: "echo".to_string(),
+            args: vec![line.to_string()],
+            env: HashMap::new(),
+            working_dir: None,
+            stdin: None,
+        });
+        phase
+    }
+
+    #[tokio::test]
+    async fn test_outputs_are_passed_to_downstream_phases() {
+        let phases = vec![
+            echo_phase("out-init", &[], r#"{"outputs": {"cluster_id": "c-42"}}"#),
+            echo_phase(
+                "out-deploy",
+                &["out-init"],
+                r#"{"outputs": {"target": "${phases.out-init.outputs.cluster_id}"}}"#,
+            ),
+        ];
+
+        let report = run_plan(&phases, &ExecutionOptions::default()).await.unwrap();
+        assert!(report.succeeded());
+        assert_eq!(report.phases[0].outputs["cluster_id"], "c-42");
+        assert_eq!(report.phases[1].outputs["target"], "c-42");
+    }
+
+    #[tokio::test]
+    async fn test_completed_phase_outputs_come_from_options() {
+        let phases = vec![
+            echo_phase("out-resume-init", &[], r#"{"outputs": {"cluster_id": "fresh"}}"#),
+            echo_phase(
+                "out-resume-deploy",
+                &["out-resume-init"],
+                r#"{"outputs": {"target": "${phases.out-resume-init.outputs.cluster_id}"}}"#,
+            ),
+        ];
+        let mut earlier = HashMap::new();
+        earlier.insert(
+            "out-resume-init".to_string(),
+            HashMap::from([("cluster_id".to_string(), "earlier".to_string())]),
+        );
+
+        let options = ExecutionOptions::default()
+            .with_completed_phases(vec!["out-resume-init".to_string()])
+            .with_outputs(earlier);
+        let report = run_plan(&phases, &options).await.unwrap();
+        assert_eq!(report.phases[1].outputs["target"], "earlier");
+    }
+
+    #[tokio::test]
+    async fn test_unresolved_output_reference_fails_phase() {
+        let phases = vec![
+            echo_phase("out-silent", &[], "no outputs"),
+            echo_phase("out-needy", &["out-silent"], "${phases.out-silent.outputs.cluster_id}"),
+        ];
+
+        let report = run_plan(&phases, &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status_of("out-silent"), Some(PhaseStatus::Succeeded));
+        assert_eq!(report.status_of("out-needy"), Some(PhaseStatus::Failed));
+        assert_eq!(report.status, PlanStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_rejects_reference_to_phase_not_waited_for() {
+        let phases = vec![
+            echo_phase("out-a", &[], "a"),
+            echo_phase("out-b", &[], "${phases.out-a.outputs.id}"),
+        ];
+        assert!(matches!(
+            run_plan(&phases, &ExecutionOptions::default()).await,
+            Err(ValidationError::InvalidField { ref phase, .. }) if phase == "out-b"
+        ));
+
+        let mut own = echo_phase("out-own", &["out-a"], "${phases.out-a.outputs.id}");
+        own.spec.on_rollback = Some(Rollback {
+            driver: None,
+            command: own.spec.command.clone().map(|mut c| {
+                c.args = vec!["${phases.out-own.outputs.token}".to_string()];
+                c
+            }),
+            http: None,
+            timeout: None,
+        });
+        assert!(crate::executor::validate::validate_plan(&[phases[0].clone(), own]).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_on_use_phases_run_only_when_needed_or_requested() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-on-use-recording", Arc::new(RecordingDriver { calls: calls.clone() }));
+        let on_use = |id: &str, wait_for: &[&str]| {
+            let mut phase = create_test_phase(id, wait_for, Some("test-on-use-recording"));
+            phase.spec.instance_mode = Some("onUse".to_string());
+            phase
+        };
+        let phases = vec![
+            on_use("use-db", &[]),
+            create_test_phase("use-app", &["use-db"], Some("test-on-use-recording")),
+            on_use("use-debug", &[]),
+        ];
+
+        let report = run_plan(&phases, &ExecutionOptions::default()).await.unwrap();
+        assert!(report.succeeded());
+        assert_eq!(report.status_of("use-debug"), Some(PhaseStatus::Unused));
+        assert_eq!(*calls.lock().unwrap(), vec!["use-db@", "use-app@"]);
+
+        calls.lock().unwrap().clear();
+        let options = ExecutionOptions::default()
+            .with_completed_phases(vec!["use-db".to_string(), "use-app".to_string()])
+            .with_requested(vec!["use-debug".to_string()]);
+        let report = run_plan(&phases, &options).await.unwrap();
+        assert_eq!(report.status_of("use-debug"), Some(PhaseStatus::Succeeded));
+        assert_eq!(*calls.lock().unwrap(), vec!["use-debug@"]);
+
+        let options = ExecutionOptions::default().with_requested(vec!["missing".to_string()]);
+        assert!(matches!(
+            run_plan(&phases, &options).await,
+            Err(ValidationError::UnknownPhase(ref id)) if id == "missing"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_rejects_unknown_instance_mode() {
+        let mut phase = create_test_phase("mode", &[], None);
+        phase.spec.instance_mode = Some("parallel".to_string());
+        assert!(matches!(
+            run_plan(&[phase], &ExecutionOptions::default()).await,
+            Err(ValidationError::InvalidField { ref field, .. }) if field == "instance_mode"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_when_condition_skips_phase_but_not_dependents() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-when-recording", Arc::new(RecordingDriver { calls: calls.clone() }));
+        let phase = |id: &str, wait_for: &[&str], when: Option<&str>| {
+            let mut phase = create_test_phase(id, wait_for, Some("test-when-recording"));
+            phase.spec.when = when.map(str::to_string);
+            phase
+        };
+        let phases = vec![
+            phase("when-preflight", &[], None),
+            phase(
+                "when-seed",
+                &["when-preflight"],
+                Some(r#"phases.when-preflight.status == "succeeded" && params.env != "prod""#),
+            ),
+            phase("when-app", &["when-seed"], None),
+        ];
+
+        let params = |env: &str| HashMap::from([("env".to_string(), env.to_string())]);
+        let report = run_plan(&phases, &ExecutionOptions::default().with_params(params("prod")))
+            .await
+            .unwrap();
+        assert!(report.succeeded());
+        assert_eq!(report.status_of("when-seed"), Some(PhaseStatus::Skipped));
+        assert_eq!(report.status_of("when-app"), Some(PhaseStatus::Succeeded));
+        assert_eq!(*calls.lock().unwrap(), vec!["when-preflight@", "when-app@"]);
+
+        calls.lock().unwrap().clear();
+        run_plan(&phases, &ExecutionOptions::default().with_params(params("dev")))
+            .await
+            .unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec!["when-preflight@", "when-seed@", "when-app@"]);
+
+        let options = ExecutionOptions::default().with_dry_run(true).with_params(params("prod"));
+        let report = run_plan(&phases, &options).await.unwrap();
+        assert!(report.trace[1].action.starts_with("skip: when condition is false"));
+    }
+
+    #[tokio::test]
+    async fn test_run_plan_rejects_invalid_when() {
+        let mut phase = create_test_phase("when-bad", &[], None);
+        phase.spec.when = Some("params.env = 'prod'".to_string());
+        assert!(matches!(
+            run_plan(&[phase.clone()], &ExecutionOptions::default()).await,
+            Err(ValidationError::InvalidField { ref field, .. }) if field == "when"
+        ));
+
+        phase.spec.when = Some("phases.other.status == 'succeeded'".to_string());
+        let other = create_test_phase("other", &[], None);
+        assert!(matches!(
+            run_plan(&[other, phase], &ExecutionOptions::default()).await,
+            Err(ValidationError::InvalidField { ref field, .. }) if field == "when"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_matrix_instances_run_before_dependents() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-matrix-recording", Arc::new(RecordingDriver { calls: calls.clone() }));
+        let mut deploy = create_test_phase("matrix-deploy", &["matrix-build"], Some("test-matrix-recording"));
+        deploy.spec.matrix = Some(BTreeMap::from([(
+            "region".to_string(),
+            vec!["eu-west".to_string(), "us-east".to_string(), "ap-south".to_string()],
+        )]));
+        deploy.spec.when = Some("'${matrix.region}' != 'ap-south'".to_string());
+        let phases = vec![
+            create_test_phase("matrix-build", &[], Some("test-matrix-recording")),
+            deploy,
+            create_test_phase("matrix-verify", &["matrix-deploy"], Some("test-matrix-recording")),
+        ];
+
+        let report = run_plan(&phases, &ExecutionOptions::default()).await.unwrap();
+        assert!(report.succeeded());
+        assert_eq!(report.status_of("matrix-deploy[eu-west]"), Some(PhaseStatus::Succeeded));
+        assert_eq!(report.status_of("matrix-deploy[ap-south]"), Some(PhaseStatus::Skipped));
+        let calls = calls.lock().unwrap().clone();
+        assert_eq!(calls.len(), 4);
+        assert_eq!(calls[0], "matrix-build@");
+        assert_eq!(calls[3], "matrix-verify@");
+        assert!(calls.contains(&"matrix-deploy[us-east]@".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_approval_gate_waits_for_decision() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        register_driver("test-approval-recording", Arc::new(RecordingDriver { calls: calls.clone() }));
+        let mut gate = create_test_phase("approval-gate", &["approval-staging"], None);
+        gate.spec.approval = Some(ApprovalSpec::default());
+        let phases = vec![
+            create_test_phase("approval-staging", &[], Some("test-approval-recording")),
+            gate,
+            create_test_phase("approval-production", &["approval-gate"], Some("test-approval-recording")),
+        ];
+
+        let control = RunControl::default();
+        let options = ExecutionOptions::default().with_control(control.clone());
+        let task_phases = phases.clone();
+        let task = tokio::spawn(async move { run_plan(&task_phases, &options).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(*calls.lock().unwrap(), vec!["approval-staging@"]);
+
+        control.decide("approval-gate", ApprovalDecision { approved: true, ..Default::default() });
+        let report = task.await.unwrap().unwrap();
+        assert!(report.succeeded());
+        assert_eq!(*calls.lock().unwrap(), vec!["approval-staging@", "approval-production@"]);
+
+        let control = RunControl::default();
+        control.decide("approval-gate", ApprovalDecision::default());
+        let report = run_plan(&phases, &ExecutionOptions::default().with_control(control))
+            .await
+            .unwrap();
+        assert_eq!(report.status, PlanStatus::Failed);
+        assert_eq!(report.status_of("approval-gate"), Some(PhaseStatus::Failed));
+        assert_eq!(report.status_of("approval-production"), Some(PhaseStatus::Skipped));
+
+        // Without a decision the gate fails once its timeout passes, and
+        // outside a run it cannot be decided at all
+        let mut timed = phases.clone();
+        timed[1].spec.approval = Some(ApprovalSpec {
+            timeout: Some("20ms".to_string()),
+            message: None,
+        });
+        let options = ExecutionOptions::default().with_control(RunControl::default());
+        assert_eq!(run_plan(&timed, &options).await.unwrap().status_of("approval-gate"), Some(PhaseStatus::Failed));
+        let report = run_plan(&phases, &ExecutionOptions::default()).await.unwrap();
+        assert_eq!(report.status_of("approval-gate"), Some(PhaseStatus::Failed));
+    }
+}
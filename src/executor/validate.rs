@@ -0,0 +1,221 @@
+use crate::executor::condition;
+use crate::executor::dependencies::dependencies;
+use crate::executor::driver;
+use crate::executor::hooks::HandlerAction;
+use crate::executor::instance::InstanceMode;
+use crate::executor::outputs;
+use crate::executor::retry::RetryPolicy;
+use crate::executor::rollback::compensation_phase;
+use crate::executor::scheduler::{topological_order, ScheduleError};
+use crate::executor::secrets;
+use crate::model::{Handler, Phase};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Reasons a plan is rejected before execution
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    Schedule(ScheduleError),
+    UnknownDriver { phase: String, driver: String },
+    InvalidDriverConfig { phase: String, driver: String, message: String },
+    InvalidField { phase: String, field: String, message: String },
+    /// A phase was requested that the plan does not contain
+    UnknownPhase(String),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::Schedule(e) => write!(f, "{}", e),
+            ValidationError::UnknownDriver { phase, driver } => {
+                write!(f, "phase '{}' uses unknown driver '{}'", phase, driver)
+            }
+            ValidationError::InvalidDriverConfig { phase, driver, message } => {
+                write!(f, "phase '{}' is invalid for driver '{}': {}", phase, driver, message)
+            }
+            ValidationError::InvalidField { phase, field, message } => {
+                write!(f, "phase '{}' has invalid {}: {}", phase, field, message)
+            }
+            ValidationError::UnknownPhase(id) => {
+                write!(f, "requested phase '{}' is not part of the plan", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<ScheduleError> for ValidationError {
+    fn from(e: ScheduleError) -> Self {
+        ValidationError::Schedule(e)
+    }
+}
+
+/// Check that a plan can be executed, returning phase ids in execution order
+pub fn validate_plan(phases: &[Phase]) -> Result<Vec<String>, ValidationError> {
+    for phase in phases {
+        let name = driver::driver_name(phase);
+        let Some(phase_driver) = driver::get_driver(name) else {
+            return Err(ValidationError::UnknownDriver {
+                phase: phase.id.clone(),
+                driver: name.to_string(),
+            });
+        };
+        if let Err(message) = phase_driver.validate(phase) {
+            return Err(ValidationError::InvalidDriverConfig {
+                phase: phase.id.clone(),
+                driver: name.to_string(),
+                message,
+            });
+        }
+
+        if let Some(Err(message)) = phase.spec.instance_mode.as_deref().map(InstanceMode::parse) {
+            return Err(ValidationError::InvalidField {
+                phase: phase.id.clone(),
+                field: "instance_mode".to_string(),
+                message,
+            });
+        }
+        check_duration(phase, "timeout", phase.spec.timeout.as_deref())?;
+        check_duration(phase, "attempt_timeout", phase.spec.attempt_timeout.as_deref())?;
+        if let Some(approval) = &phase.spec.approval {
+            check_duration(phase, "approval.timeout", approval.timeout.as_deref())?;
+        }
+        if let Err(message) = RetryPolicy::from_spec(phase.spec.retry.as_ref()) {
+            return Err(ValidationError::InvalidField {
+                phase: phase.id.clone(),
+                field: "retry".to_string(),
+                message,
+            });
+        }
+        check_action(phase, "onFailure", phase.spec.on_failure.as_ref())?;
+        check_action(phase, "onSuccess", phase.spec.on_success.as_ref())?;
+        check_rollback(phase)?;
+        if let Err(message) = secrets::check(phase) {
+            return Err(ValidationError::InvalidField {
+                phase: phase.id.clone(),
+                field: "secrets".to_string(),
+                message,
+            });
+        }
+    }
+
+    let order = topological_order(phases)?;
+    let by_id: HashMap<&str, &Phase> = phases.iter().map(|p| (p.id.as_str(), p)).collect();
+    for phase in phases {
+        check_output_references(phase, &by_id)?;
+        check_condition(phase, &by_id)?;
+    }
+    Ok(order)
+}
+
+/// Check that every phase requested for a run is part of the plan
+pub fn validate_requested(phases: &[Phase], requested: &[String]) -> Result<(), ValidationError> {
+    match requested.iter().find(|id| !phases.iter().any(|p| &p.id == *id)) {
+        Some(id) => Err(ValidationError::UnknownPhase(id.clone())),
+        None => Ok(()),
+    }
+}
+
+fn check_duration(phase: &Phase, field: &str, value: Option<&str>) -> Result<(), ValidationError> {
+    match value.map(humantime::parse_duration) {
+        Some(Err(e)) => Err(ValidationError::InvalidField {
+            phase: phase.id.clone(),
+            field: field.to_string(),
+            message: e.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn check_action(phase: &Phase, field: &str, handler: Option<&Handler>) -> Result<(), ValidationError> {
+    match handler.and_then(|h| h.action.as_deref()).map(HandlerAction::parse) {
+        Some(Err(message)) => Err(ValidationError::InvalidField {
+            phase: phase.id.clone(),
+            field: format!("{}.action", field),
+            message,
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn check_rollback(phase: &Phase) -> Result<(), ValidationError> {
+    let (Some(rollback), Some(compensation)) = (&phase.spec.on_rollback, compensation_phase(phase)) else {
+        return Ok(());
+    };
+    let invalid = |message: String| ValidationError::InvalidField {
+        phase: phase.id.clone(),
+        field: "onRollback".to_string(),
+        message,
+    };
+
+    if rollback.driver.is_none() && rollback.command.is_none() && rollback.http.is_none() {
+        return Err(invalid("requires a driver, command or http step".to_string()));
+    }
+    let name = driver::driver_name(&compensation);
+    let Some(phase_driver) = driver::get_driver(name) else {
+        return Err(invalid(format!("unknown driver '{}'", name)));
+    };
+    phase_driver.validate(&compensation).map_err(invalid)?;
+    check_duration(phase, "onRollback.timeout", rollback.timeout.as_deref())
+}
+
+/// A phase may only reference outputs of phases it (transitively) waits
+/// for; its `onRollback` step may also reference its own outputs
+fn check_output_references(phase: &Phase, by_id: &HashMap<&str, &Phase>) -> Result<(), ValidationError> {
+    let upstream = upstream_phases(phase, by_id);
+    let rollback_refs = compensation_phase(phase)
+        .map(|c| outputs::references(&c))
+        .unwrap_or_default();
+    let body_refs = outputs::references(phase).into_iter().map(|r| (r, false));
+
+    for ((id, name), in_rollback) in body_refs.chain(rollback_refs.into_iter().map(|r| (r, true))) {
+        if upstream.contains(id.as_str()) || (in_rollback && id == phase.id) {
+            continue;
+        }
+        return Err(ValidationError::InvalidField {
+            phase: phase.id.clone(),
+            field: "output reference".to_string(),
+            message: format!(
+                "'{}' refers to phase '{}', which it does not wait for",
+                outputs::reference(&id, &name),
+                id
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// `when` must parse, and may only refer to phases `phase` waits for
+fn check_condition(phase: &Phase, by_id: &HashMap<&str, &Phase>) -> Result<(), ValidationError> {
+    let Some(source) = &phase.spec.when else {
+        return Ok(());
+    };
+    let invalid = |message: String| ValidationError::InvalidField {
+        phase: phase.id.clone(),
+        field: "when".to_string(),
+        message,
+    };
+
+    let expr = condition::parse(source).map_err(invalid)?;
+    let upstream = upstream_phases(phase, by_id);
+    match expr.phases().into_iter().find(|id| !upstream.contains(id)) {
+        Some(id) => Err(invalid(format!("refers to phase '{}', which it does not wait for", id))),
+        None => Ok(()),
+    }
+}
+
+/// Ids of the plan phases `phase` waits for, directly or through others
+fn upstream_phases<'a>(phase: &'a Phase, by_id: &HashMap<&str, &'a Phase>) -> HashSet<&'a str> {
+    let mut upstream = HashSet::new();
+    let mut stack: Vec<&str> = dependencies(phase).iter().map(String::as_str).collect();
+    while let Some(id) = stack.pop() {
+        let Some(dep) = by_id.get(id) else {
+            continue;
+        };
+        if upstream.insert(dep.id.as_str()) {
+            stack.extend(dependencies(dep).iter().map(String::as_str));
+        }
+    }
+    upstream
+}
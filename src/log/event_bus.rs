@@ -5,9 +5,30 @@ use serde::{Deserialize, Serialize};
 pub enum Event {
     PhaseReceived(String),
     PhaseExecuted { id: String, success: bool },
+    PhaseAttempt { id: String, attempt: u32, success: bool },
+    RollbackStarted { phases: Vec<String> },
+    PhaseRolledBack { id: String, success: bool },
+    PhaseTornDown { id: String, success: bool },
+    /// The phase's `when` condition was false
+    PhaseSkipped { id: String, reason: String },
+    /// An approval gate is waiting for a decision
+    ApprovalRequested { id: String, message: Option<String> },
+    /// An operator decided an approval gate of a run
+    PhaseApproved { run_id: String, phase_id: String, approver: Option<String>, comment: Option<String> },
+    PhaseRejected { run_id: String, phase_id: String, approver: Option<String>, comment: Option<String> },
     DiffComputed { adds: usize, updates: usize, deletes: usize },
     PlanSubmitted { plan_id: String, phases_count: usize },
     PlanApplied { plan_id: String },
+    RunPaused { run_id: String },
+    RunResumed { run_id: String },
+    RunCancelled { run_id: String },
+    /// An `onUse` phase of a finished run was requested; the new run is
+    /// recorded with its plan
+    PhaseRequested { run_id: String, phase_id: String },
+    ScheduleCreated { schedule_id: String },
+    /// A schedule came due and started a run
+    /// `run_id` is absent when the plan was only simulated, without Redis
+    ScheduleTriggered { schedule_id: String, run_id: Option<String> },
     DiffResult { plan_id: String, changes: Vec<String> },
     Error(String),
 }
@@ -78,14 +78,15 @@ impl LogStorage {
     ) -> Result<Vec<LogEntry>, Box<dyn std::error::Error + Send + Sync>> {
         // Get log IDs from index (most recent first)
         let log_ids: Vec<String> = self.get_log_ids_from_index(limit.unwrap_or(100)).await?;
+        let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
         
         let mut logs = Vec::new();
         for log_id in log_ids {
-            let key = format!("{}{}", LOGS_KEY_PREFIX, log_id);
+            let key = format!("{}:{}{}", tenant, LOGS_KEY_PREFIX, log_id);
             if let Ok(Some(entry)) = get_json::<LogEntry>(&self.client, &key).await {
                 // Apply filters
-                let matches_plan = plan_id.map_or(true, |pid| entry.plan_id.as_deref() == Some(pid));
-                let matches_phase = phase_id.map_or(true, |pid| entry.phase_id.as_deref() == Some(pid));
+                let matches_plan = plan_id.is_none_or(|pid| entry.plan_id.as_deref() == Some(pid));
+                let matches_phase = phase_id.is_none_or(|pid| entry.phase_id.as_deref() == Some(pid));
                 
                 if matches_plan && matches_phase {
                     logs.push(entry);
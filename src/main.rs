@@ -1,19 +1,11 @@
-mod log;
-mod model;
-mod diff;
-mod executor;
-mod state;
-mod config;
-mod nats;
-
 use axum::{routing::{get, post}, Router};
 use std::net::SocketAddr;
 use tokio::net::TcpListener;
-use routes::plan::{submit_plan, AppState};
-use log::{init_logger, LoggingService};
+use planter::{model, routes, state};
+use planter::routes::plan::{submit_plan, AppState};
+use planter::log::{init_logger, LoggingService};
 use sha2::{Sha256, Digest};
 
-mod routes;
 #[tokio::main]
 async fn main() {
     use axum::extract::State;
@@ -21,7 +13,7 @@ async fn main() {
     use axum::Json;
     use std::sync::Arc;
     use tokio::sync::Notify;
-    use crate::state::tracker::{save_state_file, load_state_file};
+    use planter::state::tracker::{save_state_file, load_state_file};
 
     let shutdown_notify = Arc::new(Notify::new());
 
@@ -46,9 +38,12 @@ async fn main() {
     // Initialize NATS client if configured
     let nats_client = if let Ok(nats_url) = std::env::var("NATS_URL") {
         println!("Connecting to NATS at {}", nats_url);
-        match crate::nats::client::NatsClient::connect(&nats_url).await {
+        match planter::nats::client::NatsClient::connect(&nats_url).await {
             Ok(client) => {
                 println!("Connected to NATS successfully");
+                if let Err(e) = client.follow_peer_state().await {
+                    eprintln!("Failed to subscribe to peer phase state: {}", e);
+                }
                 Some(std::sync::Arc::new(client))
             }
             Err(e) => {
@@ -94,7 +89,7 @@ async fn main() {
 
     if let Some(phases) = initial_state {
         if let Some(redis_client) = &redis_client {
-            crate::state::tracker::store_current_plan(redis_client, &phases).await;
+            planter::state::tracker::store_current_plan(redis_client, &phases).await;
         }
     }
 
@@ -113,7 +108,7 @@ async fn main() {
                                     Ok(json) => {
                                         if let Some(phases) = json.get("phases").and_then(|p| serde_json::from_value::<Vec<model::Phase>>(p.clone()).ok()) {
                                             if let Some(redis_client) = &redis_client {
-                                                crate::state::tracker::store_current_plan(redis_client, &phases).await;
+                                                planter::state::tracker::store_current_plan(redis_client, &phases).await;
                                                 println!("Periodic sync: updated local state with {} phases", json.get("phases").and_then(|p| p.as_array()).map_or(0, |arr| arr.len()));
                                             }
                                         } else {
@@ -140,7 +135,7 @@ async fn main() {
         async move {
             // Save state to file
             if let Some(redis_client) = &app_state.redis_client {
-                if let Some(phases) = crate::state::tracker::load_current_plan(redis_client).await {
+                if let Some(phases) = planter::state::tracker::load_current_plan(redis_client).await {
                     let _ = save_state_file(&phases);
                 }
             }
@@ -154,7 +149,7 @@ async fn main() {
         let loaded = load_state_file();
         if let Some(phases) = loaded {
             if let Some(redis_client) = &app_state.redis_client {
-                crate::state::tracker::store_current_plan(redis_client, &phases).await;
+                planter::state::tracker::store_current_plan(redis_client, &phases).await;
                 return Json(serde_json::json!({ "status": "reloaded", "count": phases.len() }));
             }
         }
@@ -162,42 +157,6 @@ async fn main() {
     }
     init_logger();
 
-    // Initialize Redis client if configured
-    let redis_client = if let Ok(redis_url) = std::env::var("REDIS_URL") {
-        println!("Connecting to Redis at {}", redis_url);
-        match state::redis::connect(&redis_url).await {
-            Ok(client) => {
-                println!("Redis client initialized successfully");
-                Some(std::sync::Arc::new(client))
-            }
-            Err(e) => {
-                eprintln!("Failed to connect to Redis: {}", e);
-                None
-            }
-        }
-    } else {
-        println!("No REDIS_URL configured - running in stateless mode");
-        None
-    };
-
-    // Initialize NATS client if configured
-    let nats_client = if let Ok(nats_url) = std::env::var("NATS_URL") {
-        println!("Connecting to NATS at {}", nats_url);
-        match crate::nats::client::NatsClient::connect(&nats_url).await {
-            Ok(client) => {
-                println!("Connected to NATS successfully");
-                Some(std::sync::Arc::new(client))
-            }
-            Err(e) => {
-                eprintln!("Failed to connect to NATS: {}", e);
-                None
-            }
-        }
-    } else {
-        println!("No NATS_URL configured - skipping NATS integration");
-        None
-    };
-
     // Get prefix from environment variable
     let prefix = std::env::var("PLANTER_PREFIX").unwrap_or_else(|_| "".to_string());
     let prefix = prefix.trim_end_matches('/').to_string();
@@ -213,13 +172,51 @@ async fn main() {
     };
     // Expose tenant key for tracker and storage
     std::env::set_var("TENANT_KEY", &tenant_key);
+
+    // Phases completed by earlier runs satisfy dependencies in new plans
+    if let Some(redis_client) = &redis_client {
+        planter::executor::restore_completed_phases(redis_client).await;
+    }
+
     let app_state = AppState {
         redis_client: redis_client.clone(),
         nats_client: nats_client.clone(),
         logging_service: LoggingService::new(redis_client.clone().map(|c| (*c).clone())),
         tenant_key: tenant_key.clone(),
+        run_manager: match &redis_client {
+            Some(client) => planter::state::runs::RunManager::with_checkpoints(client.clone(), &planter::config::replica_id()),
+            None => planter::state::runs::RunManager::default(),
+        },
+        schedule_manager: match &redis_client {
+            Some(client) => planter::state::schedules::ScheduleManager::with_redis(client.clone()),
+            None => planter::state::schedules::ScheduleManager::with_file(planter::config::schedules_file_path()),
+        },
+        plan_lock: match &redis_client {
+            Some(client) => planter::state::lock::PlanLock::with_redis(client.clone()),
+            None => planter::state::lock::PlanLock::default(),
+        },
+        idempotency: match &redis_client {
+            Some(client) => planter::state::idempotency::IdempotencyStore::with_redis(client.clone()),
+            None => planter::state::idempotency::IdempotencyStore::default(),
+        },
     };
 
+    // Runs interrupted by the last shutdown resume from their checkpoints
+    let auto_resume = matches!(
+        std::env::var("PLANTER_RESUME_RUNS").as_deref(),
+        Ok("1") | Ok("true")
+    );
+    routes::runs::restore_interrupted_runs(&app_state, auto_resume).await;
+    // Runs left behind by replicas that stopped renewing their leases
+    routes::runs::spawn_orphan_adoption(app_state.clone(), auto_resume);
+
+    // Schedules registered before the last shutdown carry on
+    let schedules = app_state.schedule_manager.load().await;
+    if schedules > 0 {
+        println!("Loaded {} schedules", schedules);
+    }
+    routes::schedules::spawn_scheduler(app_state.clone());
+
     // Helper to prepend prefix to a route
     let route = |path: &str| {
         if prefix.is_empty() {
@@ -237,6 +234,17 @@ async fn main() {
         .route(&route("/logs"), get(routes::logs::get_logs))
         .route(&route("/phases/:id"), get(routes::phases::get_phase))
         .route(&route("/apply"), post(routes::apply::apply_plan))
+        .route(&route("/runs"), get(routes::runs::list_runs))
+        .route(&route("/runs/:id"), get(routes::runs::get_run))
+        .route(&route("/runs/:id/phases/:phase/attempts"), get(routes::runs::get_phase_attempts))
+        .route(&route("/runs/:id/phases/:phase/use"), post(routes::runs::use_phase))
+        .route(&route("/runs/:id/phases/:phase/approve"), post(routes::runs::approve_phase))
+        .route(&route("/runs/:id/phases/:phase/reject"), post(routes::runs::reject_phase))
+        .route(&route("/runs/:id/pause"), post(routes::runs::pause_run))
+        .route(&route("/runs/:id/resume"), post(routes::runs::resume_run))
+        .route(&route("/runs/:id/cancel"), post(routes::runs::cancel_run))
+        .route(&route("/schedules"), get(routes::schedules::list_schedules).post(routes::schedules::create_schedule))
+        .route(&route("/schedules/:id"), get(routes::schedules::get_schedule).delete(routes::schedules::delete_schedule))
         .route(&route("/health"), get(routes::health::health_check))
         .route(&route("/ready"), get(routes::health::readiness_check))
         .route(&route("/metrics"), get(routes::health::metrics))
@@ -259,7 +267,7 @@ async fn main() {
             _ = sigterm.recv() => {
                 println!("Received SIGTERM, saving state and shutting down...");
                 if let Some(redis_client) = &app_state.redis_client {
-                    if let Some(phases) = crate::state::tracker::load_current_plan(redis_client).await {
+                    if let Some(phases) = planter::state::tracker::load_current_plan(redis_client).await {
                         let _ = save_state_file(&phases);
                     }
                 }
@@ -268,7 +276,7 @@ async fn main() {
             _ = sigint.recv() => {
                 println!("Received SIGINT, saving state and shutting down...");
                 if let Some(redis_client) = &app_state.redis_client {
-                    if let Some(phases) = crate::state::tracker::load_current_plan(redis_client).await {
+                    if let Some(phases) = planter::state::tracker::load_current_plan(redis_client).await {
                         let _ = save_state_file(&phases);
                     }
                 }
@@ -279,7 +287,7 @@ async fn main() {
                 let loaded = load_state_file();
                 if let Some(phases) = loaded {
                     if let Some(redis_client) = &app_state.redis_client {
-                        crate::state::tracker::store_current_plan(redis_client, &phases).await;
+                        planter::state::tracker::store_current_plan(redis_client, &phases).await;
                     }
                 }
             }
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -9,23 +9,121 @@ pub struct Phase {
     pub spec: PhaseSpec,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct PhaseSpec {
     pub description: String,
     pub selector: Selector,
     #[serde(default)]
     pub instance_mode: Option<String>,
+    /// Name of the registered driver that executes this phase
+    #[serde(default)]
+    pub driver: Option<String>,
+    /// Process to run when using the `command` driver
+    #[serde(default)]
+    pub command: Option<CommandSpec>,
+    /// Request to send when using the `http` driver
+    #[serde(default)]
+    pub http: Option<HttpSpec>,
+    /// Upper bound on the whole phase, across all attempts (e.g. "10m")
+    #[serde(default)]
+    pub timeout: Option<String>,
+    /// Upper bound on a single attempt (e.g. "30s")
+    #[serde(default)]
+    pub attempt_timeout: Option<String>,
     #[serde(default)]
     pub wait_for: Option<WaitFor>,
+    /// Condition the phase runs under, evaluated just before it would
+    /// start (see [`crate::executor::condition`]); skipped when false
+    #[serde(default)]
+    pub when: Option<String>,
+    /// Parameter values the phase fans out over: one instance per
+    /// combination, with the values available as `${matrix.<name>}`
+    /// (see [`crate::executor::matrix`])
+    #[serde(default)]
+    pub matrix: Option<BTreeMap<String, Vec<String>>>,
+    /// Makes the phase an approval gate: instead of running a driver it
+    /// waits for an operator to approve or reject it
+    #[serde(default)]
+    pub approval: Option<ApprovalSpec>,
     #[serde(default)]
     pub retry: Option<Retry>,
     #[serde(default, rename = "onFailure")]
     pub on_failure: Option<Handler>,
     #[serde(default, rename = "onSuccess")]
     pub on_success: Option<Handler>,
+    /// Compensation run when a later failure rolls the plan back
+    #[serde(default, rename = "onRollback")]
+    pub on_rollback: Option<Rollback>,
+    /// Where the phase publishes named outputs besides its stdout
+    #[serde(default)]
+    pub outputs: Option<OutputSpec>,
+    /// Secrets the phase uses, by name: referenced as `${secrets.<name>}`
+    /// and, for commands, exported as environment variables (see
+    /// [`crate::executor::secrets`])
+    #[serde(default)]
+    pub secrets: Option<BTreeMap<String, SecretRef>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandSpec {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    #[serde(default)]
+    pub stdin: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpSpec {
+    pub url: String,
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
+    /// Status codes treated as success; any 2xx when empty
+    #[serde(default)]
+    pub success_codes: Vec<u16>,
+}
+
+/// Named values a phase hands to downstream phases, which reference them
+/// as `${phases.<id>.outputs.<name>}`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutputSpec {
+    /// JSON object file the phase writes its outputs to
+    #[serde(default)]
+    pub file: Option<String>,
+}
+
+/// Where a secret's value is read from when its phase executes; exactly
+/// one of the two is set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SecretRef {
+    /// File under `PLANTER_ROOT/secrets`
+    #[serde(default)]
+    pub file: Option<String>,
+    /// Environment variable of the Planter process
+    #[serde(default)]
+    pub env: Option<String>,
+}
+
+/// Settings of an approval gate
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalSpec {
+    /// How long to wait for a decision before the gate fails (e.g. "24h")
+    #[serde(default)]
+    pub timeout: Option<String>,
+    /// Shown to whoever is asked to approve
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Selector {
     pub match_labels: HashMap<String, String>,
 }
@@ -38,10 +136,40 @@ pub struct WaitFor {
     pub timeout: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Retry {
     #[serde(default)]
     pub max_attempts: Option<u32>,
+    /// Delay before the first retry (e.g. "1s"); retries are immediate when unset
+    #[serde(default)]
+    pub initial_delay: Option<String>,
+    /// Factor applied to the delay after each retry; 1.0 keeps it fixed
+    #[serde(default)]
+    pub backoff_multiplier: Option<f64>,
+    /// Upper bound on the delay between attempts
+    #[serde(default)]
+    pub max_delay: Option<String>,
+    /// Randomise each delay by up to this fraction (0.0 - 1.0)
+    #[serde(default)]
+    pub jitter: Option<f64>,
+    /// Only retry on these failures: "timeout", "failure", "exit:<code>", "http:<status>"
+    #[serde(default)]
+    pub retry_on: Vec<String>,
+}
+
+/// Compensating step for a phase that already succeeded. It is executed
+/// like a phase body: by `driver`, or inferred from `command` / `http`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rollback {
+    #[serde(default)]
+    pub driver: Option<String>,
+    #[serde(default)]
+    pub command: Option<CommandSpec>,
+    #[serde(default)]
+    pub http: Option<HttpSpec>,
+    /// Upper bound on the compensation step (e.g. "1m")
+    #[serde(default)]
+    pub timeout: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -68,4 +196,5 @@ pub struct Notify {
 }
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests;
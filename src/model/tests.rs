@@ -25,6 +25,7 @@ mod tests {
                 }),
                 retry: Some(Retry {
                     max_attempts: Some(3),
+                    ..Default::default()
                 }),
                 on_failure: Some(Handler {
                     action: Some("continue".to_string()),
@@ -41,7 +42,7 @@ mod tests {
                         }),
                     }),
                 }),
-                on_success: None,
+                ..Default::default()
             },
         };
 
@@ -121,6 +122,10 @@ mod tests {
                             "status": "success"
                         }
                     }
+                },
+                "onRollback": {
+                    "command": { "program": "./teardown.sh" },
+                    "timeout": "2m"
                 }
             }
         });
@@ -162,6 +167,11 @@ mod tests {
         assert_eq!(on_success.action, Some("log".to_string()));
         let success_spec = on_success.spec.as_ref().unwrap();
         assert_eq!(success_spec.message, vec!["Phase completed successfully".to_string()]);
+
+        // Test on_rollback
+        let on_rollback = phase.spec.on_rollback.as_ref().unwrap();
+        assert_eq!(on_rollback.command.as_ref().unwrap().program, "./teardown.sh");
+        assert_eq!(on_rollback.timeout, Some("2m".to_string()));
     }
 
     #[test]
@@ -1,7 +1,9 @@
 use async_nats::{Client, ConnectOptions};
+use futures::StreamExt;
 use std::time::Duration;
-// use tokio_stream::StreamExt;  // not needed here
+use super::messages::StateMessage;
 use super::NatsSession;
+use crate::executor::dependencies;
 
 /// NATS client wrapper for PMP
 pub struct NatsClient {
@@ -45,10 +47,7 @@ impl NatsClient {
     /// Check connection health
     pub async fn health_check(&self) -> bool {
         // Try to publish to a health check subject
-        match self.client.publish("health.check", "ping".into()).await {
-            Ok(_) => true,
-            Err(_) => false,
-        }
+        self.client.publish("health.check", "ping".into()).await.is_ok()
     }
 
     /// Get connection info
@@ -61,4 +60,20 @@ impl NatsClient {
         let sub = self.client.subscribe("plan.session.>").await?;
         Ok(sub)
     }
+
+    /// Track phases reported complete by runtime peers on any session's
+    /// state subject, so local phases waiting on them can proceed
+    pub async fn follow_peer_state(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut sub = self.client.subscribe("plan.session.*.state").await?;
+        tokio::spawn(async move {
+            while let Some(msg) = sub.next().await {
+                if let Ok(state) = serde_json::from_slice::<StateMessage>(&msg.payload) {
+                    if state.status == "complete" {
+                        dependencies::mark_completed(&state.phase_id);
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
 }
@@ -1,25 +1,52 @@
 use axum::{
     extract::{Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
 use serde_json::Value;
 use std::process::{Command, Stdio};
-use crate::routes::plan::{submit_plan, AppState};
+use crate::routes::plan::{idempotent, submit_plan, AppState, PlanParams};
+use crate::state::idempotency::fingerprint;
+use crate::state::lock::ConflictPolicy;
 
-#[derive(Debug, serde::Deserialize)]
+/// Query parameters, named like those of `POST /plan`. The snake_case
+/// `dry_run` and `validate_only` are still accepted.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ManifestParams {
+    #[serde(alias = "dry_run")]
     pub dry_run: Option<bool>,
+    #[serde(alias = "validate_only")]
     pub validate_only: Option<bool>,
+    pub max_concurrency: Option<usize>,
+    pub incremental: Option<bool>,
+    #[serde(rename = "use")]
+    pub use_phases: Option<String>,
+    pub params: Option<String>,
+    pub on_conflict: Option<ConflictPolicy>,
 }
 
 /// Handler for POST /manifest
 pub async fn submit_manifest(
     State(state): State<AppState>,
     Query(params): Query<ManifestParams>,
+    headers: HeaderMap,
     body: String,
 ) -> impl IntoResponse {
+    let request = fingerprint(&(&params, &body));
+    idempotent(&state.clone(), "manifest", &headers, &request, async move {
+        render_manifest(state, params, body).await.into_response()
+    })
+    .await
+}
+
+/// Render a manifest with Janet and submit the resulting plan
+async fn render_manifest(
+    state: AppState,
+    params: ManifestParams,
+    body: String,
+) -> (StatusCode, Json<Value>) {
     // Create a temp directory and write plan.yaml for Janet
     let tmp_dir = match tempfile::tempdir() {
         Ok(dir) => dir,
@@ -126,21 +153,46 @@ pub async fn submit_manifest(
         }
     };
 
-    // Use dry_run param if present
-    // (You can extend submit_plan to accept dry_run if needed)
-    let _plan_response = submit_plan(State(state.clone()), Json(plan_json)).await.into_response();
-    // Instead of serializing the full response, just indicate success and timestamp
+    let dry_run = params.dry_run.unwrap_or(false);
+    let plan_response = submit_plan(
+        State(state.clone()),
+        Query(PlanParams {
+            max_concurrency: params.max_concurrency,
+            dry_run: Some(dry_run),
+            incremental: params.incremental,
+            use_phases: params.use_phases,
+            params: params.params,
+            on_conflict: params.on_conflict,
+        }),
+        HeaderMap::new(),
+        Json(plan_json),
+    ).await.into_response();
+
+    // Pass the /plan outcome through: a refused plan keeps its status and
+    // body, so it is not mistaken for a success (nor replayed as one), and
+    // an accepted one keeps its `202` and `run_id`
+    let status = plan_response.status();
+    let plan_response = axum::body::to_bytes(plan_response.into_body(), usize::MAX)
+        .await
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok())
+        .unwrap_or(Value::Null);
+    if !status.is_success() {
+        return (status, Json(plan_response));
+    }
+
     (
-        StatusCode::OK,
+        status,
         Json(serde_json::json!({
             "status": "ok",
             "phases_extracted": phases.as_array().map(|a| a.len()).unwrap_or(0),
             "from": "janet",
+            "run_id": plan_response.get("run_id"),
             "execution": {
-                "dry_run": params.dry_run.unwrap_or(false),
+                "dry_run": dry_run,
                 "started_at": chrono::Utc::now().to_rfc3339(),
             },
-            "plan_response": "forwarded to /plan"
+            "plan_response": plan_response
         }))
     )
 }
@@ -6,3 +6,5 @@ pub mod logs;
 pub mod phases;
 pub mod health;
 pub mod apply;
+pub mod runs;
+pub mod schedules;
@@ -4,6 +4,7 @@ use axum::{
     response::IntoResponse,
     Json,
 };
+use crate::executor::attempt::AttemptRecord;
 use crate::routes::plan::AppState;
 
 /// Handler for GET /phases/:id
@@ -15,11 +16,13 @@ pub async fn get_phase(
     match state.logging_service.get_phase_logs(&phase_id).await {
         Ok(logs) => {
             let phase_info = if !logs.is_empty() {
+                let attempts: Vec<AttemptRecord> = logs.iter().filter_map(AttemptRecord::from_log).collect();
                 serde_json::json!({
                     "id": phase_id,
                     "status": "found",
                     "logs_count": logs.len(),
                     "logs": logs,
+                    "attempts": attempts,
                     "last_activity": logs.first().map(|l| l.timestamp)
                 })
             } else {
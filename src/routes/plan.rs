@@ -1,19 +1,35 @@
 use axum::{
-    extract::{Json, State},
-    response::IntoResponse,
-    http::StatusCode,
+    body::Body,
+    extract::{Json, Query, State},
+    response::{IntoResponse, Response},
+    http::{HeaderMap, HeaderValue, StatusCode},
 };
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
 
 use crate::log::{log_event, Event, LoggingService};
 use crate::model::Phase;
-use crate::executor::execute_plan;
+use crate::executor::control::ControlState;
+use crate::executor::{execute_plan, matrix, run_plan, ExecutionOptions};
+use crate::executor::validate::{validate_plan, validate_requested};
+use crate::state::idempotency::{fingerprint, IdempotencyRecord, IdempotencyStore};
+use crate::state::lock::{ConflictPolicy, LockError, PlanLock, LOCK_RENEW_INTERVAL};
 use crate::state::redis::RedisClient;
+use crate::state::runs::{Run, RunManager, RunSettings};
+use crate::state::schedules::ScheduleManager;
 use crate::diff::{diff_plans, DiffResult};
 use crate::state::tracker::{load_applied_plan, store_current_plan};
 use crate::nats::client::NatsClient;
 
+/// Header with a client-chosen key that makes a submission idempotent
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Header marking a response replayed for a repeated idempotency key
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
 #[allow(dead_code)]
 #[derive(Clone)]
 pub struct AppState {
@@ -22,14 +38,121 @@ pub struct AppState {
     pub logging_service: LoggingService,
     /// Tenant namespace key derived from prefix
     pub tenant_key: String,
+    /// Plans executing or executed by this instance
+    pub run_manager: RunManager,
+    /// Plans executed periodically
+    pub schedule_manager: ScheduleManager,
+    /// Held by the run applying a plan, so plans execute one at a time
+    pub plan_lock: PlanLock,
+    /// Responses to submissions made with an `Idempotency-Key`
+    pub idempotency: IdempotencyStore,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanParams {
+    /// Run up to this many independent phases at once (default: sequential)
+    pub max_concurrency: Option<usize>,
+    /// Describe what would run without executing or recording anything
+    pub dry_run: Option<bool>,
+    /// Only run phases changed since the applied plan and tear down deleted ones
+    pub incremental: Option<bool>,
+    /// Comma-separated `onUse` phases to run even if no phase waits for them
+    #[serde(rename = "use")]
+    pub use_phases: Option<String>,
+    /// Comma-separated `name:value` plan parameters for `when` conditions
+    pub params: Option<String>,
+    /// What to do if another plan is executing (default: reject)
+    pub on_conflict: Option<ConflictPolicy>,
+}
+
+impl PlanParams {
+    pub fn execution_options(&self) -> ExecutionOptions {
+        let options = match self.max_concurrency {
+            Some(n) => ExecutionOptions::parallel(n),
+            None => ExecutionOptions::default(),
+        };
+        options
+            .with_dry_run(self.is_dry_run())
+            .with_incremental(self.is_incremental())
+            .with_requested(self.requested_phases())
+            .with_params(self.plan_params())
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.unwrap_or(false)
+    }
+
+    pub fn is_incremental(&self) -> bool {
+        self.incremental.unwrap_or(false)
+    }
+
+    /// Phases named in `use`
+    pub fn requested_phases(&self) -> Vec<String> {
+        self.use_phases
+            .iter()
+            .flat_map(|ids| ids.split(','))
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Parameters named in `params`; a name without a value is empty
+    pub fn plan_params(&self) -> HashMap<String, String> {
+        self.params
+            .iter()
+            .flat_map(|params| params.split(','))
+            .filter(|param| !param.trim().is_empty())
+            .map(|param| {
+                let (name, value) = param.split_once(':').unwrap_or((param, ""));
+                (name.trim().to_string(), value.trim().to_string())
+            })
+            .collect()
+    }
+
+    /// How runs created from these parameters execute
+    pub fn run_settings(&self) -> RunSettings {
+        RunSettings {
+            max_concurrency: self.max_concurrency,
+            incremental: self.is_incremental(),
+            requested: self.requested_phases(),
+            params: self.plan_params(),
+        }
+    }
 }
 
 /// Handler for POST /plan
 pub async fn submit_plan(
     State(state): State<AppState>,
+    Query(params): Query<PlanParams>,
+    headers: HeaderMap,
     Json(phases): Json<Vec<Phase>>,
 ) -> impl IntoResponse {
+    let request = fingerprint(&(&params, &phases));
+    idempotent(&state.clone(), "plan", &headers, &request, handle_plan(state, params, phases)).await
+}
+
+/// Validate, record and execute a submitted plan
+async fn handle_plan(state: AppState, params: PlanParams, phases: Vec<Phase>) -> Response {
     let plan_id = uuid::Uuid::new_v4().to_string();
+
+    // Expand matrix phases, then reject plans with unknown drivers or
+    // unknown/cyclic dependencies before anything runs
+    let (phases, order) = match matrix::expand(&phases).and_then(|phases| {
+        let order = validate_plan(&phases)?;
+        validate_requested(&phases, &params.requested_phases())?;
+        Ok((phases, order))
+    }) {
+        Ok(checked) => checked,
+        Err(e) => {
+            log_event(Event::Error(format!("Invalid plan: {}", e)));
+            return (StatusCode::BAD_REQUEST, Json(json!({
+                "status": "error",
+                "message": format!("Invalid plan: {}", e)
+            }))).into_response();
+        }
+    };
     
     // Log plan submission
     log_event(Event::PhaseReceived(format!("Received plan with {} phases", phases.len())));
@@ -57,6 +180,10 @@ pub async fn submit_plan(
         ).await;
     }
 
+    if params.is_dry_run() {
+        return dry_run_plan(&state, &params, &plan_id, &phases).await;
+    }
+
     // If NATS is configured, dispatch plan as a session
     if let Some(nats_client) = &state.nats_client {
         // Create a new session and start it
@@ -70,6 +197,13 @@ pub async fn submit_plan(
 
     // Fallback: if Redis is available, compute diff and execute
     if let Some(redis_client) = &state.redis_client {
+        // Only one plan executes at a time
+        let policy = params.on_conflict.unwrap_or_default();
+        let run = match reserve_run(&state, policy, &plan_id, &phases, params.run_settings()).await {
+            Ok(run) => run,
+            Err(response) => return response,
+        };
+
         // Load the previously applied plan for diffing
         let previous_plan = load_applied_plan(redis_client).await.unwrap_or_default();
         
@@ -77,28 +211,16 @@ pub async fn submit_plan(
         let diff = diff_plans(&previous_plan, &phases);
         
         // Log diff results
-        let mut diff_changes = Vec::new();
+        let diff_changes = describe_diff(&diff);
         if !diff.is_empty() {
             println!("Plan differences detected:");
-            for change in &diff {
-                match change {
-                    DiffResult::Add(phase) => {
-                        let change_desc = format!("Add: {} ({})", phase.id, phase.spec.description);
-                        println!("  + {}", change_desc);
-                        diff_changes.push(change_desc);
-                    }
-                    DiffResult::Update { old, new } => {
-                        let change_desc = format!("Update: {} ({} -> {})", 
-                               new.id, old.spec.description, new.spec.description);
-                        println!("  ~ {}", change_desc);
-                        diff_changes.push(change_desc);
-                    }
-                    DiffResult::Delete(phase) => {
-                        let change_desc = format!("Delete: {} ({})", phase.id, phase.spec.description);
-                        println!("  - {}", change_desc);
-                        diff_changes.push(change_desc);
-                    }
-                }
+            for (change, change_desc) in diff.iter().zip(&diff_changes) {
+                let marker = match change {
+                    DiffResult::Add(_) => '+',
+                    DiffResult::Update { .. } => '~',
+                    DiffResult::Delete(_) => '-',
+                };
+                println!("  {} {}", marker, change_desc);
             }
         } else {
             println!("No changes detected in plan");
@@ -115,45 +237,338 @@ pub async fn submit_plan(
             std::collections::HashMap::new(),
         ).await;
 
-        // Store current plan
-        store_current_plan(redis_client, &phases).await;
-
-        // Execute the plan
-        execute_plan(redis_client, &phases).await;
+        // Execute the plan in the background; progress is exposed via GET /runs/:id
+        start_run(&state, Some(redis_client), &run);
 
-        (StatusCode::OK, Json(json!({
-            "status": "success",
-            "message": "Plan received and executed",
+        (StatusCode::ACCEPTED, Json(json!({
+            "status": "accepted",
+            "message": "Plan received and scheduled",
+            "run_id": run.id,
             "plan_id": plan_id,
+            "incremental": params.is_incremental(),
             "phases_count": phases.len(),
             "changes_count": diff.len(),
             "changes": diff_changes
         }))).into_response()
     } else {
         // No Redis - just simulate execution
-        println!("No Redis configured - simulating execution");
-        
-        for phase in &phases {
-            println!("Simulating execution of phase: {}", phase.id);
-            let mut context = std::collections::HashMap::new();
-            context.insert("mode".to_string(), "simulation".to_string());
-            
-            let _ = state.logging_service.log_event_with_context(
-                Event::PhaseExecuted { 
-                    id: phase.id.clone(), 
-                    success: true 
-                },
-                Some(plan_id.clone()),
-                Some(phase.id.clone()),
-                context,
-            ).await;
-        }
+        simulate_plan(&state, &plan_id, &order).await;
 
         (StatusCode::OK, Json(json!({
             "status": "success",
             "message": "Plan received and simulated",
             "plan_id": plan_id,
-            "phases_count": phases.len()
+            "phases_count": phases.len(),
+            "order": order
         }))).into_response()
     }
 }
+
+/// Create the run for a submitted plan after applying `policy` to the
+/// plans executing or queued. A rejecting submission takes the plan lock
+/// before it is accepted, so of two racing submissions only one can be;
+/// the other gets `409 Conflict`, or `503 Service Unavailable` if Redis
+/// cannot tell who holds the lock.
+pub async fn reserve_run(
+    state: &AppState,
+    policy: ConflictPolicy,
+    plan_id: &str,
+    phases: &[Phase],
+    settings: RunSettings,
+) -> Result<Run, Response> {
+    if let Err(holder) = resolve_conflict(state, policy).await {
+        return Err(rejected(plan_id, LockError::Held(holder)));
+    }
+    let run = state.run_manager.create(plan_id, phases, settings);
+    if policy == ConflictPolicy::Reject {
+        if let Err(e) = state.plan_lock.try_acquire(&run.id).await {
+            state.run_manager.discard(&run.id);
+            return Err(rejected(plan_id, e));
+        }
+    }
+    Ok(run)
+}
+
+/// Response to a submission refused because the plan lock could not be taken
+fn rejected(plan_id: &str, error: LockError) -> Response {
+    let message = format!("Plan {} is not applied: {}", plan_id, error);
+    log_event(Event::Error(message.clone()));
+    match error {
+        LockError::Held(holder) => (StatusCode::CONFLICT, Json(json!({
+            "status": "error",
+            "message": message,
+            "run_id": holder
+        }))).into_response(),
+        LockError::Unavailable(_) => (StatusCode::SERVICE_UNAVAILABLE, Json(json!({
+            "status": "error",
+            "message": message
+        }))).into_response(),
+    }
+}
+
+/// Handle a submission once per `Idempotency-Key` header: a retry with
+/// the same key within the retention window gets the first response back,
+/// marked `Idempotent-Replayed: true`, without `submit` running again.
+/// Only successful responses are kept, so a failed submission can be
+/// retried with its key. A submission whose `request` fingerprint differs
+/// from the one that used the key is refused with `422 Unprocessable Entity`.
+pub async fn idempotent(
+    state: &AppState,
+    scope: &str,
+    headers: &HeaderMap,
+    request: &str,
+    submit: impl Future<Output = Response>,
+) -> Response {
+    let Some(key) = headers
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+    else {
+        return submit.await;
+    };
+    if key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+        return (StatusCode::BAD_REQUEST, Json(json!({
+            "status": "error",
+            "message": format!("Idempotency-Key must be at most {} characters", MAX_IDEMPOTENCY_KEY_LEN)
+        }))).into_response();
+    }
+
+    match state.idempotency.begin(scope, key, request).await {
+        Some(record) if record.fingerprint() != request => {
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({
+                "status": "error",
+                "message": format!("Idempotency-Key {} was already used with a different request", key)
+            }))).into_response();
+        }
+        Some(IdempotencyRecord::Completed { status, body, .. }) => {
+            println!("Replaying response for Idempotency-Key {}", key);
+            let status = StatusCode::from_u16(status).unwrap_or(StatusCode::OK);
+            let mut response = (status, Json(body)).into_response();
+            response
+                .headers_mut()
+                .insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+            return response;
+        }
+        Some(IdempotencyRecord::InProgress { .. }) => {
+            return (StatusCode::CONFLICT, Json(json!({
+                "status": "error",
+                "message": format!("A submission with Idempotency-Key {} is still being handled", key)
+            }))).into_response();
+        }
+        None => {}
+    }
+
+    let response = submit.await;
+    if !response.status().is_success() {
+        state.idempotency.abandon(scope, key).await;
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap_or_default();
+    match serde_json::from_slice(&bytes) {
+        Ok(body) => state.idempotency.complete(scope, key, request, parts.status.as_u16(), body).await,
+        Err(_) => state.idempotency.abandon(scope, key).await,
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// Log every phase of a plan as executed, in `order`, without running
+/// anything. This is how plans are applied when Redis is not configured.
+pub(crate) async fn simulate_plan(state: &AppState, plan_id: &str, order: &[String]) {
+    println!("No Redis configured - simulating execution");
+
+    for phase_id in order {
+        println!("Simulating execution of phase: {}", phase_id);
+        let mut context = std::collections::HashMap::new();
+        context.insert("mode".to_string(), "simulation".to_string());
+
+        let _ = state.logging_service.log_event_with_context(
+            Event::PhaseExecuted {
+                id: phase_id.clone(),
+                success: true
+            },
+            Some(plan_id.to_string()),
+            Some(phase_id.clone()),
+            context,
+        ).await;
+    }
+}
+
+/// Execute `run` in the background, skipping the phases it has already
+/// completed. Its plan is stored as `plan:current` once the run holds the
+/// plan lock. Without Redis the plan is executed but not recorded as
+/// applied.
+pub fn start_run(state: &AppState, redis_client: Option<&Arc<RedisClient>>, run: &Run) {
+    let settings = run.settings();
+    let options = match settings.max_concurrency {
+        Some(n) => ExecutionOptions::parallel(n),
+        None => ExecutionOptions::default(),
+    };
+    let options = options
+        .with_incremental(settings.incremental)
+        .with_requested(settings.requested.clone())
+        .with_params(settings.params.clone())
+        .with_plan_id(run.plan_id.clone())
+        .with_logging(state.logging_service.clone())
+        .with_observer(state.run_manager.observer(&run.id))
+        .with_control(run.control())
+        .with_completed_phases(run.completed_phases())
+        .with_outputs(run.phase_outputs());
+    tokio::spawn(execute_run(
+        state.run_manager.clone(),
+        state.plan_lock.clone(),
+        run.clone(),
+        redis_client.cloned(),
+        options,
+    ));
+}
+
+/// Execute a submitted plan once it holds the plan lock, recording progress
+/// and the outcome on its run
+async fn execute_run(
+    run_manager: RunManager,
+    plan_lock: PlanLock,
+    run: Run,
+    redis_client: Option<Arc<RedisClient>>,
+    options: ExecutionOptions,
+) {
+    let control = run.control();
+    let locked = plan_lock
+        .acquire(&run.id, || control.state() == ControlState::Cancelled)
+        .await;
+    let keeper = (locked && plan_lock.is_shared()).then(|| {
+        tokio::spawn(keep_lock(run_manager.clone(), plan_lock.clone(), run.id.clone()))
+    });
+
+    run_manager.mark_started(&run.id);
+    let result = match &redis_client {
+        Some(redis_client) if locked => {
+            store_current_plan(redis_client, run.plan()).await;
+            execute_plan(redis_client, run.plan(), &options).await
+        }
+        // Without the lock the run was cancelled while queued, and stops
+        // before running anything
+        _ => run_plan(run.plan(), &options).await,
+    };
+    match result {
+        Ok(report) => run_manager.finish(&run.id, Ok(&report)),
+        Err(e) => run_manager.finish(&run.id, Err(format!("Invalid plan: {}", e))),
+    }
+
+    if let Some(keeper) = keeper {
+        keeper.abort();
+    }
+    if locked {
+        plan_lock.release(&run.id).await;
+    }
+}
+
+/// Renew the Redis lock held by run `run_id` while it executes
+async fn keep_lock(run_manager: RunManager, plan_lock: PlanLock, run_id: String) {
+    loop {
+        tokio::time::sleep(LOCK_RENEW_INTERVAL).await;
+        renew_lock(&run_manager, &plan_lock, &run_id).await;
+    }
+}
+
+/// Renew the lock held by run `run_id` once. The run is cancelled if it no
+/// longer holds the lock, since another replica may already be applying a
+/// plan, or if another replica superseded it.
+pub async fn renew_lock(run_manager: &RunManager, plan_lock: &PlanLock, run_id: &str) {
+    if !plan_lock.renew(run_id).await {
+        if run_manager.cancel(run_id).is_ok() {
+            eprintln!("Run {} lost the plan lock and is cancelled", run_id);
+        }
+    } else if plan_lock.release_requested(run_id).await && run_manager.cancel(run_id).is_ok() {
+        println!("Run {} was superseded by a plan submitted to another replica", run_id);
+    }
+}
+
+/// Apply `policy` to the plans executing or queued when a new one is
+/// submitted. Returns the run holding the lock if the submission is
+/// rejected.
+async fn resolve_conflict(state: &AppState, policy: ConflictPolicy) -> Result<(), String> {
+    let active: Vec<String> = state
+        .run_manager
+        .list()
+        .into_iter()
+        .filter(|run| run.status.is_active())
+        .map(|run| run.id)
+        .collect();
+    let holder = state.plan_lock.holder().await;
+    let Some(blocking) = holder.clone().or_else(|| active.last().cloned()) else {
+        return Ok(());
+    };
+    match policy {
+        ConflictPolicy::Reject => Err(blocking),
+        ConflictPolicy::Queue => Ok(()),
+        ConflictPolicy::Supersede => {
+            for run_id in active {
+                if let Ok(run) = state.run_manager.cancel(&run_id) {
+                    let _ = state.logging_service.log_event_with_context(
+                        Event::RunCancelled { run_id },
+                        Some(run.plan_id),
+                        None,
+                        HashMap::new(),
+                    ).await;
+                }
+            }
+            if let Some(holder) = holder.filter(|holder| state.run_manager.get(holder).is_none()) {
+                state.plan_lock.request_release(&holder).await;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Execute a plan in dry-run mode: ordering, actions and driver descriptions
+/// are resolved, but nothing runs and neither `plan:current` nor
+/// `plan:applied` changes.
+async fn dry_run_plan(
+    state: &AppState,
+    params: &PlanParams,
+    plan_id: &str,
+    phases: &[Phase],
+) -> axum::response::Response {
+    let options = params.execution_options().with_plan_id(plan_id);
+    let (result, changes) = match &state.redis_client {
+        Some(redis_client) => {
+            let previous_plan = load_applied_plan(redis_client).await.unwrap_or_default();
+            let changes = describe_diff(&diff_plans(&previous_plan, phases));
+            (execute_plan(redis_client, phases, &options).await, changes)
+        }
+        None => (run_plan(phases, &options).await, Vec::new()),
+    };
+
+    match result {
+        Ok(report) => (StatusCode::OK, Json(json!({
+            "status": "success",
+            "message": "Dry run: no phases were executed",
+            "plan_id": plan_id,
+            "dry_run": true,
+            "phases_count": phases.len(),
+            "changes_count": changes.len(),
+            "changes": changes,
+            "plan_status": report.status,
+            "phases": report.phases,
+            "trace": report.trace
+        }))).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({
+            "status": "error",
+            "message": format!("Invalid plan: {}", e)
+        }))).into_response(),
+    }
+}
+
+/// Human-readable description of each change in a diff
+fn describe_diff(diff: &[DiffResult]) -> Vec<String> {
+    diff.iter()
+        .map(|change| match change {
+            DiffResult::Add(phase) => format!("Add: {} ({})", phase.id, phase.spec.description),
+            DiffResult::Update { old, new } => format!("Update: {} ({} -> {})",
+                   new.id, old.spec.description, new.spec.description),
+            DiffResult::Delete(phase) => format!("Delete: {} ({})", phase.id, phase.spec.description),
+        })
+        .collect()
+}
@@ -0,0 +1,268 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use crate::executor::control::ApprovalDecision;
+use crate::log::Event;
+use crate::routes::plan::{start_run, AppState};
+use crate::state::runs::{Run, RunControlError, RunStatus, RUN_LEASE_TTL};
+use crate::state::tracker::{claim_run_lease, load_run_attempts, load_run_checkpoints};
+
+/// Handler for GET /runs
+pub async fn list_runs(State(state): State<AppState>) -> impl IntoResponse {
+    let runs = state.run_manager.list();
+    Json(json!({
+        "status": "ok",
+        "count": runs.len(),
+        "runs": runs
+    }))
+}
+
+/// Handler for GET /runs/:id
+pub async fn get_run(
+    Path(run_id): Path<String>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match state.run_manager.get(&run_id) {
+        Some(run) => (StatusCode::OK, Json(json!({
+            "status": "ok",
+            "run": run
+        }))),
+        None => (StatusCode::NOT_FOUND, Json(json!({
+            "status": "error",
+            "message": format!("Run {} not found", run_id)
+        }))),
+    }
+}
+
+/// Handler for GET /runs/:id/phases/:phase/attempts. Attempts made since
+/// this instance started come from memory; older ones, and those of runs
+/// executed by other replicas, from the records Redis keeps per run.
+pub async fn get_phase_attempts(
+    Path((run_id, phase_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let mut attempts = state.run_manager.attempts(&run_id, &phase_id);
+    if attempts.as_ref().is_none_or(Vec::is_empty) {
+        if let Some(redis_client) = &state.redis_client {
+            let stored = load_run_attempts(redis_client, &run_id, &phase_id).await;
+            if !stored.is_empty() {
+                attempts = Some(stored);
+            }
+        }
+    }
+    let Some(attempts) = attempts else {
+        return (StatusCode::NOT_FOUND, Json(json!({
+            "status": "error",
+            "message": format!("Phase {} of run {} not found", phase_id, run_id)
+        })));
+    };
+
+    (StatusCode::OK, Json(json!({
+        "status": "ok",
+        "run_id": run_id,
+        "phase_id": phase_id,
+        "attempts": attempts
+    })))
+}
+
+/// Handler for POST /runs/:id/pause
+pub async fn pause_run(
+    Path(run_id): Path<String>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let result = state.run_manager.pause(&run_id);
+    control_response(&state, result, Event::RunPaused { run_id }).await
+}
+
+/// Handler for POST /runs/:id/resume. A paused run continues; a run
+/// interrupted by a restart is executed again from its checkpoint.
+pub async fn resume_run(
+    Path(run_id): Path<String>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let interrupted = state
+        .run_manager
+        .get(&run_id)
+        .is_some_and(|run| run.status == RunStatus::Interrupted);
+    let result = match &state.redis_client {
+        Some(redis_client) if interrupted => state.run_manager.restart(&run_id).inspect(|run| {
+            start_run(&state, Some(redis_client), run);
+        }),
+        _ => state.run_manager.resume(&run_id),
+    };
+    control_response(&state, result, Event::RunResumed { run_id }).await
+}
+
+/// Handler for POST /runs/:id/cancel
+pub async fn cancel_run(
+    Path(run_id): Path<String>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let result = state.run_manager.cancel(&run_id);
+    control_response(&state, result, Event::RunCancelled { run_id }).await
+}
+
+/// Handler for POST /runs/:id/phases/:phase/use. Starts a new run of the
+/// same plan that executes the `onUse` phase, reusing what the finished run
+/// `id` completed.
+pub async fn use_phase(
+    Path((run_id, phase_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    // Runs are only created, and can only be started, with Redis configured
+    let Some(redis_client) = &state.redis_client else {
+        return (StatusCode::NOT_FOUND, Json(json!({
+            "status": "error",
+            "message": format!("Run {} not found", run_id)
+        })));
+    };
+    let result = state.run_manager.use_phase(&run_id, &phase_id).inspect(|run| {
+        start_run(&state, Some(redis_client), run);
+    });
+    let (status, body) = control_response(&state, result, Event::PhaseRequested {
+        run_id: run_id.clone(),
+        phase_id,
+    })
+    .await;
+    let status = if status == StatusCode::OK { StatusCode::ACCEPTED } else { status };
+    (status, body)
+}
+
+/// Optional body of POST /runs/:id/phases/:phase/approve and reject
+#[derive(Debug, Default, Deserialize)]
+pub struct ApprovalRequest {
+    pub approver: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// Handler for POST /runs/:id/phases/:phase/approve
+pub async fn approve_phase(
+    Path((run_id, phase_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+    body: Option<Json<ApprovalRequest>>,
+) -> impl IntoResponse {
+    decide_phase(&state, run_id, phase_id, true, body.map(|Json(b)| b).unwrap_or_default()).await
+}
+
+/// Handler for POST /runs/:id/phases/:phase/reject. The gate fails and its
+/// `onFailure` action applies.
+pub async fn reject_phase(
+    Path((run_id, phase_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+    body: Option<Json<ApprovalRequest>>,
+) -> impl IntoResponse {
+    decide_phase(&state, run_id, phase_id, false, body.map(|Json(b)| b).unwrap_or_default()).await
+}
+
+async fn decide_phase(
+    state: &AppState,
+    run_id: String,
+    phase_id: String,
+    approved: bool,
+    request: ApprovalRequest,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let decision = ApprovalDecision {
+        approved,
+        approver: request.approver,
+        comment: request.comment,
+    };
+    let result = state.run_manager.decide(&run_id, &phase_id, decision.clone());
+    let ApprovalDecision { approver, comment, .. } = decision;
+    let event = if approved {
+        Event::PhaseApproved { run_id, phase_id, approver, comment }
+    } else {
+        Event::PhaseRejected { run_id, phase_id, approver, comment }
+    };
+    control_response(state, result, event).await
+}
+
+/// Record an accepted transition as `event` and describe the outcome
+async fn control_response(
+    state: &AppState,
+    result: Result<Run, RunControlError>,
+    event: Event,
+) -> (StatusCode, Json<serde_json::Value>) {
+    match result {
+        Ok(run) => {
+            let _ = state.logging_service.log_event_with_context(
+                event,
+                Some(run.plan_id.clone()),
+                None,
+                std::collections::HashMap::new(),
+            ).await;
+            (StatusCode::OK, Json(json!({
+                "status": "ok",
+                "run": run
+            })))
+        }
+        Err(e) => {
+            let status = match e {
+                RunControlError::NotFound(_) | RunControlError::UnknownPhase { .. } => StatusCode::NOT_FOUND,
+                RunControlError::InvalidTransition { .. } | RunControlError::NotAwaitingApproval { .. } => {
+                    StatusCode::CONFLICT
+                }
+                RunControlError::NotOnUse { .. } => StatusCode::BAD_REQUEST,
+            };
+            (status, Json(json!({
+                "status": "error",
+                "message": e.to_string()
+            })))
+        }
+    }
+}
+
+/// Register the runs that were unfinished when their replica last stopped.
+/// Only runs this replica already held, or whose lease has expired, are
+/// taken over; runs another live replica is executing are left alone. With
+/// `auto_resume` they are executed again at once; otherwise they stay
+/// `interrupted` until `POST /runs/:id/resume`. Phases a run had already
+/// completed are not executed again.
+pub async fn restore_interrupted_runs(state: &AppState, auto_resume: bool) {
+    let Some(redis_client) = &state.redis_client else {
+        return;
+    };
+    for checkpoint in load_run_checkpoints(redis_client).await {
+        let id = &checkpoint.run.id;
+        if state.run_manager.get(id).is_some()
+            || !claim_run_lease(redis_client, id, state.run_manager.replica(), RUN_LEASE_TTL).await
+        {
+            continue;
+        }
+        let run = state.run_manager.restore(checkpoint);
+        if !auto_resume {
+            println!("Run {} was interrupted; POST /runs/{}/resume to continue it", run.id, run.id);
+            continue;
+        }
+        if let Ok(run) = state.run_manager.restart(&run.id) {
+            println!("Resuming interrupted run {}", run.id);
+            start_run(state, Some(redis_client), &run);
+            let _ = state.logging_service.log_event_with_context(
+                Event::RunResumed { run_id: run.id.clone() },
+                Some(run.plan_id.clone()),
+                None,
+                std::collections::HashMap::new(),
+            ).await;
+        }
+    }
+}
+
+/// Periodically take over checkpointed runs whose replica stopped renewing
+/// their lease, as [`restore_interrupted_runs`] does at startup
+pub fn spawn_orphan_adoption(state: AppState, auto_resume: bool) {
+    if state.redis_client.is_none() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(RUN_LEASE_TTL);
+        tick.tick().await;
+        loop {
+            tick.tick().await;
+            restore_interrupted_runs(&state, auto_resume).await;
+        }
+    });
+}
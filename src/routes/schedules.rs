@@ -0,0 +1,214 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use crate::executor::matrix;
+use crate::executor::validate::{validate_plan, validate_requested};
+use crate::log::{log_event, Event};
+use crate::model::Phase;
+use crate::routes::plan::{simulate_plan, start_run, AppState};
+use crate::state::runs::RunSettings;
+use crate::state::schedules::{OverlapPolicy, Schedule};
+use crate::state::tracker::load_run_checkpoint;
+
+/// How often due schedules are looked for
+const SCHEDULER_TICK: Duration = Duration::from_secs(1);
+
+/// Body of POST /schedules
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleRequest {
+    pub name: Option<String>,
+    /// Cron expression, in UTC
+    pub cron: Option<String>,
+    /// Time between runs, e.g. "6h"
+    pub interval: Option<String>,
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+    pub phases: Vec<Phase>,
+    /// Run up to this many independent phases at once (default: sequential)
+    pub max_concurrency: Option<usize>,
+    #[serde(default)]
+    pub incremental: bool,
+    /// `onUse` phases to run every time
+    #[serde(default, rename = "use")]
+    pub use_phases: Vec<String>,
+    /// Plan parameters `when` conditions can refer to
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+}
+
+/// Handler for POST /schedules
+pub async fn create_schedule(
+    State(state): State<AppState>,
+    Json(request): Json<ScheduleRequest>,
+) -> impl IntoResponse {
+    let checked = matrix::expand(&request.phases).and_then(|phases| {
+        validate_plan(&phases)?;
+        validate_requested(&phases, &request.use_phases)?;
+        Ok(phases)
+    });
+    let phases = match checked {
+        Ok(phases) => phases,
+        Err(e) => return bad_request(format!("Invalid plan: {}", e)),
+    };
+    let settings = RunSettings {
+        max_concurrency: request.max_concurrency,
+        incremental: request.incremental,
+        requested: request.use_phases,
+        params: request.params,
+    };
+    let schedule = match Schedule::new(request.name, request.cron, request.interval, request.overlap, phases, settings) {
+        Ok(schedule) => schedule,
+        Err(e) => return bad_request(format!("Invalid schedule: {}", e)),
+    };
+
+    let schedule = state.schedule_manager.create(schedule).await;
+    let _ = state.logging_service.log_event_with_context(
+        Event::ScheduleCreated { schedule_id: schedule.id.clone() },
+        None,
+        None,
+        HashMap::new(),
+    ).await;
+    (StatusCode::CREATED, Json(json!({
+        "status": "ok",
+        "schedule": schedule
+    })))
+}
+
+/// Handler for GET /schedules
+pub async fn list_schedules(State(state): State<AppState>) -> impl IntoResponse {
+    let schedules = state.schedule_manager.list();
+    Json(json!({
+        "status": "ok",
+        "count": schedules.len(),
+        "schedules": schedules
+    }))
+}
+
+/// Handler for GET /schedules/:id
+pub async fn get_schedule(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match state.schedule_manager.get(&id) {
+        Some(schedule) => (StatusCode::OK, Json(json!({
+            "status": "ok",
+            "schedule": schedule
+        }))),
+        None => not_found(&id),
+    }
+}
+
+/// Handler for DELETE /schedules/:id. Runs the schedule already started
+/// are not affected.
+pub async fn delete_schedule(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match state.schedule_manager.remove(&id).await {
+        Some(schedule) => (StatusCode::OK, Json(json!({
+            "status": "ok",
+            "schedule": schedule
+        }))),
+        None => not_found(&id),
+    }
+}
+
+/// Start runs for due schedules every second, for as long as Planter runs
+pub fn spawn_scheduler(state: AppState) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(SCHEDULER_TICK);
+        tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tick.tick().await;
+            run_due_schedules(&state).await;
+        }
+    });
+}
+
+/// Start a run for every schedule that is due now. With Redis, schedules
+/// are reloaded first, and a schedule's last run counts as active while
+/// any replica has it unfinished. Without Redis the plan is simulated, as
+/// `POST /plan` does, and no run is started.
+pub async fn run_due_schedules(state: &AppState) {
+    let now = Utc::now();
+    state.schedule_manager.reload().await;
+    let mut remote_active = HashSet::new();
+    if let Some(redis_client) = &state.redis_client {
+        for schedule in state.schedule_manager.list() {
+            let Some(run_id) = schedule.last_run_id else {
+                continue;
+            };
+            if state.run_manager.get(&run_id).is_some() {
+                continue;
+            }
+            if load_run_checkpoint(redis_client, &run_id).await.is_some_and(|c| c.run.status.is_active()) {
+                remote_active.insert(run_id);
+            }
+        }
+    }
+    let due = state
+        .schedule_manager
+        .due(now, |run_id| match state.run_manager.get(run_id) {
+            Some(run) => run.status.is_active(),
+            None => remote_active.contains(run_id),
+        })
+        .await;
+    for schedule in due {
+        let plan_id = uuid::Uuid::new_v4().to_string();
+        let run_id = match &state.redis_client {
+            Some(redis_client) => {
+                let run = state.run_manager.create(&plan_id, &schedule.phases, schedule.settings.clone());
+                println!("Schedule {} started run {}", schedule.id, run.id);
+                start_run(state, Some(redis_client), &run);
+                Some(run.id)
+            }
+            None => {
+                let order = match matrix::expand(&schedule.phases).and_then(|phases| validate_plan(&phases)) {
+                    Ok(order) => order,
+                    Err(e) => {
+                        log_event(Event::Error(format!("Schedule {} has an invalid plan: {}", schedule.id, e)));
+                        continue;
+                    }
+                };
+                println!("Schedule {} came due", schedule.id);
+                simulate_plan(state, &plan_id, &order).await;
+                None
+            }
+        };
+        state.schedule_manager.record_run(&schedule.id, run_id.as_deref(), now).await;
+        let _ = state.logging_service.log_event_with_context(
+            Event::ScheduleTriggered {
+                schedule_id: schedule.id.clone(),
+                run_id,
+            },
+            Some(plan_id),
+            None,
+            HashMap::new(),
+        ).await;
+    }
+}
+
+fn bad_request(message: String) -> (StatusCode, Json<serde_json::Value>) {
+    log_event(Event::Error(message.clone()));
+    (StatusCode::BAD_REQUEST, Json(json!({
+        "status": "error",
+        "message": message
+    })))
+}
+
+fn not_found(id: &str) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::NOT_FOUND, Json(json!({
+        "status": "error",
+        "message": format!("Schedule {} not found", id)
+    })))
+}
@@ -0,0 +1,183 @@
+use crate::state::redis::RedisClient;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const IDEMPOTENCY_KEY_PREFIX: &str = "idempotency";
+const DEFAULT_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+/// How long a key stays claimed by a submission that is still being
+/// handled, so a replica that dies mid-submission does not block the key
+/// for the whole retention window
+const CLAIM_TTL: Duration = Duration::from_secs(60);
+
+/// How long a submission's response is replayed for its idempotency key,
+/// from `PLANTER_IDEMPOTENCY_TTL` (default 24h)
+pub fn retention() -> Duration {
+    std::env::var("PLANTER_IDEMPOTENCY_TTL")
+        .ok()
+        .and_then(|v| humantime::parse_duration(&v).ok())
+        .filter(|d| !d.is_zero())
+        .unwrap_or(DEFAULT_RETENTION)
+}
+
+/// SHA-256 of a submission, to tell a retry from a different submission
+/// reusing its idempotency key. JSON objects serialize with sorted keys, so
+/// equal requests have equal fingerprints.
+pub fn fingerprint(request: &impl Serialize) -> String {
+    let canonical = serde_json::to_value(request).map(|v| v.to_string()).unwrap_or_default();
+    hex::encode(Sha256::digest(canonical.as_bytes()))
+}
+
+/// What is recorded for an idempotency key, with the [`fingerprint`] of
+/// the submission that claimed it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum IdempotencyRecord {
+    /// The first submission with the key is still being handled
+    InProgress {
+        #[serde(default)]
+        fingerprint: String,
+    },
+    /// The response the first submission with the key received
+    Completed {
+        #[serde(default)]
+        fingerprint: String,
+        status: u16,
+        body: serde_json::Value,
+    },
+}
+
+impl IdempotencyRecord {
+    /// The fingerprint of the submission that claimed the key
+    pub fn fingerprint(&self) -> &str {
+        match self {
+            IdempotencyRecord::InProgress { fingerprint } => fingerprint,
+            IdempotencyRecord::Completed { fingerprint, .. } => fingerprint,
+        }
+    }
+}
+
+/// Responses to submissions made with an `Idempotency-Key`, kept for the
+/// retention window so a retried submission gets the original response
+/// instead of being executed again. Kept in Redis when configured, so
+/// replicas share them, and otherwise in memory. Clones share the records.
+#[derive(Clone, Default)]
+pub struct IdempotencyStore {
+    records: Arc<Mutex<HashMap<String, (IdempotencyRecord, Instant)>>>,
+    redis: Option<Arc<RedisClient>>,
+}
+
+impl IdempotencyStore {
+    /// A store that keeps records in Redis
+    pub fn with_redis(client: Arc<RedisClient>) -> Self {
+        Self {
+            redis: Some(client),
+            ..Self::default()
+        }
+    }
+
+    /// Claim `key` of `scope` for a new submission with `fingerprint`.
+    /// Returns `None` if the key is new, and otherwise what is recorded for
+    /// it. The claim lasts a minute; [`complete`](Self::complete) keeps the
+    /// key for the retention window.
+    pub async fn begin(&self, scope: &str, key: &str, fingerprint: &str) -> Option<IdempotencyRecord> {
+        let key = record_key(scope, key);
+        let claim = IdempotencyRecord::InProgress {
+            fingerprint: fingerprint.to_string(),
+        };
+        if let Some(client) = &self.redis {
+            let claimed: redis::RedisResult<Option<String>> = redis::cmd("SET")
+                .arg(&key)
+                .arg(serde_json::to_string(&claim).unwrap_or_default())
+                .arg("NX")
+                .arg("PX")
+                .arg(CLAIM_TTL.as_millis() as u64)
+                .query_async(&mut *client.lock().await)
+                .await;
+            match claimed {
+                Ok(Some(_)) => return None,
+                Ok(None) => {
+                    let recorded: Option<String> = redis::cmd("GET")
+                        .arg(&key)
+                        .query_async(&mut *client.lock().await)
+                        .await
+                        .unwrap_or(None);
+                    // A record that expired since the SET is as good as new
+                    return recorded.and_then(|r| serde_json::from_str(&r).ok());
+                }
+                Err(e) => eprintln!("Failed to claim idempotency key in Redis: {e}"),
+            }
+        }
+
+        let now = Instant::now();
+        let mut records = self.records.lock().unwrap();
+        records.retain(|_, (_, expires)| *expires > now);
+        if let Some((record, _)) = records.get(&key) {
+            return Some(record.clone());
+        }
+        records.insert(key, (claim, now + CLAIM_TTL));
+        None
+    }
+
+    /// Record the response to the submission with `fingerprint` that
+    /// claimed `key` of `scope`
+    pub async fn complete(
+        &self,
+        scope: &str,
+        key: &str,
+        fingerprint: &str,
+        status: u16,
+        body: serde_json::Value,
+    ) {
+        let record = IdempotencyRecord::Completed {
+            fingerprint: fingerprint.to_string(),
+            status,
+            body,
+        };
+        let key = record_key(scope, key);
+        if let Some(client) = &self.redis {
+            let stored: redis::RedisResult<()> = redis::cmd("SET")
+                .arg(&key)
+                .arg(serde_json::to_string(&record).unwrap_or_default())
+                .arg("PX")
+                .arg(retention().as_millis() as u64)
+                .query_async(&mut *client.lock().await)
+                .await;
+            match stored {
+                Ok(()) => return,
+                Err(e) => eprintln!("Failed to store idempotent response in Redis: {e}"),
+            }
+        }
+        self.records
+            .lock()
+            .unwrap()
+            .insert(key, (record, Instant::now() + retention()));
+    }
+
+    /// Forget `key` of `scope`, so a retried submission is handled afresh
+    pub async fn abandon(&self, scope: &str, key: &str) {
+        let key = record_key(scope, key);
+        if let Some(client) = &self.redis {
+            let removed: redis::RedisResult<()> = redis::cmd("DEL")
+                .arg(&key)
+                .query_async(&mut *client.lock().await)
+                .await;
+            if let Err(e) = removed {
+                eprintln!("Failed to remove idempotency key from Redis: {e}");
+            }
+        }
+        self.records.lock().unwrap().remove(&key);
+    }
+}
+
+fn record_key(scope: &str, key: &str) -> String {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    format!("{}:{}:{}:{}", tenant, IDEMPOTENCY_KEY_PREFIX, scope, key)
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use crate::state::idempotency::{fingerprint, IdempotencyRecord, IdempotencyStore};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_begin_claims_key_once() {
+        let store = IdempotencyStore::default();
+        assert_eq!(store.begin("plan", "ci-1", "a").await, None);
+        assert_eq!(
+            store.begin("plan", "ci-1", "a").await,
+            Some(IdempotencyRecord::InProgress { fingerprint: "a".to_string() })
+        );
+        // Keys are scoped by endpoint
+        assert_eq!(store.begin("manifest", "ci-1", "a").await, None);
+
+        store.complete("plan", "ci-1", "a", 202, json!({"run_id": "run-1"})).await;
+        assert_eq!(
+            store.begin("plan", "ci-1", "b").await,
+            Some(IdempotencyRecord::Completed {
+                fingerprint: "a".to_string(),
+                status: 202,
+                body: json!({"run_id": "run-1"})
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_key_can_be_claimed_again() {
+        let store = IdempotencyStore::default();
+        assert_eq!(store.begin("plan", "ci-2", "a").await, None);
+        store.abandon("plan", "ci-2").await;
+        assert_eq!(store.begin("plan", "ci-2", "b").await, None);
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_map_order() {
+        let forward: HashMap<String, u32> = (0..32).map(|i| (i.to_string(), i)).collect();
+        let backward: HashMap<String, u32> = (0..32).rev().map(|i| (i.to_string(), i)).collect();
+        assert_eq!(fingerprint(&forward), fingerprint(&backward));
+        assert_ne!(fingerprint(&forward), fingerprint(&HashMap::<String, u32>::new()));
+    }
+}
@@ -0,0 +1,250 @@
+use crate::state::redis::RedisClient;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+const PLAN_LOCK_KEY: &str = "plan:lock";
+const PLAN_LOCK_RELEASE_KEY: &str = "plan:lock:release";
+
+/// How long the Redis lock outlives a replica that stopped renewing it
+pub const LOCK_TTL: Duration = Duration::from_secs(30);
+/// How often the holder renews the Redis lock
+pub const LOCK_RENEW_INTERVAL: Duration = Duration::from_secs(10);
+/// How often a waiting run checks whether a lock held elsewhere was released
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What a submission does when another plan is executing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Refuse the submission with `409 Conflict`
+    #[default]
+    Reject,
+    /// Start the run once the executing plan has finished
+    Queue,
+    /// Cancel the executing plan and start the run once it has stopped
+    Supersede,
+}
+
+/// Why the plan lock could not be taken
+#[derive(Debug, Clone, PartialEq)]
+pub enum LockError {
+    /// Another run holds the lock
+    Held(String),
+    /// Redis could not be asked who holds the lock
+    Unavailable(String),
+}
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockError::Held(holder) => write!(f, "run {} is executing a plan", holder),
+            LockError::Unavailable(e) => write!(f, "the plan lock is unavailable: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LockError {}
+
+/// Per-tenant lock held by the run applying a plan, so only one plan
+/// executes at a time. Within an instance the holder is kept in memory;
+/// with Redis the lock is also a key that expires unless its holder renews
+/// it, which keeps replicas sharing a Redis from applying simultaneously.
+/// Holders are identified by run id, and acquiring a lock one already
+/// holds succeeds. Clones share the lock.
+#[derive(Clone, Default)]
+pub struct PlanLock {
+    holder: Arc<Mutex<Option<String>>>,
+    released: Arc<Notify>,
+    redis: Option<Arc<RedisClient>>,
+}
+
+impl PlanLock {
+    /// A lock shared through Redis with other replicas
+    pub fn with_redis(client: Arc<RedisClient>) -> Self {
+        Self {
+            redis: Some(client),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the lock is kept in Redis and has to be renewed
+    pub fn is_shared(&self) -> bool {
+        self.redis.is_some()
+    }
+
+    /// Take the lock for `token`. Fails with the token holding it, or if
+    /// Redis cannot be reached, since the lock is then not known to be free.
+    pub async fn try_acquire(&self, token: &str) -> Result<(), LockError> {
+        if let Some(holder) = self.local_holder().filter(|holder| holder != token) {
+            return Err(LockError::Held(holder));
+        }
+        if let Some(client) = &self.redis {
+            let key = tenant_key(PLAN_LOCK_KEY);
+            loop {
+                let acquired: redis::RedisResult<Option<String>> = redis::cmd("SET")
+                    .arg(&key)
+                    .arg(token)
+                    .arg("NX")
+                    .arg("PX")
+                    .arg(LOCK_TTL.as_millis() as u64)
+                    .query_async(&mut *client.lock().await)
+                    .await;
+                match acquired {
+                    Ok(Some(_)) => break,
+                    Ok(None) => match try_redis_holder(client).await {
+                        Ok(Some(holder)) if holder == token => break,
+                        Ok(Some(holder)) => return Err(LockError::Held(holder)),
+                        // Released since the SET; try again
+                        Ok(None) => continue,
+                        Err(e) => return Err(LockError::Unavailable(e.to_string())),
+                    },
+                    Err(e) => return Err(LockError::Unavailable(e.to_string())),
+                }
+            }
+        }
+        let mut holder = self.holder.lock().unwrap();
+        match holder.as_deref() {
+            Some(current) if current != token => Err(LockError::Held(current.to_string())),
+            _ => {
+                *holder = Some(token.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    /// Wait until the lock can be taken for `token`, retrying while Redis
+    /// is unavailable. Returns false without taking it if `cancelled`
+    /// becomes true first.
+    pub async fn acquire(&self, token: &str, cancelled: impl Fn() -> bool) -> bool {
+        loop {
+            let released = self.released.notified();
+            match self.try_acquire(token).await {
+                Ok(()) => return true,
+                Err(LockError::Unavailable(e)) => eprintln!("Failed to acquire plan lock in Redis: {e}"),
+                Err(LockError::Held(_)) => {}
+            }
+            if cancelled() {
+                return false;
+            }
+            tokio::select! {
+                _ = released => {}
+                _ = tokio::time::sleep(LOCK_POLL_INTERVAL) => {}
+            }
+        }
+    }
+
+    /// The token holding the lock, here or on another replica
+    pub async fn holder(&self) -> Option<String> {
+        if let Some(holder) = self.local_holder() {
+            return Some(holder);
+        }
+        match &self.redis {
+            Some(client) => redis_holder(client).await,
+            None => None,
+        }
+    }
+
+    /// Extend the Redis lock held by `token`; returns false if it is no
+    /// longer held by `token`
+    pub async fn renew(&self, token: &str) -> bool {
+        let Some(client) = &self.redis else {
+            return self.local_holder().as_deref() == Some(token);
+        };
+        let renewed: redis::RedisResult<i64> = redis::Script::new(
+            "if redis.call('GET', KEYS[1]) == ARGV[1] then \
+                 return redis.call('PEXPIRE', KEYS[1], ARGV[2]) \
+             else return 0 end",
+        )
+        .key(tenant_key(PLAN_LOCK_KEY))
+        .arg(token)
+        .arg(LOCK_TTL.as_millis() as u64)
+        .invoke_async(&mut *client.lock().await)
+        .await;
+        matches!(renewed, Ok(1))
+    }
+
+    /// Release the lock if `token` holds it
+    pub async fn release(&self, token: &str) {
+        if let Some(client) = &self.redis {
+            let released: redis::RedisResult<i64> = redis::Script::new(
+                "if redis.call('GET', KEYS[1]) == ARGV[1] then \
+                     redis.call('DEL', KEYS[2]) \
+                     return redis.call('DEL', KEYS[1]) \
+                 else return 0 end",
+            )
+            .key(tenant_key(PLAN_LOCK_KEY))
+            .key(tenant_key(PLAN_LOCK_RELEASE_KEY))
+            .arg(token)
+            .invoke_async(&mut *client.lock().await)
+            .await;
+            if let Err(e) = released {
+                eprintln!("Failed to release plan lock: {e}");
+            }
+        }
+        {
+            let mut holder = self.holder.lock().unwrap();
+            if holder.as_deref() == Some(token) {
+                *holder = None;
+            }
+        }
+        self.released.notify_waiters();
+    }
+
+    /// Ask the replica holding the lock for `holder` to cancel its run
+    pub async fn request_release(&self, holder: &str) {
+        let Some(client) = &self.redis else {
+            return;
+        };
+        let requested: redis::RedisResult<()> = redis::cmd("SET")
+            .arg(tenant_key(PLAN_LOCK_RELEASE_KEY))
+            .arg(holder)
+            .arg("PX")
+            .arg(LOCK_TTL.as_millis() as u64)
+            .query_async(&mut *client.lock().await)
+            .await;
+        if let Err(e) = requested {
+            eprintln!("Failed to request plan lock release: {e}");
+        }
+    }
+
+    /// Whether another replica asked the holder `token` to cancel its run
+    pub async fn release_requested(&self, token: &str) -> bool {
+        let Some(client) = &self.redis else {
+            return false;
+        };
+        let requested: Option<String> = redis::cmd("GET")
+            .arg(tenant_key(PLAN_LOCK_RELEASE_KEY))
+            .query_async(&mut *client.lock().await)
+            .await
+            .unwrap_or(None);
+        requested.as_deref() == Some(token)
+    }
+
+    fn local_holder(&self) -> Option<String> {
+        self.holder.lock().unwrap().clone()
+    }
+}
+
+async fn redis_holder(client: &RedisClient) -> Option<String> {
+    try_redis_holder(client).await.unwrap_or(None)
+}
+
+async fn try_redis_holder(client: &RedisClient) -> redis::RedisResult<Option<String>> {
+    redis::cmd("GET")
+        .arg(tenant_key(PLAN_LOCK_KEY))
+        .query_async(&mut *client.lock().await)
+        .await
+}
+
+fn tenant_key(key: &str) -> String {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    format!("{}:{}", tenant, key)
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
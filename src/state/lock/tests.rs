@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod tests {
+    use crate::state::lock::{ConflictPolicy, LockError, PlanLock};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_lock_is_exclusive_and_reentrant() {
+        let lock = PlanLock::default();
+        assert_eq!(lock.holder().await, None);
+        assert_eq!(lock.try_acquire("run-1").await, Ok(()));
+        assert_eq!(lock.try_acquire("run-1").await, Ok(()));
+        assert_eq!(lock.try_acquire("run-2").await, Err(LockError::Held("run-1".to_string())));
+        assert_eq!(lock.holder().await.as_deref(), Some("run-1"));
+        assert!(lock.renew("run-1").await);
+        assert!(!lock.renew("run-2").await);
+
+        // Only the holder releases it
+        lock.release("run-2").await;
+        assert_eq!(lock.holder().await.as_deref(), Some("run-1"));
+        lock.release("run-1").await;
+        assert_eq!(lock.holder().await, None);
+        assert_eq!(lock.try_acquire("run-2").await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_release() {
+        let lock = PlanLock::default();
+        lock.try_acquire("run-1").await.unwrap();
+
+        let waiting = tokio::spawn({
+            let lock = lock.clone();
+            async move { lock.acquire("run-2", || false).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+
+        lock.release("run-1").await;
+        assert!(tokio::time::timeout(Duration::from_secs(1), waiting).await.unwrap().unwrap());
+        assert_eq!(lock.holder().await.as_deref(), Some("run-2"));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_gives_up_when_cancelled() {
+        let lock = PlanLock::default();
+        lock.try_acquire("run-1").await.unwrap();
+        assert!(!lock.acquire("run-2", || true).await);
+        assert_eq!(lock.holder().await.as_deref(), Some("run-1"));
+    }
+
+    #[test]
+    fn test_conflict_policy_names() {
+        let policy: ConflictPolicy = serde_json::from_str("\"supersede\"").unwrap();
+        assert_eq!(policy, ConflictPolicy::Supersede);
+        assert_eq!(ConflictPolicy::default(), ConflictPolicy::Reject);
+    }
+}
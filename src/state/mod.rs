@@ -1,2 +1,6 @@
+pub mod idempotency;
+pub mod lock;
 pub mod redis;
+pub mod runs;
+pub mod schedules;
 pub mod tracker;
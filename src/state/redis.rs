@@ -27,3 +27,27 @@ pub async fn get_json<T: for<'de> serde::Deserialize<'de>>(
     let data: Option<String> = client.lock().await.get(key).await?;
     Ok(data.and_then(|s| serde_json::from_str(&s).ok()))
 }
+
+pub async fn hset_json<T: serde::Serialize + ?Sized>(
+    client: &RedisClient,
+    key: &str,
+    field: &str,
+    value: &T,
+) -> redis::RedisResult<()> {
+    let json = serde_json::to_string(value)
+        .map_err(|e| redis::RedisError::from((redis::ErrorKind::InvalidClientConfig, "JSON serialization failed", e.to_string())))?;
+    client.lock().await.hset(key, field, json).await
+}
+
+pub async fn hdel(client: &RedisClient, key: &str, field: &str) -> redis::RedisResult<()> {
+    client.lock().await.hdel(key, field).await
+}
+
+/// Every field of a hash whose value parses as `T`
+pub async fn hgetall_json<T: for<'de> serde::Deserialize<'de>>(
+    client: &RedisClient,
+    key: &str,
+) -> redis::RedisResult<Vec<T>> {
+    let data: std::collections::HashMap<String, String> = client.lock().await.hgetall(key).await?;
+    Ok(data.values().filter_map(|s| serde_json::from_str(s).ok()).collect())
+}
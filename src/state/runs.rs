@@ -0,0 +1,720 @@
+use crate::executor::control::{ApprovalDecision, RunControl};
+use crate::executor::attempt::AttemptRecord;
+use crate::executor::instance::InstanceMode;
+use crate::executor::outputs::PhaseOutputs;
+use crate::executor::scheduler::{PhaseReport, PhaseStatus, PlanReport, PlanStatus};
+use crate::executor::ExecutionObserver;
+use crate::model::Phase;
+use crate::state::redis::RedisClient;
+use crate::state::tracker::{remove_run_checkpoint, renew_run_lease, store_run_attempt, store_run_checkpoint};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Finished runs kept in memory before the oldest are dropped
+const MAX_FINISHED_RUNS: usize = 200;
+
+/// How long a checkpointed run stays with a replica that stopped renewing
+/// its lease before another replica may restore it
+pub const RUN_LEASE_TTL: Duration = Duration::from_secs(30);
+/// How often a replica renews the leases of its unfinished runs
+const RUN_LEASE_RENEW_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Lifecycle of a submitted plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Pending,
+    Running,
+    /// No further phase starts until the run is resumed
+    Paused,
+    /// An approval gate is waiting for `approve` or `reject`
+    AwaitingApproval,
+    Succeeded,
+    Failed,
+    Aborted,
+    /// Stopped by an operator
+    Cancelled,
+    /// Was executing when Planter stopped; resume it to continue
+    Interrupted,
+}
+
+impl RunStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            RunStatus::Succeeded | RunStatus::Failed | RunStatus::Aborted | RunStatus::Cancelled
+        )
+    }
+
+    /// Whether the run is executing, or waiting to be started, resumed or
+    /// approved. Interrupted runs only continue if someone resumes them.
+    pub fn is_active(self) -> bool {
+        !self.is_finished() && self != RunStatus::Interrupted
+    }
+}
+
+impl fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RunStatus::Pending => "pending",
+            RunStatus::Running => "running",
+            RunStatus::Paused => "paused",
+            RunStatus::AwaitingApproval => "awaiting_approval",
+            RunStatus::Succeeded => "succeeded",
+            RunStatus::Failed => "failed",
+            RunStatus::Aborted => "aborted",
+            RunStatus::Cancelled => "cancelled",
+            RunStatus::Interrupted => "interrupted",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl From<PlanStatus> for RunStatus {
+    fn from(status: PlanStatus) -> Self {
+        match status {
+            PlanStatus::Succeeded => RunStatus::Succeeded,
+            PlanStatus::Failed => RunStatus::Failed,
+            PlanStatus::Aborted => RunStatus::Aborted,
+            PlanStatus::Cancelled => RunStatus::Cancelled,
+        }
+    }
+}
+
+/// Progress of one phase within a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunPhase {
+    pub id: String,
+    #[serde(default)]
+    pub instance_mode: InstanceMode,
+    pub status: PhaseStatus,
+    pub attempts: u32,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Message from the most recent attempt, if any
+    pub message: Option<String>,
+    /// Values the phase published for downstream phases
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub outputs: PhaseOutputs,
+    /// Every attempt made by this instance, with its output
+    #[serde(skip)]
+    history: Vec<AttemptRecord>,
+}
+
+/// A plan submitted for asynchronous execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Run {
+    pub id: String,
+    pub plan_id: String,
+    pub status: RunStatus,
+    pub submitted_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Why the run could not be executed
+    pub error: Option<String>,
+    pub phases: Vec<RunPhase>,
+    /// Phases removed from the plan and torn down by an incremental apply
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub teardown: Vec<PhaseReport>,
+    #[serde(skip)]
+    control: RunControl,
+    #[serde(skip)]
+    plan: Arc<Vec<Phase>>,
+    #[serde(skip)]
+    settings: RunSettings,
+}
+
+/// Submission parameters a run is executed with
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunSettings {
+    /// Per-plan concurrency limit
+    pub max_concurrency: Option<usize>,
+    /// Only run phases changed since the applied plan
+    #[serde(default)]
+    pub incremental: bool,
+    /// `onUse` phases to run even if no phase waits for them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requested: Vec<String>,
+    /// Plan parameters `when` conditions can refer to
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub params: HashMap<String, String>,
+}
+
+impl Run {
+    /// Handle to pass to the executor so pause, resume and cancel reach it
+    pub fn control(&self) -> RunControl {
+        self.control.clone()
+    }
+
+    /// The phases submitted for this run
+    pub fn plan(&self) -> &[Phase] {
+        &self.plan
+    }
+
+    pub fn settings(&self) -> &RunSettings {
+        &self.settings
+    }
+
+    /// Phases that have already succeeded and need not run again
+    pub fn completed_phases(&self) -> Vec<String> {
+        self.phases
+            .iter()
+            .filter(|p| p.status == PhaseStatus::Succeeded)
+            .map(|p| p.id.clone())
+            .collect()
+    }
+
+    /// Outputs published by the phases of this run, keyed by phase id
+    pub fn phase_outputs(&self) -> HashMap<String, PhaseOutputs> {
+        self.phases
+            .iter()
+            .filter(|p| !p.outputs.is_empty())
+            .map(|p| (p.id.clone(), p.outputs.clone()))
+            .collect()
+    }
+}
+
+/// Progress of an unfinished run together with what is needed to continue
+/// it after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunCheckpoint {
+    pub run: Run,
+    pub phases: Vec<Phase>,
+    #[serde(flatten)]
+    pub settings: RunSettings,
+}
+
+impl From<&Run> for RunCheckpoint {
+    fn from(run: &Run) -> Self {
+        Self {
+            run: run.clone(),
+            phases: run.plan.to_vec(),
+            settings: run.settings.clone(),
+        }
+    }
+}
+
+/// Why a pause, resume or cancel request was refused
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunControlError {
+    NotFound(String),
+    InvalidTransition { id: String, status: RunStatus, action: &'static str },
+    UnknownPhase { id: String, phase: String },
+    /// Only `onUse` phases can be requested
+    NotOnUse { phase: String },
+    /// Only approval gates waiting for a decision can be approved or rejected
+    NotAwaitingApproval { id: String, phase: String },
+}
+
+impl fmt::Display for RunControlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunControlError::NotFound(id) => write!(f, "Run {} not found", id),
+            RunControlError::InvalidTransition { id, status, action } => {
+                write!(f, "Cannot {} run {}: run is {}", action, id, status)
+            }
+            RunControlError::UnknownPhase { id, phase } => {
+                write!(f, "Phase {} of run {} not found", phase, id)
+            }
+            RunControlError::NotOnUse { phase } => {
+                write!(f, "Phase {} is not an onUse phase", phase)
+            }
+            RunControlError::NotAwaitingApproval { id, phase } => {
+                write!(f, "Phase {} of run {} is not awaiting approval", phase, id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RunControlError {}
+
+enum CheckpointOp {
+    Store(Box<RunCheckpoint>),
+    Remove(String),
+    Attempt(String, Box<AttemptRecord>),
+}
+
+/// In-memory registry of runs, shared by the HTTP handlers and the tasks
+/// executing the plans
+#[derive(Clone, Default)]
+pub struct RunManager {
+    runs: Arc<RwLock<HashMap<String, Run>>>,
+    /// Receives a checkpoint whenever an unfinished run changes, and every
+    /// attempt record
+    checkpoints: Option<mpsc::UnboundedSender<CheckpointOp>>,
+    /// Identifies this replica in the leases of the runs it checkpoints
+    replica: String,
+}
+
+impl RunManager {
+    /// A manager that checkpoints unfinished runs to Redis, in the order
+    /// their changes happen, so they can be resumed after a restart, and
+    /// stores the attempt records of every run there by run id. Each
+    /// checkpoint is leased to this replica, identified by `replica`, and
+    /// the lease is renewed for as long as the run is unfinished. A run whose
+    /// lease another replica took over, after this one stalled past
+    /// [`RUN_LEASE_TTL`], is cancelled here and no longer checkpointed, so
+    /// only the replica that took it over carries it on.
+    pub fn with_checkpoints(client: Arc<RedisClient>, replica: &str) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let runs: Arc<RwLock<HashMap<String, Run>>> = Arc::default();
+        let owner = replica.to_string();
+        let unfinished = runs.clone();
+        tokio::spawn(async move {
+            let mut renew = tokio::time::interval(RUN_LEASE_RENEW_INTERVAL);
+            let mut lost: HashSet<String> = HashSet::new();
+            loop {
+                tokio::select! {
+                    op = rx.recv() => match op {
+                        Some(CheckpointOp::Store(checkpoint)) => {
+                            let id = &checkpoint.run.id;
+                            if lost.contains(id) {
+                                continue;
+                            }
+                            if renew_run_lease(&client, id, &owner, RUN_LEASE_TTL).await {
+                                store_run_checkpoint(&client, &checkpoint).await;
+                            } else {
+                                lose_run(&unfinished, &mut lost, id);
+                            }
+                        }
+                        // The checkpoint and lease of a lost run are another replica's
+                        Some(CheckpointOp::Remove(id)) => {
+                            if !lost.remove(&id) {
+                                remove_run_checkpoint(&client, &id).await;
+                            }
+                        }
+                        Some(CheckpointOp::Attempt(id, record)) => store_run_attempt(&client, &id, &record).await,
+                        None => break,
+                    },
+                    _ = renew.tick() => {
+                        let ids: Vec<String> = unfinished
+                            .read()
+                            .unwrap()
+                            .values()
+                            .filter(|run| !run.status.is_finished() && !lost.contains(&run.id))
+                            .map(|run| run.id.clone())
+                            .collect();
+                        for id in ids {
+                            if !renew_run_lease(&client, &id, &owner, RUN_LEASE_TTL).await {
+                                lose_run(&unfinished, &mut lost, &id);
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        Self {
+            runs,
+            checkpoints: Some(tx),
+            replica: replica.to_string(),
+        }
+    }
+
+    /// The replica named in the leases of this manager's checkpoints
+    pub fn replica(&self) -> &str {
+        &self.replica
+    }
+
+    /// Register a new pending run for `phases`
+    pub fn create(&self, plan_id: &str, phases: &[Phase], settings: RunSettings) -> Run {
+        self.register(new_run(plan_id, phases, settings))
+    }
+
+    /// Forget a run that was created but never started, along with its
+    /// checkpoint
+    pub fn discard(&self, id: &str) {
+        if self.runs.write().unwrap().remove(id).is_none() {
+            return;
+        }
+        if let Some(checkpoints) = &self.checkpoints {
+            let _ = checkpoints.send(CheckpointOp::Remove(id.to_string()));
+        }
+    }
+
+    /// Register a new pending run that uses the `onUse` phase `phase_id` of
+    /// the finished run `id`. Phases that succeeded in that run are carried
+    /// over with their outputs, so only the requested phase and whatever it
+    /// waits for that has not succeeded yet are executed. The caller starts
+    /// the new run.
+    pub fn use_phase(&self, id: &str, phase_id: &str) -> Result<Run, RunControlError> {
+        let previous = self.get(id).ok_or_else(|| RunControlError::NotFound(id.to_string()))?;
+        let Some(phase) = previous.plan.iter().find(|p| p.id == phase_id) else {
+            return Err(RunControlError::UnknownPhase {
+                id: id.to_string(),
+                phase: phase_id.to_string(),
+            });
+        };
+        if InstanceMode::of(phase) != InstanceMode::OnUse {
+            return Err(RunControlError::NotOnUse {
+                phase: phase_id.to_string(),
+            });
+        }
+        if !previous.status.is_finished() {
+            return Err(RunControlError::InvalidTransition {
+                id: id.to_string(),
+                status: previous.status,
+                action: "use a phase of",
+            });
+        }
+
+        let settings = RunSettings {
+            incremental: false,
+            requested: vec![phase_id.to_string()],
+            ..previous.settings.clone()
+        };
+        let mut run = new_run(&previous.plan_id, &previous.plan, settings);
+        for (phase, earlier) in run.phases.iter_mut().zip(&previous.phases) {
+            if earlier.status == PhaseStatus::Succeeded {
+                *phase = earlier.clone();
+            }
+        }
+        Ok(self.register(run))
+    }
+
+    fn register(&self, run: Run) -> Run {
+        let mut runs = self.runs.write().unwrap();
+        runs.insert(run.id.clone(), run.clone());
+        prune(&mut runs);
+        self.save(&run);
+        run
+    }
+
+    /// Register a run from its checkpoint as `interrupted`. Phases that were
+    /// running when it stopped are pending again.
+    pub fn restore(&self, checkpoint: RunCheckpoint) -> Run {
+        let mut run = checkpoint.run;
+        run.status = RunStatus::Interrupted;
+        run.control = RunControl::default();
+        run.plan = Arc::new(checkpoint.phases);
+        run.settings = checkpoint.settings;
+        for phase in &mut run.phases {
+            if matches!(phase.status, PhaseStatus::Running | PhaseStatus::AwaitingApproval) {
+                phase.status = PhaseStatus::Pending;
+                phase.started_at = None;
+            }
+        }
+
+        self.runs.write().unwrap().insert(run.id.clone(), run.clone());
+        self.save(&run);
+        run
+    }
+
+    /// Attempts of `phase_id` in run `id` recorded by this instance, or
+    /// `None` if the run or phase is unknown
+    pub fn attempts(&self, id: &str, phase_id: &str) -> Option<Vec<AttemptRecord>> {
+        let runs = self.runs.read().unwrap();
+        let phase = runs.get(id)?.phases.iter().find(|p| p.id == phase_id)?;
+        Some(phase.history.clone())
+    }
+
+    pub fn get(&self, id: &str) -> Option<Run> {
+        self.runs.read().unwrap().get(id).cloned()
+    }
+
+    /// All known runs, most recently submitted first
+    pub fn list(&self) -> Vec<Run> {
+        let mut runs: Vec<Run> = self.runs.read().unwrap().values().cloned().collect();
+        runs.sort_by_key(|r| std::cmp::Reverse(r.submitted_at));
+        runs
+    }
+
+    /// Observer that records execution progress against run `id`
+    pub fn observer(&self, id: &str) -> Arc<dyn ExecutionObserver> {
+        Arc::new(RunObserver {
+            manager: self.clone(),
+            run_id: id.to_string(),
+        })
+    }
+
+    pub fn mark_started(&self, id: &str) {
+        self.update(id, |run| {
+            // A run paused before it started stays paused
+            if run.status == RunStatus::Pending {
+                run.status = RunStatus::Running;
+            }
+            run.started_at = Some(Utc::now());
+            true
+        });
+    }
+
+    /// Stop starting new phases of a pending or running run
+    pub fn pause(&self, id: &str) -> Result<Run, RunControlError> {
+        self.transition(id, "pause", |run| {
+            let active = matches!(run.status, RunStatus::Pending | RunStatus::Running | RunStatus::AwaitingApproval);
+            if !active || !run.control.pause() {
+                return false;
+            }
+            run.status = RunStatus::Paused;
+            true
+        })
+    }
+
+    /// Make an interrupted run pending again so it can be executed; the
+    /// caller starts it
+    pub fn restart(&self, id: &str) -> Result<Run, RunControlError> {
+        self.transition(id, "restart", |run| {
+            if run.status != RunStatus::Interrupted {
+                return false;
+            }
+            run.status = RunStatus::Pending;
+            run.finished_at = None;
+            true
+        })
+    }
+
+    /// Continue a paused run
+    pub fn resume(&self, id: &str) -> Result<Run, RunControlError> {
+        self.transition(id, "resume", |run| {
+            if run.status != RunStatus::Paused || !run.control.resume() {
+                return false;
+            }
+            run.status = if run.started_at.is_some() {
+                RunStatus::Running
+            } else {
+                RunStatus::Pending
+            };
+            sync_approval_status(run);
+            true
+        })
+    }
+
+    /// Approve or reject the approval gate `phase_id` of run `id`. The
+    /// executor carries on with the gate's dependents, or handles the
+    /// rejection as the gate's failure.
+    pub fn decide(&self, id: &str, phase_id: &str, decision: ApprovalDecision) -> Result<Run, RunControlError> {
+        let mut runs = self.runs.write().unwrap();
+        let run = runs
+            .get_mut(id)
+            .ok_or_else(|| RunControlError::NotFound(id.to_string()))?;
+        let Some(phase) = run.phases.iter().find(|p| p.id == phase_id) else {
+            return Err(RunControlError::UnknownPhase {
+                id: id.to_string(),
+                phase: phase_id.to_string(),
+            });
+        };
+        if phase.status != PhaseStatus::AwaitingApproval || !run.control.decide(phase_id, decision) {
+            return Err(RunControlError::NotAwaitingApproval {
+                id: id.to_string(),
+                phase: phase_id.to_string(),
+            });
+        }
+        Ok(run.clone())
+    }
+
+    /// Stop an unfinished run, interrupting the phases it is running. The
+    /// run becomes `cancelled` once the executor has stopped, or at once if
+    /// it is interrupted.
+    pub fn cancel(&self, id: &str) -> Result<Run, RunControlError> {
+        self.transition(id, "cancel", |run| {
+            if run.status.is_finished() || !run.control.cancel() {
+                return false;
+            }
+            if run.status == RunStatus::Interrupted {
+                run.status = RunStatus::Cancelled;
+                run.finished_at = Some(Utc::now());
+            }
+            true
+        })
+    }
+
+    fn transition(
+        &self,
+        id: &str,
+        action: &'static str,
+        apply: impl FnOnce(&mut Run) -> bool,
+    ) -> Result<Run, RunControlError> {
+        let mut runs = self.runs.write().unwrap();
+        let run = runs
+            .get_mut(id)
+            .ok_or_else(|| RunControlError::NotFound(id.to_string()))?;
+        if !apply(run) {
+            return Err(RunControlError::InvalidTransition {
+                id: id.to_string(),
+                status: run.status,
+                action,
+            });
+        }
+        self.save(run);
+        Ok(run.clone())
+    }
+
+    /// Record the final outcome of a run
+    pub fn finish(&self, id: &str, result: Result<&PlanReport, String>) {
+        self.update(id, |run| {
+            run.finished_at = Some(Utc::now());
+            match result {
+                Ok(report) => {
+                    apply_phase_statuses(run, &report.phases);
+                    run.teardown = report.teardown.clone();
+                    run.status = report.status.into();
+                }
+                Err(error) => {
+                    run.status = RunStatus::Failed;
+                    run.error = Some(error);
+                }
+            }
+            true
+        });
+    }
+
+    /// Apply `f` to run `id`, checkpointing the run if `f` reports a change
+    fn update(&self, id: &str, f: impl FnOnce(&mut Run) -> bool) {
+        if let Some(run) = self.runs.write().unwrap().get_mut(id) {
+            if f(run) {
+                self.save(run);
+            }
+        }
+    }
+
+    /// Checkpoint an unfinished run, or drop the checkpoint of a finished one
+    fn save(&self, run: &Run) {
+        let Some(checkpoints) = &self.checkpoints else {
+            return;
+        };
+        let op = if run.status.is_finished() {
+            CheckpointOp::Remove(run.id.clone())
+        } else {
+            CheckpointOp::Store(Box::new(RunCheckpoint::from(run)))
+        };
+        let _ = checkpoints.send(op);
+    }
+
+    /// Persist an attempt record of run `id`
+    fn save_attempt(&self, id: &str, record: &AttemptRecord) {
+        if let Some(checkpoints) = &self.checkpoints {
+            let _ = checkpoints.send(CheckpointOp::Attempt(id.to_string(), Box::new(record.clone())));
+        }
+    }
+}
+
+/// A pending run of `phases`, not yet registered
+/// Stop executing run `id`, whose lease another replica took over
+fn lose_run(runs: &RwLock<HashMap<String, Run>>, lost: &mut HashSet<String>, id: &str) {
+    eprintln!("Run {} was taken over by another replica and is cancelled here", id);
+    lost.insert(id.to_string());
+    if let Some(run) = runs.read().unwrap().get(id) {
+        run.control.cancel();
+    }
+}
+
+fn new_run(plan_id: &str, phases: &[Phase], settings: RunSettings) -> Run {
+    Run {
+        id: uuid::Uuid::new_v4().to_string(),
+        plan_id: plan_id.to_string(),
+        status: RunStatus::Pending,
+        submitted_at: Utc::now(),
+        started_at: None,
+        finished_at: None,
+        error: None,
+        phases: phases
+            .iter()
+            .map(|p| RunPhase {
+                id: p.id.clone(),
+                instance_mode: InstanceMode::of(p),
+                status: PhaseStatus::Pending,
+                attempts: 0,
+                started_at: None,
+                finished_at: None,
+                message: None,
+                outputs: PhaseOutputs::new(),
+                history: Vec::new(),
+            })
+            .collect(),
+        teardown: Vec::new(),
+        control: RunControl::default(),
+        plan: Arc::new(phases.to_vec()),
+        settings,
+    }
+}
+
+/// Copy scheduler statuses and outputs onto a run, stamping start and
+/// finish times on transitions. Returns whether anything changed.
+fn apply_phase_statuses(run: &mut Run, phases: &[PhaseReport]) -> bool {
+    let now = Utc::now();
+    let mut changed = false;
+    for report in phases {
+        let Some(phase) = run.phases.iter_mut().find(|p| p.id == report.id) else {
+            continue;
+        };
+        if !report.outputs.is_empty() && phase.outputs != report.outputs {
+            phase.outputs = report.outputs.clone();
+            changed = true;
+        }
+        if phase.status == report.status {
+            continue;
+        }
+        if matches!(report.status, PhaseStatus::Running | PhaseStatus::AwaitingApproval) {
+            phase.started_at.get_or_insert(now);
+        } else if !matches!(report.status, PhaseStatus::Pending | PhaseStatus::Unused | PhaseStatus::Unchanged) && phase.finished_at.is_none() {
+            phase.finished_at = Some(now);
+        }
+        phase.status = report.status;
+        changed = true;
+    }
+    changed
+}
+
+/// Move a running run to `awaiting_approval` while one of its gates waits
+/// for a decision, and back once none does. Returns whether it changed.
+fn sync_approval_status(run: &mut Run) -> bool {
+    let awaiting = run.phases.iter().any(|p| p.status == PhaseStatus::AwaitingApproval);
+    let status = match run.status {
+        RunStatus::Running if awaiting => RunStatus::AwaitingApproval,
+        RunStatus::AwaitingApproval if !awaiting => RunStatus::Running,
+        status => status,
+    };
+    let changed = status != run.status;
+    run.status = status;
+    changed
+}
+
+/// Drop the oldest finished runs beyond the retention limit
+fn prune(runs: &mut HashMap<String, Run>) {
+    let mut finished: Vec<(DateTime<Utc>, String)> = runs
+        .values()
+        .filter(|r| r.status.is_finished())
+        .map(|r| (r.submitted_at, r.id.clone()))
+        .collect();
+    if finished.len() <= MAX_FINISHED_RUNS {
+        return;
+    }
+    finished.sort();
+    for (_, id) in finished.iter().take(finished.len() - MAX_FINISHED_RUNS) {
+        runs.remove(id);
+    }
+}
+
+struct RunObserver {
+    manager: RunManager,
+    run_id: String,
+}
+
+impl ExecutionObserver for RunObserver {
+    fn phases_updated(&self, phases: &[PhaseReport]) {
+        self.manager.update(&self.run_id, |run| {
+            let changed = apply_phase_statuses(run, phases);
+            sync_approval_status(run) || changed
+        });
+    }
+
+    fn attempt_finished(&self, record: &AttemptRecord) {
+        self.manager.save_attempt(&self.run_id, record);
+        self.manager.update(&self.run_id, |run| {
+            if let Some(phase) = run.phases.iter_mut().find(|p| p.id == record.phase_id) {
+                phase.attempts = record.attempt;
+                phase.message = record.message.clone();
+                phase.history.push(record.clone());
+            }
+            true
+        });
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
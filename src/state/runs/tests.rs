@@ -0,0 +1,289 @@
+#[cfg(test)]
+mod tests {
+    use crate::executor::driver::register_driver;
+    use crate::executor::driver::simulated::SimulatedDriver;
+    use crate::executor::instance::InstanceMode;
+    use crate::executor::scheduler::{PhaseReport, PhaseStatus, PlanReport, PlanStatus};
+    use crate::executor::{run_plan, ExecutionOptions};
+    use crate::model::*;
+    use crate::executor::control::{ApprovalDecision, ControlState};
+    use crate::state::runs::{RunCheckpoint, RunControlError, RunManager, RunSettings, RunStatus};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn create_test_phase(id: &str, wait_for: &[&str], driver: Option<&str>) -> Phase {
+        Phase {
+            kind: "Phase".to_string(),
+            id: id.to_string(),
+            spec: PhaseSpec {
+                description: format!("Test phase {}", id),
+                driver: driver.map(|d| d.to_string()),
+                wait_for: Some(WaitFor {
+                    phases: wait_for.iter().map(|s| s.to_string()).collect(),
+                    timeout: None,
+                }),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_create_registers_pending_run() {
+        let manager = RunManager::default();
+        let phases = vec![create_test_phase("setup", &[], None)];
+
+        let run = manager.create("plan-1", &phases, Default::default());
+        assert_eq!(run.status, RunStatus::Pending);
+        assert_eq!(run.plan_id, "plan-1");
+        assert_eq!(run.phases.len(), 1);
+        assert_eq!(run.phases[0].status, PhaseStatus::Pending);
+
+        let stored = manager.get(&run.id).unwrap();
+        assert_eq!(stored.id, run.id);
+        assert!(manager.get("missing").is_none());
+    }
+
+    #[test]
+    fn test_list_returns_newest_first() {
+        let manager = RunManager::default();
+        let first = manager.create("plan-1", &[], Default::default());
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = manager.create("plan-2", &[], Default::default());
+
+        let ids: Vec<String> = manager.list().into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![second.id, first.id]);
+    }
+
+    #[test]
+    fn test_finish_with_error_fails_run() {
+        let manager = RunManager::default();
+        let run = manager.create("plan-1", &[], Default::default());
+
+        manager.mark_started(&run.id);
+        assert_eq!(manager.get(&run.id).unwrap().status, RunStatus::Running);
+
+        manager.finish(&run.id, Err("redis unavailable".to_string()));
+        let run = manager.get(&run.id).unwrap();
+        assert_eq!(run.status, RunStatus::Failed);
+        assert_eq!(run.error.as_deref(), Some("redis unavailable"));
+        assert!(run.finished_at.is_some());
+    }
+
+    #[test]
+    fn test_pause_resume_and_cancel_transitions() {
+        let manager = RunManager::default();
+        let run = manager.create("plan-1", &[], Default::default());
+
+        let paused = manager.pause(&run.id).unwrap();
+        assert_eq!(paused.status, RunStatus::Paused);
+        assert_eq!(run.control().state(), ControlState::Paused);
+
+        // Starting a paused run keeps it paused; resuming it makes it running
+        manager.mark_started(&run.id);
+        assert_eq!(manager.get(&run.id).unwrap().status, RunStatus::Paused);
+        assert_eq!(manager.resume(&run.id).unwrap().status, RunStatus::Running);
+        assert!(matches!(
+            manager.resume(&run.id),
+            Err(RunControlError::InvalidTransition { status: RunStatus::Running, .. })
+        ));
+
+        manager.cancel(&run.id).unwrap();
+        assert_eq!(run.control().state(), ControlState::Cancelled);
+        assert!(manager.cancel(&run.id).is_err());
+        assert!(manager.pause(&run.id).is_err());
+        assert!(matches!(manager.pause("missing"), Err(RunControlError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_restore_from_checkpoint() {
+        let phases = vec![
+            create_test_phase("setup", &[], None),
+            create_test_phase("deploy", &["setup"], None),
+            create_test_phase("verify", &["deploy"], None),
+        ];
+        let manager = RunManager::default();
+        let mut run = manager.create("plan-1", &phases, RunSettings { max_concurrency: Some(4), incremental: true, ..Default::default() });
+        run.status = RunStatus::Running;
+        run.phases[0].status = PhaseStatus::Succeeded;
+        run.phases[0].attempts = 2;
+        run.phases[0].outputs.insert("cluster_id".to_string(), "c-42".to_string());
+        run.phases[1].status = PhaseStatus::Running;
+
+        // Checkpoints survive a round trip through JSON
+        let json = serde_json::to_string(&RunCheckpoint::from(&run)).unwrap();
+        let checkpoint: RunCheckpoint = serde_json::from_str(&json).unwrap();
+
+        let restarted = RunManager::default();
+        let restored = restarted.restore(checkpoint);
+        assert_eq!(restored.id, run.id);
+        assert_eq!(restored.status, RunStatus::Interrupted);
+        assert_eq!(restored.plan(), phases.as_slice());
+        assert_eq!(restored.settings().max_concurrency, Some(4));
+        assert!(restored.settings().incremental);
+        assert_eq!(restored.phases[0].attempts, 2);
+        assert_eq!(restored.phases[1].status, PhaseStatus::Pending);
+        assert_eq!(restored.completed_phases(), vec!["setup".to_string()]);
+        assert_eq!(restored.phase_outputs()["setup"]["cluster_id"], "c-42");
+
+        // Interrupted runs are restarted rather than resumed
+        assert!(restarted.resume(&run.id).is_err());
+        assert_eq!(restarted.restart(&run.id).unwrap().status, RunStatus::Pending);
+        assert!(restarted.restart(&run.id).is_err());
+    }
+
+    #[test]
+    fn test_cancel_interrupted_run_finishes_it() {
+        let manager = RunManager::default();
+        let run = manager.create("plan-1", &[], Default::default());
+        let restored = manager.restore(RunCheckpoint::from(&run));
+
+        let cancelled = manager.cancel(&restored.id).unwrap();
+        assert_eq!(cancelled.status, RunStatus::Cancelled);
+        assert!(cancelled.finished_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_observer_records_phase_progress() {
+        register_driver("test-runs-flaky", Arc::new(SimulatedDriver::default().failing_attempts(1)));
+        register_driver("test-runs-broken", Arc::new(SimulatedDriver::default().always_failing()));
+
+        let mut flaky = create_test_phase("runs-flaky", &[], Some("test-runs-flaky"));
+        flaky.spec.retry = Some(Retry { max_attempts: Some(2), ..Default::default() });
+        let phases = vec![
+            flaky,
+            create_test_phase("runs-broken", &["runs-flaky"], Some("test-runs-broken")),
+            create_test_phase("runs-after", &["runs-broken"], None),
+        ];
+
+        let manager = RunManager::default();
+        let run = manager.create("plan-1", &phases, Default::default());
+        let options = ExecutionOptions::default().with_observer(manager.observer(&run.id));
+        manager.mark_started(&run.id);
+        let report = run_plan(&phases, &options).await.unwrap();
+        manager.finish(&run.id, Ok(&report));
+
+        let run = manager.get(&run.id).unwrap();
+        assert_eq!(run.status, RunStatus::Failed);
+        let phase = |id: &str| run.phases.iter().find(|p| p.id == id).unwrap().clone();
+
+        let flaky = phase("runs-flaky");
+        assert_eq!(flaky.status, PhaseStatus::Succeeded);
+        assert_eq!(flaky.attempts, 2);
+        assert!(flaky.started_at.is_some() && flaky.finished_at.is_some());
+
+        assert_eq!(phase("runs-broken").status, PhaseStatus::Failed);
+        assert_eq!(phase("runs-broken").attempts, 1);
+
+        let history = manager.attempts(&run.id, "runs-flaky").unwrap();
+        let outcomes: Vec<(u32, bool)> = history.iter().map(|a| (a.attempt, a.is_success())).collect();
+        assert_eq!(outcomes, vec![(1, false), (2, true)]);
+        assert!(manager.attempts(&run.id, "missing").is_none());
+
+        let after = phase("runs-after");
+        assert_eq!(after.status, PhaseStatus::Skipped);
+        assert_eq!(after.attempts, 0);
+        assert!(after.started_at.is_none());
+    }
+
+    #[test]
+    fn test_use_phase_starts_run_with_completed_phases() {
+        let mut debug = create_test_phase("debug", &["setup"], None);
+        debug.spec.instance_mode = Some("onUse".to_string());
+        let phases = vec![create_test_phase("setup", &[], None), debug];
+        let manager = RunManager::default();
+        let run = manager.create("plan-1", &phases, Default::default());
+        assert_eq!(run.phases[1].instance_mode, InstanceMode::OnUse);
+        assert!(matches!(
+            manager.use_phase(&run.id, "debug"),
+            Err(RunControlError::InvalidTransition { status: RunStatus::Pending, .. })
+        ));
+
+        let report = PlanReport {
+            status: PlanStatus::Succeeded,
+            phases: vec![
+                PhaseReport {
+                    id: "setup".to_string(),
+                    status: PhaseStatus::Succeeded,
+                    outputs: HashMap::from([("token".to_string(), "t-1".to_string())]),
+                },
+                PhaseReport {
+                    id: "debug".to_string(),
+                    status: PhaseStatus::Unused,
+                    outputs: HashMap::new(),
+                },
+            ],
+            trace: Vec::new(),
+            teardown: Vec::new(),
+        };
+        manager.finish(&run.id, Ok(&report));
+        assert!(manager.get(&run.id).unwrap().phases[1].finished_at.is_none());
+
+        let used = manager.use_phase(&run.id, "debug").unwrap();
+        assert_ne!(used.id, run.id);
+        assert_eq!(used.plan_id, "plan-1");
+        assert_eq!(used.settings().requested, vec!["debug".to_string()]);
+        assert_eq!(used.completed_phases(), vec!["setup".to_string()]);
+        assert_eq!(used.phase_outputs()["setup"]["token"], "t-1");
+        assert_eq!(used.phases[1].status, PhaseStatus::Pending);
+
+        assert!(matches!(manager.use_phase(&run.id, "setup"), Err(RunControlError::NotOnUse { .. })));
+        assert!(matches!(manager.use_phase(&run.id, "missing"), Err(RunControlError::UnknownPhase { .. })));
+        assert!(matches!(manager.use_phase("missing", "debug"), Err(RunControlError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_decide_approval_gate() {
+        let mut gate = create_test_phase("runs-gate", &["runs-staging"], None);
+        gate.spec.approval = Some(ApprovalSpec::default());
+        let phases = vec![
+            create_test_phase("runs-staging", &[], Some("noop")),
+            gate,
+            create_test_phase("runs-production", &["runs-gate"], Some("noop")),
+        ];
+        let manager = RunManager::default();
+        let run = manager.create("plan-1", &phases, Default::default());
+        assert!(matches!(
+            manager.decide(&run.id, "runs-gate", ApprovalDecision::default()),
+            Err(RunControlError::NotAwaitingApproval { .. })
+        ));
+
+        let options = ExecutionOptions::default()
+            .with_observer(manager.observer(&run.id))
+            .with_control(run.control());
+        manager.mark_started(&run.id);
+        let task = tokio::spawn(async move { run_plan(&phases, &options).await });
+        for _ in 0..100 {
+            if manager.get(&run.id).unwrap().status == RunStatus::AwaitingApproval {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let waiting = manager.get(&run.id).unwrap();
+        assert_eq!(waiting.status, RunStatus::AwaitingApproval);
+        assert_eq!(waiting.phases[1].status, PhaseStatus::AwaitingApproval);
+        assert_eq!(waiting.phases[2].status, PhaseStatus::Pending);
+        assert!(matches!(
+            manager.decide(&run.id, "runs-production", ApprovalDecision::default()),
+            Err(RunControlError::NotAwaitingApproval { .. })
+        ));
+        assert!(matches!(
+            manager.decide(&run.id, "missing", ApprovalDecision::default()),
+            Err(RunControlError::UnknownPhase { .. })
+        ));
+
+        let decision = ApprovalDecision {
+            approved: true,
+            approver: Some("alice".to_string()),
+            comment: Some("staging looks good".to_string()),
+        };
+        manager.decide(&run.id, "runs-gate", decision.clone()).unwrap();
+        assert!(manager.decide(&run.id, "runs-gate", decision).is_err());
+        let report = task.await.unwrap().unwrap();
+        manager.finish(&run.id, Ok(&report));
+
+        let run = manager.get(&run.id).unwrap();
+        assert_eq!(run.status, RunStatus::Succeeded);
+        assert_eq!(run.phases[1].message.as_deref(), Some("Approved by alice: staging looks good"));
+        assert_eq!(run.phases[2].status, PhaseStatus::Succeeded);
+    }
+}
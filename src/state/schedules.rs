@@ -0,0 +1,340 @@
+use crate::model::Phase;
+use crate::state::redis::RedisClient;
+use crate::state::runs::RunSettings;
+use crate::state::tracker::{
+    claim_schedule_occurrence, load_schedules, load_schedules_file, remove_schedule, save_schedules_file,
+    store_schedule,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+/// What happens when a schedule comes due while the run it started last
+/// is still active
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// Let the occurrence pass
+    #[default]
+    Skip,
+    /// Start a run as soon as the active one finishes. Occurrences that
+    /// come due meanwhile are coalesced into that one run.
+    Queue,
+}
+
+/// A plan executed periodically, on a cron expression or at an interval
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Cron expression, in UTC (e.g. "0 3 * * *")
+    #[serde(default)]
+    pub cron: Option<String>,
+    /// Time between runs (e.g. "6h")
+    #[serde(default)]
+    pub interval: Option<String>,
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+    pub phases: Vec<Phase>,
+    /// How each run is executed
+    #[serde(default)]
+    pub settings: RunSettings,
+    pub created_at: DateTime<Utc>,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_run_id: Option<String>,
+    /// A run is waiting for the active one to finish
+    #[serde(default)]
+    pub queued: bool,
+}
+
+impl Schedule {
+    /// A schedule of `phases` first due at the next occurrence after now.
+    /// Exactly one of `cron` and `interval` must be given.
+    pub fn new(
+        name: Option<String>,
+        cron: Option<String>,
+        interval: Option<String>,
+        overlap: OverlapPolicy,
+        phases: Vec<Phase>,
+        settings: RunSettings,
+    ) -> Result<Self, ScheduleError> {
+        let now = Utc::now();
+        let mut schedule = Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            cron,
+            interval,
+            overlap,
+            phases,
+            settings,
+            created_at: now,
+            next_run_at: None,
+            last_run_at: None,
+            last_run_id: None,
+            queued: false,
+        };
+        schedule.next_run_at = Some(schedule.next_after(now)?);
+        Ok(schedule)
+    }
+
+    /// The first occurrence strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Result<DateTime<Utc>, ScheduleError> {
+        match (&self.cron, &self.interval) {
+            (Some(expression), None) => {
+                let schedule = parse_cron(expression)?;
+                schedule
+                    .after(&after)
+                    .next()
+                    .ok_or_else(|| ScheduleError::InvalidCron(format!("'{}' never occurs again", expression)))
+            }
+            (None, Some(interval)) => {
+                let interval = humantime::parse_duration(interval)
+                    .map_err(|e| ScheduleError::InvalidInterval(e.to_string()))?;
+                if interval.is_zero() {
+                    return Err(ScheduleError::InvalidInterval("must be longer than zero".to_string()));
+                }
+                chrono::Duration::from_std(interval)
+                    .ok()
+                    .and_then(|interval| after.checked_add_signed(interval))
+                    .ok_or_else(|| ScheduleError::InvalidInterval("too long".to_string()))
+            }
+            _ => Err(ScheduleError::Trigger),
+        }
+    }
+}
+
+/// Parse a cron expression. The usual five fields (minute to day of week)
+/// are accepted as well as the six- and seven-field forms with seconds and
+/// years.
+fn parse_cron(expression: &str) -> Result<cron::Schedule, ScheduleError> {
+    let expression = expression.trim();
+    let expression = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+    cron::Schedule::from_str(&expression).map_err(|e| ScheduleError::InvalidCron(e.to_string()))
+}
+
+/// Why a schedule was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleError {
+    /// Neither or both of `cron` and `interval` were given
+    Trigger,
+    InvalidCron(String),
+    InvalidInterval(String),
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::Trigger => write!(f, "exactly one of 'cron' and 'interval' is required"),
+            ScheduleError::InvalidCron(message) => write!(f, "invalid cron expression: {}", message),
+            ScheduleError::InvalidInterval(message) => write!(f, "invalid interval: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+/// Where schedules are persisted
+#[derive(Clone, Default)]
+enum ScheduleStore {
+    #[default]
+    Memory,
+    File(PathBuf),
+    Redis(Arc<RedisClient>),
+}
+
+/// Registry of schedules, persisted so they survive restarts
+#[derive(Clone, Default)]
+pub struct ScheduleManager {
+    schedules: Arc<RwLock<HashMap<String, Schedule>>>,
+    store: ScheduleStore,
+}
+
+impl ScheduleManager {
+    /// A manager that keeps schedules in Redis
+    pub fn with_redis(client: Arc<RedisClient>) -> Self {
+        Self {
+            schedules: Arc::default(),
+            store: ScheduleStore::Redis(client),
+        }
+    }
+
+    /// A manager that keeps schedules in a JSON file
+    pub fn with_file(path: PathBuf) -> Self {
+        Self {
+            schedules: Arc::default(),
+            store: ScheduleStore::File(path),
+        }
+    }
+
+    /// Register the persisted schedules; returns how many there are
+    pub async fn load(&self) -> usize {
+        let schedules = match &self.store {
+            ScheduleStore::Memory => Vec::new(),
+            ScheduleStore::File(path) => load_schedules_file(path),
+            ScheduleStore::Redis(client) => load_schedules(client).await.unwrap_or_default(),
+        };
+        let mut registered = self.schedules.write().unwrap();
+        for schedule in schedules {
+            registered.insert(schedule.id.clone(), schedule);
+        }
+        registered.len()
+    }
+
+    /// Replace the registered schedules with those in Redis, so schedules
+    /// created, changed or removed by other replicas are picked up. Other
+    /// stores belong to this instance alone and are left as they are.
+    pub async fn reload(&self) {
+        let ScheduleStore::Redis(client) = &self.store else {
+            return;
+        };
+        match load_schedules(client).await {
+            Ok(schedules) => {
+                *self.schedules.write().unwrap() = schedules.into_iter().map(|s| (s.id.clone(), s)).collect();
+            }
+            Err(e) => eprintln!("Failed to reload schedules: {e}"),
+        }
+    }
+
+    pub async fn create(&self, schedule: Schedule) -> Schedule {
+        self.schedules
+            .write()
+            .unwrap()
+            .insert(schedule.id.clone(), schedule.clone());
+        self.save(std::slice::from_ref(&schedule), None).await;
+        schedule
+    }
+
+    pub fn get(&self, id: &str) -> Option<Schedule> {
+        self.schedules.read().unwrap().get(id).cloned()
+    }
+
+    /// All schedules, oldest first
+    pub fn list(&self) -> Vec<Schedule> {
+        let mut schedules: Vec<Schedule> = self.schedules.read().unwrap().values().cloned().collect();
+        schedules.sort_by_key(|s| s.created_at);
+        schedules
+    }
+
+    /// Stop and forget a schedule; runs it already started carry on
+    pub async fn remove(&self, id: &str) -> Option<Schedule> {
+        let removed = self.schedules.write().unwrap().remove(id)?;
+        self.save(&[], Some(id)).await;
+        Some(removed)
+    }
+
+    /// Schedules that should start a run at `now`. Schedules that came due
+    /// are moved on to their next occurrence; when the run a schedule
+    /// started last is still `active`, the occurrence is skipped or queued
+    /// according to its overlap policy. A queued run is returned once
+    /// that run has finished. With Redis, each run is only returned to the
+    /// replica that claims its occurrence.
+    pub async fn due(&self, now: DateTime<Utc>, active: impl Fn(&str) -> bool) -> Vec<Schedule> {
+        let mut start = Vec::new();
+        let mut changed = Vec::new();
+        for mut schedule in self.list() {
+            let came_due = schedule.next_run_at.is_some_and(|next| next <= now);
+            if !came_due && !schedule.queued {
+                continue;
+            }
+            let running = schedule.last_run_id.clone().filter(|id| active(id));
+            match running {
+                Some(run_id) if came_due && schedule.overlap == OverlapPolicy::Queue => {
+                    println!("Schedule {} is due while run {} is active; queueing", schedule.id, run_id);
+                    schedule.queued = true;
+                }
+                Some(run_id) if came_due => {
+                    println!("Schedule {} is due while run {} is active; skipping", schedule.id, run_id);
+                }
+                Some(_) => continue,
+                None => {
+                    let occurrence = match schedule.next_run_at.filter(|_| came_due) {
+                        Some(next) => next.timestamp_millis().to_string(),
+                        None => format!("after:{}", schedule.last_run_id.as_deref().unwrap_or_default()),
+                    };
+                    if !self.claim(&schedule.id, &occurrence).await {
+                        continue;
+                    }
+                    schedule.queued = false;
+                    start.push(schedule.clone());
+                }
+            }
+            if came_due {
+                schedule.next_run_at = schedule.next_after(now).ok();
+            }
+            changed.push(schedule);
+        }
+
+        {
+            let mut schedules = self.schedules.write().unwrap();
+            for schedule in &changed {
+                // A schedule removed meanwhile stays removed
+                if let Some(registered) = schedules.get_mut(&schedule.id) {
+                    *registered = schedule.clone();
+                }
+            }
+        }
+        self.save(&changed, None).await;
+        start
+    }
+
+    /// Whether this replica gets to start the run for `occurrence` of
+    /// schedule `id`
+    async fn claim(&self, id: &str, occurrence: &str) -> bool {
+        match &self.store {
+            ScheduleStore::Redis(client) => claim_schedule_occurrence(client, id, occurrence).await,
+            _ => true,
+        }
+    }
+
+    /// Record that schedule `id` started run `run_id` at `at`, or only
+    /// simulated its plan when there is no run
+    pub async fn record_run(&self, id: &str, run_id: Option<&str>, at: DateTime<Utc>) {
+        let updated = {
+            let mut schedules = self.schedules.write().unwrap();
+            let Some(schedule) = schedules.get_mut(id) else {
+                return;
+            };
+            schedule.last_run_at = Some(at);
+            schedule.last_run_id = run_id.map(str::to_string);
+            schedule.clone()
+        };
+        self.save(&[updated], None).await;
+    }
+
+    /// Persist `changed` schedules and the removal of `removed`
+    async fn save(&self, changed: &[Schedule], removed: Option<&str>) {
+        match &self.store {
+            ScheduleStore::Memory => {}
+            ScheduleStore::File(path) => {
+                if changed.is_empty() && removed.is_none() {
+                    return;
+                }
+                if let Err(e) = save_schedules_file(path, &self.list()) {
+                    eprintln!("Failed to save schedules to {}: {}", path.display(), e);
+                }
+            }
+            ScheduleStore::Redis(client) => {
+                for schedule in changed {
+                    store_schedule(client, schedule).await;
+                }
+                if let Some(id) = removed {
+                    remove_schedule(client, id).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use crate::model::*;
+    use crate::state::runs::RunSettings;
+    use crate::state::schedules::{OverlapPolicy, Schedule, ScheduleError, ScheduleManager};
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    fn create_test_phase(id: &str) -> Phase {
+        Phase {
+            kind: "Phase".to_string(),
+            id: id.to_string(),
+            spec: PhaseSpec {
+                description: format!("Test phase {}", id),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn create_schedule(cron: Option<&str>, interval: Option<&str>, overlap: OverlapPolicy) -> Result<Schedule, ScheduleError> {
+        Schedule::new(
+            Some("nightly".to_string()),
+            cron.map(|c| c.to_string()),
+            interval.map(|i| i.to_string()),
+            overlap,
+            vec![create_test_phase("sync")],
+            RunSettings::default(),
+        )
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_new_requires_one_valid_trigger() {
+        assert_eq!(create_schedule(None, None, OverlapPolicy::Skip), Err(ScheduleError::Trigger));
+        assert_eq!(
+            create_schedule(Some("0 3 * * *"), Some("1h"), OverlapPolicy::Skip),
+            Err(ScheduleError::Trigger)
+        );
+        assert!(matches!(
+            create_schedule(Some("not a cron"), None, OverlapPolicy::Skip),
+            Err(ScheduleError::InvalidCron(_))
+        ));
+        assert!(matches!(
+            create_schedule(None, Some("often"), OverlapPolicy::Skip),
+            Err(ScheduleError::InvalidInterval(_))
+        ));
+        assert!(matches!(
+            create_schedule(None, Some("0s"), OverlapPolicy::Skip),
+            Err(ScheduleError::InvalidInterval(_))
+        ));
+
+        let schedule = create_schedule(None, Some("1h"), OverlapPolicy::Skip).unwrap();
+        assert!(schedule.next_run_at.unwrap() > schedule.created_at);
+        assert_eq!(schedule.last_run_at, None);
+    }
+
+    #[test]
+    fn test_next_after() {
+        let cron = create_schedule(Some("30 3 * * *"), None, OverlapPolicy::Skip).unwrap();
+        assert_eq!(cron.next_after(at(1, 0)).unwrap(), at(3, 30));
+        assert_eq!(cron.next_after(at(3, 30)).unwrap(), at(3, 30) + Duration::days(1));
+
+        let seconds = create_schedule(Some("0 */15 * * * *"), None, OverlapPolicy::Skip).unwrap();
+        assert_eq!(seconds.next_after(at(1, 7)).unwrap(), at(1, 15));
+
+        let interval = create_schedule(None, Some("6h"), OverlapPolicy::Skip).unwrap();
+        assert_eq!(interval.next_after(at(1, 0)).unwrap(), at(7, 0));
+    }
+
+    #[tokio::test]
+    async fn test_due_skips_while_last_run_active() {
+        let manager = ScheduleManager::default();
+        let mut schedule = create_schedule(None, Some("1h"), OverlapPolicy::Skip).unwrap();
+        schedule.next_run_at = Some(at(1, 0));
+        let schedule = manager.create(schedule).await;
+
+        assert!(manager.due(at(0, 59), |_| false).await.is_empty());
+
+        let due = manager.due(at(1, 0), |_| false).await;
+        assert_eq!(due.len(), 1);
+        manager.record_run(&schedule.id, Some("run-1"), at(1, 0)).await;
+        let recorded = manager.get(&schedule.id).unwrap();
+        assert_eq!(recorded.next_run_at, Some(at(2, 0)));
+        assert_eq!(recorded.last_run_at, Some(at(1, 0)));
+        assert_eq!(recorded.last_run_id.as_deref(), Some("run-1"));
+
+        assert!(manager.due(at(2, 0), |id| id == "run-1").await.is_empty());
+        let skipped = manager.get(&schedule.id).unwrap();
+        assert_eq!(skipped.next_run_at, Some(at(3, 0)));
+        assert!(!skipped.queued);
+        assert!(manager.due(at(2, 30), |_| false).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_due_queues_while_last_run_active() {
+        let manager = ScheduleManager::default();
+        let mut schedule = create_schedule(None, Some("1h"), OverlapPolicy::Queue).unwrap();
+        schedule.next_run_at = Some(at(1, 0));
+        schedule.last_run_id = Some("run-1".to_string());
+        let schedule = manager.create(schedule).await;
+
+        assert!(manager.due(at(1, 0), |_| true).await.is_empty());
+        assert!(manager.get(&schedule.id).unwrap().queued);
+        assert!(manager.due(at(1, 10), |_| true).await.is_empty());
+
+        let due = manager.due(at(1, 20), |_| false).await;
+        assert_eq!(due.len(), 1);
+        let started = manager.get(&schedule.id).unwrap();
+        assert!(!started.queued);
+        assert_eq!(started.next_run_at, Some(at(2, 0)));
+        assert!(manager.due(at(1, 30), |_| false).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schedules.json");
+        let manager = ScheduleManager::with_file(path.clone());
+        let kept = manager
+            .create(create_schedule(Some("0 3 * * *"), None, OverlapPolicy::Queue).unwrap())
+            .await;
+        let removed = manager
+            .create(create_schedule(None, Some("5m"), OverlapPolicy::Skip).unwrap())
+            .await;
+        manager.record_run(&kept.id, Some("run-1"), at(3, 0)).await;
+        assert!(manager.remove(&removed.id).await.is_some());
+        assert!(manager.remove(&removed.id).await.is_none());
+
+        let restored = ScheduleManager::with_file(path);
+        assert_eq!(restored.load().await, 1);
+        assert_eq!(restored.list(), manager.list());
+        assert_eq!(restored.get(&kept.id).unwrap().last_run_id.as_deref(), Some("run-1"));
+    }
+}
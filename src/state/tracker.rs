@@ -5,54 +5,6 @@ pub fn save_state_file(phases: &[Phase]) -> std::io::Result<()> {
     std::fs::write(path, json)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::model::{Phase, PhaseSpec, Selector};
-    use std::collections::HashMap;
-    use std::env;
-    use tempfile::TempDir;
-
-    fn sample_phases() -> Vec<Phase> {
-        vec![Phase {
-            kind: "TestKind".to_string(),
-            id: "id1".to_string(),
-            spec: PhaseSpec {
-                description: "desc".to_string(),
-                selector: Selector { match_labels: HashMap::new() },
-                instance_mode: None,
-                wait_for: None,
-                retry: None,
-                on_failure: None,
-                on_success: None,
-            },
-        }]
-    }
-
-    #[test]
-    fn test_save_and_load_state_file() {
-        // Use a temporary directory for planter root
-        let tmp = TempDir::new().expect("create temp dir");
-        env::set_var("PLANTER_ROOT", tmp.path());
-        // Save sample phases
-        let phases = sample_phases();
-        save_state_file(&phases).expect("save state");
-        // Load back
-        let loaded = load_state_file().expect("load state");
-        assert_eq!(loaded, phases);
-    }
-
-    #[test]
-    fn test_load_missing_file() {
-        // Use a fresh temp dir without saving
-        let tmp = TempDir::new().expect("create temp dir");
-        env::set_var("PLANTER_ROOT", tmp.path());
-        // No file exists yet
-        let loaded = load_state_file();
-        assert!(loaded.is_none());
-    }
-}
-
 pub fn load_state_file() -> Option<Vec<Phase>> {
     let path = state_file_path();
     let data = std::fs::read_to_string(path).ok()?;
@@ -61,11 +13,27 @@ pub fn load_state_file() -> Option<Vec<Phase>> {
 // pub async fn save_plan(phases: &[Phase]) -> Result<()>;
 // pub async fn load_current_plan() -> Result<Vec<Phase>>;
 // pub async fn get_phase(id: &str) -> Option<Phase>;
+use crate::executor::attempt::AttemptRecord;
+use crate::executor::outputs::PhaseOutputs;
 use crate::model::Phase;
-use crate::state::redis::{RedisClient, get_json, set_json};
+use crate::state::redis::{RedisClient, get_json, hdel, hgetall_json, hset_json, set_json};
+use crate::state::runs::RunCheckpoint;
+use crate::state::schedules::Schedule;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
 
 const PLAN_CURRENT_KEY: &str = "plan:current";
 const PLAN_APPLIED_KEY: &str = "plan:applied";
+const PHASES_COMPLETED_KEY: &str = "phases:completed";
+const RUN_CHECKPOINTS_KEY: &str = "runs:checkpoints";
+const RUN_LEASE_KEY: &str = "runs:lease";
+const RUN_ATTEMPTS_KEY: &str = "runs:attempts";
+const PHASES_OUTPUTS_KEY: &str = "phases:outputs";
+const SCHEDULES_KEY: &str = "schedules";
+const SCHEDULE_CLAIM_KEY: &str = "schedules:claim";
+/// How long a claimed schedule occurrence is remembered
+const SCHEDULE_CLAIM_TTL: Duration = Duration::from_secs(24 * 60 * 60);
 
 pub async fn store_current_plan(client: &RedisClient, phases: &[Phase]) {
     // Namespace key by tenant
@@ -98,3 +66,298 @@ pub async fn load_applied_plan(client: &RedisClient) -> Option<Vec<Phase>> {
     let key = format!("{}:{}", tenant, PLAN_APPLIED_KEY);
     get_json(client, &key).await.ok().flatten()
 }
+
+/// Record the phases completed by the last applied run, replacing those of
+/// earlier runs
+pub async fn store_completed_phases(client: &RedisClient, ids: &[String]) {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    let key = format!("{}:{}", tenant, PHASES_COMPLETED_KEY);
+    if let Err(e) = set_json(client, &key, ids).await {
+        eprintln!("Failed to store completed phases: {e}");
+    }
+}
+
+pub async fn load_completed_phases(client: &RedisClient) -> Vec<String> {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    let key = format!("{}:{}", tenant, PHASES_COMPLETED_KEY);
+    get_json(client, &key).await.ok().flatten().unwrap_or_default()
+}
+
+/// Record the outputs last published by phases, replacing earlier ones
+pub async fn store_phase_outputs(client: &RedisClient, outputs: &HashMap<String, PhaseOutputs>) {
+    if outputs.is_empty() {
+        return;
+    }
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    let key = format!("{}:{}", tenant, PHASES_OUTPUTS_KEY);
+    let mut stored = load_phase_outputs(client).await;
+    stored.extend(outputs.iter().map(|(id, o)| (id.clone(), o.clone())));
+    if let Err(e) = set_json(client, &key, &stored).await {
+        eprintln!("Failed to store phase outputs: {e}");
+    }
+}
+
+/// Outputs last published by each phase, keyed by phase id
+pub async fn load_phase_outputs(client: &RedisClient) -> HashMap<String, PhaseOutputs> {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    let key = format!("{}:{}", tenant, PHASES_OUTPUTS_KEY);
+    get_json(client, &key).await.ok().flatten().unwrap_or_default()
+}
+
+/// Save the progress of an unfinished run
+pub async fn store_run_checkpoint(client: &RedisClient, checkpoint: &RunCheckpoint) {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    let key = format!("{}:{}", tenant, RUN_CHECKPOINTS_KEY);
+    if let Err(e) = hset_json(client, &key, &checkpoint.run.id, checkpoint).await {
+        eprintln!("Failed to store checkpoint of run {}: {e}", checkpoint.run.id);
+    }
+}
+
+/// Forget a run's checkpoint and lease once it has finished
+pub async fn remove_run_checkpoint(client: &RedisClient, run_id: &str) {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    let key = format!("{}:{}", tenant, RUN_CHECKPOINTS_KEY);
+    if let Err(e) = hdel(client, &key, run_id).await {
+        eprintln!("Failed to remove checkpoint of run {run_id}: {e}");
+    }
+    let lease: redis::RedisResult<()> = redis::cmd("DEL")
+        .arg(run_lease_key(run_id))
+        .query_async(&mut *client.lock().await)
+        .await;
+    if let Err(e) = lease {
+        eprintln!("Failed to remove lease of run {run_id}: {e}");
+    }
+}
+
+fn run_lease_key(run_id: &str) -> String {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    format!("{}:{}:{}", tenant, RUN_LEASE_KEY, run_id)
+}
+
+/// Record `owner` as the replica holding run `run_id` for the next `ttl`,
+/// unless another replica took the run over since. Returns false only in
+/// that case; if Redis cannot be reached nobody else can take the run over
+/// either, and the lease is left to expire.
+pub async fn renew_run_lease(client: &RedisClient, run_id: &str, owner: &str, ttl: Duration) -> bool {
+    let renewed: redis::RedisResult<i64> = redis::Script::new(
+        "local holder = redis.call('GET', KEYS[1]) \
+         if holder == ARGV[1] then \
+             return redis.call('PEXPIRE', KEYS[1], ARGV[2]) \
+         elseif not holder then \
+             redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2]) \
+             return 1 \
+         else return 0 end",
+    )
+    .key(run_lease_key(run_id))
+    .arg(owner)
+    .arg(ttl.as_millis() as u64)
+    .invoke_async(&mut *client.lock().await)
+    .await;
+    match renewed {
+        Ok(renewed) => renewed == 1,
+        Err(e) => {
+            eprintln!("Failed to renew lease of run {run_id}: {e}");
+            true
+        }
+    }
+}
+
+/// Take over run `run_id` for `owner`, unless another replica holds a lease
+/// on it that has not expired. Also fails if Redis cannot be reached, so a
+/// run is never restored by two replicas.
+pub async fn claim_run_lease(client: &RedisClient, run_id: &str, owner: &str, ttl: Duration) -> bool {
+    let key = run_lease_key(run_id);
+    let claimed: redis::RedisResult<Option<String>> = redis::cmd("SET")
+        .arg(&key)
+        .arg(owner)
+        .arg("NX")
+        .arg("PX")
+        .arg(ttl.as_millis() as u64)
+        .query_async(&mut *client.lock().await)
+        .await;
+    match claimed {
+        Ok(Some(_)) => true,
+        Ok(None) => {
+            let holder: Option<String> = redis::cmd("GET")
+                .arg(&key)
+                .query_async(&mut *client.lock().await)
+                .await
+                .unwrap_or(None);
+            holder.as_deref() == Some(owner) && renew_run_lease(client, run_id, owner, ttl).await
+        }
+        Err(e) => {
+            eprintln!("Failed to claim lease of run {run_id}: {e}");
+            false
+        }
+    }
+}
+
+/// The checkpoint of run `run_id`, if it has not finished
+pub async fn load_run_checkpoint(client: &RedisClient, run_id: &str) -> Option<RunCheckpoint> {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    let key = format!("{}:{}", tenant, RUN_CHECKPOINTS_KEY);
+    let data: Option<String> = redis::cmd("HGET")
+        .arg(&key)
+        .arg(run_id)
+        .query_async(&mut *client.lock().await)
+        .await
+        .unwrap_or(None);
+    data.and_then(|d| serde_json::from_str(&d).ok())
+}
+
+/// Checkpoints of runs that had not finished when they were last saved
+pub async fn load_run_checkpoints(client: &RedisClient) -> Vec<RunCheckpoint> {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    let key = format!("{}:{}", tenant, RUN_CHECKPOINTS_KEY);
+    hgetall_json(client, &key).await.unwrap_or_default()
+}
+
+fn run_attempts_key(run_id: &str, phase_id: &str) -> String {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    format!("{}:{}:{}:{}", tenant, RUN_ATTEMPTS_KEY, run_id, phase_id)
+}
+
+/// Append an attempt of a phase to the records of run `run_id`
+pub async fn store_run_attempt(client: &RedisClient, run_id: &str, record: &AttemptRecord) {
+    let stored: redis::RedisResult<()> = match serde_json::to_string(record) {
+        Ok(json) => redis::cmd("RPUSH")
+            .arg(run_attempts_key(run_id, &record.phase_id))
+            .arg(json)
+            .query_async(&mut *client.lock().await)
+            .await,
+        Err(e) => {
+            eprintln!("Failed to serialize attempt {} of phase {}: {e}", record.attempt, record.phase_id);
+            return;
+        }
+    };
+    if let Err(e) = stored {
+        eprintln!("Failed to store attempt {} of phase {} in run {run_id}: {e}", record.attempt, record.phase_id);
+    }
+}
+
+/// Every recorded attempt of phase `phase_id` in run `run_id`, oldest first
+pub async fn load_run_attempts(client: &RedisClient, run_id: &str, phase_id: &str) -> Vec<AttemptRecord> {
+    let entries: Vec<String> = redis::cmd("LRANGE")
+        .arg(run_attempts_key(run_id, phase_id))
+        .arg(0)
+        .arg(-1)
+        .query_async(&mut *client.lock().await)
+        .await
+        .unwrap_or_default();
+    entries.iter().filter_map(|e| serde_json::from_str(e).ok()).collect()
+}
+
+/// Save a schedule, replacing any earlier version of it
+pub async fn store_schedule(client: &RedisClient, schedule: &Schedule) {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    let key = format!("{}:{}", tenant, SCHEDULES_KEY);
+    if let Err(e) = hset_json(client, &key, &schedule.id, schedule).await {
+        eprintln!("Failed to store schedule {}: {e}", schedule.id);
+    }
+}
+
+pub async fn remove_schedule(client: &RedisClient, id: &str) {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    let key = format!("{}:{}", tenant, SCHEDULES_KEY);
+    if let Err(e) = hdel(client, &key, id).await {
+        eprintln!("Failed to remove schedule {id}: {e}");
+    }
+}
+
+pub async fn load_schedules(client: &RedisClient) -> redis::RedisResult<Vec<Schedule>> {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    let key = format!("{}:{}", tenant, SCHEDULES_KEY);
+    hgetall_json(client, &key).await
+}
+
+/// Claim one occurrence of schedule `id` so only one replica starts a run
+/// for it. Returns false if another replica claimed it first, or if Redis
+/// cannot be reached.
+pub async fn claim_schedule_occurrence(client: &RedisClient, id: &str, occurrence: &str) -> bool {
+    // Namespace key by tenant
+    let tenant = std::env::var("TENANT_KEY").unwrap_or_else(|_| "global".to_string());
+    let key = format!("{}:{}:{}:{}", tenant, SCHEDULE_CLAIM_KEY, id, occurrence);
+    let claimed: redis::RedisResult<Option<String>> = redis::cmd("SET")
+        .arg(&key)
+        .arg(1)
+        .arg("NX")
+        .arg("PX")
+        .arg(SCHEDULE_CLAIM_TTL.as_millis() as u64)
+        .query_async(&mut *client.lock().await)
+        .await;
+    match claimed {
+        Ok(claimed) => claimed.is_some(),
+        Err(e) => {
+            eprintln!("Failed to claim occurrence {occurrence} of schedule {id}: {e}");
+            false
+        }
+    }
+}
+
+/// Write every schedule to `path`, for when Redis is not configured
+pub fn save_schedules_file(path: &Path, schedules: &[Schedule]) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(schedules)?;
+    std::fs::write(path, json)
+}
+
+pub fn load_schedules_file(path: &Path) -> Vec<Schedule> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Phase, PhaseSpec};
+    use std::env;
+    use tempfile::TempDir;
+
+    fn sample_phases() -> Vec<Phase> {
+        vec![Phase {
+            kind: "TestKind".to_string(),
+            id: "id1".to_string(),
+            spec: PhaseSpec {
+                description: "desc".to_string(),
+                ..Default::default()
+            },
+        }]
+    }
+
+    #[test]
+    fn test_save_and_load_state_file() {
+        // Use a temporary directory for planter root
+        let tmp = TempDir::new().expect("create temp dir");
+        env::set_var("PLANTER_ROOT", tmp.path());
+        // Save sample phases
+        let phases = sample_phases();
+        save_state_file(&phases).expect("save state");
+        // Load back
+        let loaded = load_state_file().expect("load state");
+        assert_eq!(loaded, phases);
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        // Use a fresh temp dir without saving
+        let tmp = TempDir::new().expect("create temp dir");
+        env::set_var("PLANTER_ROOT", tmp.path());
+        // No file exists yet
+        let loaded = load_state_file();
+        assert!(loaded.is_none());
+    }
+}
@@ -5,7 +5,7 @@ use tokio::time::sleep;
 
 // Import the app construction from main.rs
 use planter::routes::plan::AppState;
-use planter::log::{init_logger, LoggingService};
+use planter::log::init_logger;
 use axum::{Router, routing::{get, post}};
 
 async fn spawn_server() {
@@ -15,6 +15,10 @@ async fn spawn_server() {
         nats_client: None,
         logging_service: planter::log::LoggingService::new(None),
         tenant_key: "global".to_string(),
+        run_manager: planter::state::runs::RunManager::default(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
     };
     // Get prefix from environment variable for test
     let prefix = std::env::var("PLANTER_PREFIX").unwrap_or_else(|_| "".to_string());
@@ -79,7 +83,7 @@ async fn test_all_endpoints() {
             }
         }
     ]);
-    let res = client.post(&format!("{}/plan", base_url))
+    let res = client.post(format!("{}/plan", base_url))
         .json(&plan)
         .send()
         .await
@@ -87,56 +91,56 @@ async fn test_all_endpoints() {
     assert!(res.status().is_success(), "/plan: {}", res.status());
 
     // /state
-    let res = client.get(&format!("{}/state", base_url))
+    let res = client.get(format!("{}/state", base_url))
         .send()
         .await
         .expect("Failed to send /state request");
     assert!(res.status().is_success(), "/state: {}", res.status());
 
     // /diff
-    let res = client.get(&format!("{}/diff", base_url))
+    let res = client.get(format!("{}/diff", base_url))
         .send()
         .await
         .expect("Failed to send /diff request");
     assert!(res.status().is_success(), "/diff: {}", res.status());
 
     // /logs
-    let res = client.get(&format!("{}/logs", base_url))
+    let res = client.get(format!("{}/logs", base_url))
         .send()
         .await
         .expect("Failed to send /logs request");
     assert!(res.status().is_success(), "/logs: {}", res.status());
 
     // /phases/:id
-    let res = client.get(&format!("{}/phases/test-phase", base_url))
+    let res = client.get(format!("{}/phases/test-phase", base_url))
         .send()
         .await
         .expect("Failed to send /phases/:id request");
     assert!(res.status().is_success(), "/phases/:id: {}", res.status());
 
     // /apply
-    let res = client.post(&format!("{}/apply", base_url))
+    let res = client.post(format!("{}/apply", base_url))
         .send()
         .await
         .expect("Failed to send /apply request");
     assert!(res.status().is_success(), "/apply: {}", res.status());
 
     // /health
-    let res = client.get(&format!("{}/health", base_url))
+    let res = client.get(format!("{}/health", base_url))
         .send()
         .await
         .expect("Failed to send /health request");
     assert!(res.status().is_success(), "/health: {}", res.status());
 
     // /ready
-    let res = client.get(&format!("{}/ready", base_url))
+    let res = client.get(format!("{}/ready", base_url))
         .send()
         .await
         .expect("Failed to send /ready request");
     assert!(res.status().is_success(), "/ready: {}", res.status());
 
     // /metrics
-    let res = client.get(&format!("{}/metrics", base_url))
+    let res = client.get(format!("{}/metrics", base_url))
         .send()
         .await
         .expect("Failed to send /metrics request");
@@ -17,6 +17,10 @@ fn create_test_app() -> Router {
         nats_client: None,
         logging_service: LoggingService::new(None),
         tenant_key: "global".to_string(),
+        run_manager: planter::state::runs::RunManager::default(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
     };
     Router::new()
         .route("/plan", post(submit_plan))
@@ -35,11 +39,7 @@ fn create_test_phase(id: &str, description: &str) -> Phase {
                     .cloned()
                     .collect(),
             },
-            instance_mode: None,
-            wait_for: None,
-            retry: None,
-            on_failure: None,
-            on_success: None,
+            ..Default::default()
         },
     }
 }
@@ -82,13 +82,14 @@ async fn test_submit_plan_complex() {
                 .cloned()
                 .collect(),
             },
-            instance_mode: Some("parallel".to_string()),
+            instance_mode: Some("immediate".to_string()),
             wait_for: Some(WaitFor {
                 phases: vec!["initialization".to_string(), "preflight".to_string()],
                 timeout: Some("30s".to_string()),
             }),
             retry: Some(Retry {
                 max_attempts: Some(3),
+                ..Default::default()
             }),
             on_failure: Some(Handler {
                 action: Some("continue".to_string()),
@@ -105,7 +106,7 @@ async fn test_submit_plan_complex() {
                 }),
             }),
             on_success: Some(Handler {
-                action: Some("log".to_string()),
+                action: Some("continue".to_string()),
                 spec: Some(HandlerSpec {
                     message: vec!["Phase completed successfully".to_string()],
                     notify: None,
@@ -115,10 +116,15 @@ async fn test_submit_plan_complex() {
                         .collect()),
                 }),
             }),
+            ..Default::default()
         },
     };
 
-    let phases = vec![complex_phase];
+    let phases = vec![
+        create_test_phase("preflight", "Check prerequisites"),
+        create_test_phase("initialization", "Bootstrap state"),
+        complex_phase,
+    ];
 
     let request = Request::builder()
         .method("POST")
@@ -162,3 +168,515 @@ async fn test_submit_plan_invalid_json() {
     let response = app.oneshot(request).await.unwrap();
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
+
+#[tokio::test]
+async fn test_submit_plan_unknown_dependency() {
+    let app = create_test_app();
+
+    let mut phase = create_test_phase("deploy", "Deploy application");
+    phase.spec.wait_for = Some(WaitFor {
+        phases: vec!["missing".to_string()],
+        timeout: None,
+    });
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/plan")
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::to_string(&vec![phase]).unwrap()))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_submit_plan_rejects_unknown_instance_mode_and_requested_phase() {
+    let mut phase = create_test_phase("debug", "Debug shell");
+    phase.spec.instance_mode = Some("sometimes".to_string());
+    for (uri, phase) in [
+        ("/plan", phase.clone()),
+        ("/plan?use=missing", create_test_phase("debug", "Debug shell")),
+    ] {
+        let request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&vec![phase]).unwrap()))
+            .unwrap();
+
+        let response = create_test_app().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn test_submit_plan_dependency_cycle() {
+    let app = create_test_app();
+
+    let mut setup = create_test_phase("setup", "Initialize system");
+    setup.spec.wait_for = Some(WaitFor {
+        phases: vec!["deploy".to_string()],
+        timeout: None,
+    });
+    let mut deploy = create_test_phase("deploy", "Deploy application");
+    deploy.spec.wait_for = Some(WaitFor {
+        phases: vec!["setup".to_string()],
+        timeout: None,
+    });
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/plan")
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::to_string(&vec![setup, deploy]).unwrap()))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_submit_plan_dry_run_returns_trace() {
+    let app = create_test_app();
+
+    let mut deploy = create_test_phase("deploy", "Deploy application");
+    deploy.spec.command = Some(CommandSpec {
+        program: "false".to_string(),
+        args: vec![],
+        env: Default::default(),
+        working_dir: None,
+        stdin: None,
+    });
+    deploy.spec.wait_for = Some(WaitFor {
+        phases: vec!["setup".to_string()],
+        timeout: None,
+    });
+    let phases = vec![deploy, create_test_phase("setup", "Initialize system")];
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/plan?dryRun=true")
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::to_string(&phases).unwrap()))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["dry_run"], true);
+    assert_eq!(json["plan_status"], "succeeded");
+    let trace = json["trace"].as_array().unwrap();
+    assert_eq!(trace[0]["id"], "setup");
+    assert_eq!(trace[1]["id"], "deploy");
+    assert_eq!(trace[1]["driver"], "command");
+    assert_eq!(trace[1]["action"], "run `false`");
+}
+
+
+#[tokio::test]
+async fn test_get_runs() {
+    let run_manager = planter::state::runs::RunManager::default();
+    let app_state = AppState {
+        redis_client: None,
+        nats_client: None,
+        logging_service: LoggingService::new(None),
+        tenant_key: "global".to_string(),
+        run_manager: run_manager.clone(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
+    };
+    let app = Router::new()
+        .route("/runs", axum::routing::get(planter::routes::runs::list_runs))
+        .route("/runs/:id", axum::routing::get(planter::routes::runs::get_run))
+        .with_state(app_state);
+
+    let run = run_manager.create("plan-1", &[create_test_phase("setup", "Initialize system")], Default::default());
+    run_manager.mark_started(&run.id);
+
+    let request = Request::builder().uri("/runs").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["count"], 1);
+    assert_eq!(json["runs"][0]["id"], run.id.as_str());
+
+    let request = Request::builder().uri(format!("/runs/{}", run.id)).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["run"]["plan_id"], "plan-1");
+    assert_eq!(json["run"]["status"], "running");
+    assert_eq!(json["run"]["phases"][0]["id"], "setup");
+    assert_eq!(json["run"]["phases"][0]["status"], "pending");
+
+    let request = Request::builder().uri("/runs/unknown").body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_run_control_endpoints() {
+    let run_manager = planter::state::runs::RunManager::default();
+    let app_state = AppState {
+        redis_client: None,
+        nats_client: None,
+        logging_service: LoggingService::new(None),
+        tenant_key: "global".to_string(),
+        run_manager: run_manager.clone(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
+    };
+    let app = Router::new()
+        .route("/runs/:id/pause", post(planter::routes::runs::pause_run))
+        .route("/runs/:id/resume", post(planter::routes::runs::resume_run))
+        .route("/runs/:id/cancel", post(planter::routes::runs::cancel_run))
+        .with_state(app_state);
+    let run = run_manager.create("plan-1", &[create_test_phase("setup", "Initialize system")], Default::default());
+
+    let control = |action: &str, id: &str| {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/runs/{}/{}", id, action))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(control("pause", &run.id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["run"]["status"], "paused");
+
+    let response = app.clone().oneshot(control("pause", &run.id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app.clone().oneshot(control("resume", &run.id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.clone().oneshot(control("cancel", &run.id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.oneshot(control("cancel", "unknown")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_get_phase_attempts() {
+    let run_manager = planter::state::runs::RunManager::default();
+    let app_state = AppState {
+        redis_client: None,
+        nats_client: None,
+        logging_service: LoggingService::new(None),
+        tenant_key: "global".to_string(),
+        run_manager: run_manager.clone(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
+    };
+    let app = Router::new()
+        .route("/runs/:id/phases/:phase/attempts", axum::routing::get(planter::routes::runs::get_phase_attempts))
+        .with_state(app_state);
+    let run = run_manager.create("plan-1", &[create_test_phase("setup", "Initialize system")], Default::default());
+
+    let request = Request::builder()
+        .uri(format!("/runs/{}/phases/setup/attempts", run.id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["phase_id"], "setup");
+    assert_eq!(json["attempts"], serde_json::json!([]));
+
+    let request = Request::builder()
+        .uri(format!("/runs/{}/phases/unknown/attempts", run.id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_approval_endpoints_reject_phases_not_awaiting_approval() {
+    let run_manager = planter::state::runs::RunManager::default();
+    let app_state = AppState {
+        redis_client: None,
+        nats_client: None,
+        logging_service: LoggingService::new(None),
+        tenant_key: "global".to_string(),
+        run_manager: run_manager.clone(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
+    };
+    let app = Router::new()
+        .route("/runs/:id/phases/:phase/approve", post(planter::routes::runs::approve_phase))
+        .route("/runs/:id/phases/:phase/reject", post(planter::routes::runs::reject_phase))
+        .with_state(app_state);
+    let run = run_manager.create("plan-1", &[create_test_phase("setup", "Initialize system")], Default::default());
+
+    let decide = |action: &str, id: &str, phase: &str| {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/runs/{}/phases/{}/{}", id, phase, action))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"approver": "alice", "comment": "looks good"}"#))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(decide("approve", &run.id, "setup")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app.clone().oneshot(decide("reject", &run.id, "unknown")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app.oneshot(decide("approve", "unknown", "setup")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_schedule_endpoints() {
+    let app_state = AppState {
+        redis_client: None,
+        nats_client: None,
+        logging_service: LoggingService::new(None),
+        tenant_key: "global".to_string(),
+        run_manager: planter::state::runs::RunManager::default(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
+    };
+    let app = Router::new()
+        .route(
+            "/schedules",
+            axum::routing::get(planter::routes::schedules::list_schedules).post(planter::routes::schedules::create_schedule),
+        )
+        .route(
+            "/schedules/:id",
+            axum::routing::get(planter::routes::schedules::get_schedule).delete(planter::routes::schedules::delete_schedule),
+        )
+        .with_state(app_state.clone());
+    let create = |body: serde_json::Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/schedules")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let phases = vec![create_test_phase("setup", "Initialize system")];
+
+    for invalid in [
+        serde_json::json!({ "phases": phases }),
+        serde_json::json!({ "cron": "0 3 * * *", "interval": "1h", "phases": phases }),
+        serde_json::json!({ "cron": "every night", "phases": phases }),
+        serde_json::json!({ "interval": "1h", "use": ["unknown"], "phases": phases }),
+    ] {
+        let response = app.clone().oneshot(create(invalid.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", invalid);
+    }
+
+    let response = app
+        .clone()
+        .oneshot(create(serde_json::json!({ "name": "nightly", "cron": "0 3 * * *", "overlap": "queue", "phases": phases })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let id = json["schedule"]["id"].as_str().unwrap().to_string();
+    assert_eq!(json["schedule"]["overlap"], "queue");
+    assert!(json["schedule"]["next_run_at"].is_string());
+    assert!(json["schedule"]["last_run_at"].is_null());
+
+    let request = Request::builder().uri("/schedules").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["count"], 1);
+
+    // Bring the schedule due; without Redis its plan is only simulated,
+    // like a plan submitted to POST /plan
+    let mut schedule = app_state.schedule_manager.get(&id).unwrap();
+    schedule.next_run_at = Some(chrono::Utc::now() - chrono::Duration::seconds(1));
+    app_state.schedule_manager.create(schedule).await;
+    planter::routes::schedules::run_due_schedules(&app_state).await;
+    let schedule = app_state.schedule_manager.get(&id).unwrap();
+    assert!(schedule.last_run_id.is_none());
+    assert!(app_state.run_manager.list().is_empty());
+    assert!(schedule.last_run_at.is_some());
+    assert!(schedule.next_run_at.unwrap() > chrono::Utc::now());
+
+    let request = Request::builder().method("DELETE").uri(format!("/schedules/{}", id)).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = Request::builder().uri(format!("/schedules/{}", id)).body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_runs_apply_one_at_a_time() {
+    let app_state = AppState {
+        redis_client: None,
+        nats_client: None,
+        logging_service: LoggingService::new(None),
+        tenant_key: "global".to_string(),
+        run_manager: planter::state::runs::RunManager::default(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
+    };
+    let mut phase = create_test_phase("lock-sleep", "Hold the plan lock");
+    phase.spec.command = Some(CommandSpec {
+        program: "sleep".to_string(),
+        args: vec!["0.3".to_string()],
+        env: std::collections::HashMap::new(),
+        working_dir: None,
+        stdin: None,
+    });
+    let first = app_state.run_manager.create("plan-1", std::slice::from_ref(&phase), Default::default());
+    let second = app_state.run_manager.create("plan-2", &[phase], Default::default());
+    planter::routes::plan::start_run(&app_state, None, &first);
+    planter::routes::plan::start_run(&app_state, None, &second);
+
+    tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+    assert_eq!(app_state.plan_lock.holder().await, Some(first.id.clone()));
+    assert_eq!(app_state.run_manager.get(&second.id).unwrap().status, planter::state::runs::RunStatus::Pending);
+
+    for _ in 0..50 {
+        if app_state.run_manager.get(&second.id).unwrap().status.is_finished() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    let first = app_state.run_manager.get(&first.id).unwrap();
+    let second = app_state.run_manager.get(&second.id).unwrap();
+    assert_eq!(first.status, planter::state::runs::RunStatus::Succeeded);
+    assert_eq!(second.status, planter::state::runs::RunStatus::Succeeded);
+    assert!(second.started_at.unwrap() >= first.finished_at.unwrap());
+    assert_eq!(app_state.plan_lock.holder().await, None);
+}
+
+#[tokio::test]
+async fn test_rejecting_submissions_race_for_the_lock() {
+    use planter::routes::plan::reserve_run;
+    use planter::state::lock::ConflictPolicy;
+
+    let app_state = AppState {
+        redis_client: None,
+        nats_client: None,
+        logging_service: LoggingService::new(None),
+        tenant_key: "global".to_string(),
+        run_manager: planter::state::runs::RunManager::default(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
+    };
+    let phases = vec![create_test_phase("setup", "Initialize system")];
+
+    let (first, second) = tokio::join!(
+        reserve_run(&app_state, ConflictPolicy::Reject, "plan-1", &phases, Default::default()),
+        reserve_run(&app_state, ConflictPolicy::Reject, "plan-2", &phases, Default::default()),
+    );
+    let (accepted, refused) = match (first, second) {
+        (Ok(run), Err(response)) | (Err(response), Ok(run)) => (run, response),
+        _ => panic!("exactly one of two racing submissions should be accepted"),
+    };
+    assert_eq!(app_state.plan_lock.holder().await, Some(accepted.id.clone()));
+    assert_eq!(refused.status(), StatusCode::CONFLICT);
+    let body = axum::body::to_bytes(refused.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["run_id"], accepted.id);
+
+    // The refused submission leaves no run behind
+    let runs = app_state.run_manager.list();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].id, accepted.id);
+
+    // A queued submission is accepted without taking the lock
+    let queued = reserve_run(&app_state, ConflictPolicy::Queue, "plan-3", &phases, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(app_state.plan_lock.holder().await, Some(accepted.id));
+    assert_eq!(app_state.run_manager.get(&queued.id).unwrap().status, planter::state::runs::RunStatus::Pending);
+}
+
+#[tokio::test]
+async fn test_run_that_lost_the_lock_is_cancelled() {
+    use planter::executor::control::ControlState;
+    use planter::routes::plan::renew_lock;
+    use planter::state::lock::PlanLock;
+    use planter::state::runs::RunManager;
+
+    let run_manager = RunManager::default();
+    let plan_lock = PlanLock::default();
+    let phases = vec![create_test_phase("setup", "Initialize system")];
+    let run = run_manager.create("plan-1", &phases, Default::default());
+
+    plan_lock.try_acquire(&run.id).await.unwrap();
+    renew_lock(&run_manager, &plan_lock, &run.id).await;
+    assert_eq!(run_manager.get(&run.id).unwrap().control().state(), ControlState::Running);
+
+    // Another run took the lock, e.g. after this one's expired
+    plan_lock.release(&run.id).await;
+    plan_lock.try_acquire("other-run").await.unwrap();
+    renew_lock(&run_manager, &plan_lock, &run.id).await;
+    assert_eq!(run_manager.get(&run.id).unwrap().control().state(), ControlState::Cancelled);
+}
+
+#[tokio::test]
+async fn test_submit_plan_replays_idempotency_key() {
+    let app = create_test_app();
+    let phases = vec![create_test_phase("setup", "Initialize system")];
+    let submit = |key: &str| {
+        Request::builder()
+            .method("POST")
+            .uri("/plan")
+            .header("content-type", "application/json")
+            .header("idempotency-key", key)
+            .body(Body::from(serde_json::to_string(&phases).unwrap()))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(submit("ci-build-42")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("idempotent-replayed").is_none());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let first: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    let response = app.clone().oneshot(submit("ci-build-42")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("idempotent-replayed").unwrap(), "true");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let replayed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(replayed, first);
+
+    let response = app.clone().oneshot(submit("ci-build-43")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let other: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_ne!(other["plan_id"], first["plan_id"]);
+
+    // Reusing a key for a different plan is refused
+    let changed = vec![create_test_phase("deploy", "Deploy system")];
+    let request = Request::builder()
+        .method("POST")
+        .uri("/plan")
+        .header("content-type", "application/json")
+        .header("idempotency-key", "ci-build-42")
+        .body(Body::from(serde_json::to_string(&changed).unwrap()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app.oneshot(submit(&"k".repeat(256))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -13,6 +13,10 @@ async fn spawn_stateless_server() {
         nats_client: None,
         logging_service: LoggingService::new(None),
         tenant_key: "global".to_string(),
+        run_manager: planter::state::runs::RunManager::default(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
     };
     let app = Router::new()
         .route("/plan", post(planter::routes::plan::submit_plan))
@@ -49,7 +53,7 @@ async fn test_logs_stateless() {
             }
         }
     ]);
-    let res = client.post(&format!("{}/plan", base_url))
+    let res = client.post(format!("{}/plan", base_url))
         .json(&plan)
         .send()
         .await
@@ -57,7 +61,7 @@ async fn test_logs_stateless() {
     assert!(res.status().is_success(), "/plan: {}", res.status());
 
     // /logs should return stateless response
-    let res = client.get(&format!("{}/logs", base_url))
+    let res = client.get(format!("{}/logs", base_url))
         .send()
         .await
         .expect("Failed to send /logs request");
@@ -22,6 +22,10 @@ async fn test_manifest_endpoint_with_yaml() {
         nats_client: None,
         logging_service: planter::log::LoggingService::new(None),
         tenant_key: "global".to_string(),
+        run_manager: planter::state::runs::RunManager::default(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
     };
     let app = Router::new()
         .route("/manifest", axum::routing::post(submit_manifest))
@@ -54,6 +58,10 @@ async fn test_manifest_endpoint_without_janet() {
         nats_client: None,
         logging_service: planter::log::LoggingService::new(None),
         tenant_key: "global".to_string(),
+        run_manager: planter::state::runs::RunManager::default(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
     };
     let app = Router::new()
         .route("/manifest", axum::routing::post(submit_manifest))
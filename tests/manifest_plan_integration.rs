@@ -0,0 +1,100 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use std::os::unix::fs::PermissionsExt;
+use std::sync::OnceLock;
+use tower::ServiceExt; // for `oneshot`
+use planter::routes::manifest::submit_manifest;
+use planter::routes::plan::AppState;
+
+/// Put a stand-in `janet` first on PATH that renders a manifest by echoing
+/// it, so the body of a test request is the plan /manifest forwards
+fn fake_janet() {
+    static DIR: OnceLock<tempfile::TempDir> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("janet");
+        std::fs::write(&script, "#!/bin/sh\ncat \"$3/plan.yaml\"\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let path = std::env::var("PATH").unwrap_or_default();
+        std::env::set_var("PATH", format!("{}:{}", dir.path().display(), path));
+        dir
+    });
+}
+
+fn create_test_app() -> Router {
+    fake_janet();
+    let app_state = AppState {
+        redis_client: None,
+        nats_client: None,
+        logging_service: planter::log::LoggingService::new(None),
+        tenant_key: "global".to_string(),
+        run_manager: planter::state::runs::RunManager::default(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
+    };
+    Router::new()
+        .route("/manifest", axum::routing::post(submit_manifest))
+        .with_state(app_state)
+}
+
+fn submit(plan: &serde_json::Value, key: &str) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/manifest")
+        .header("content-type", "application/x-yaml")
+        .header("idempotency-key", key)
+        .body(Body::from(plan.to_string()))
+        .unwrap()
+}
+
+async fn json_body(response: axum::response::Response) -> serde_json::Value {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_manifest_passes_plan_failure_through_and_does_not_replay_it() {
+    let app = create_test_app();
+    let plan = serde_json::json!([{
+        "Kind": "Phase",
+        "Id": "deploy",
+        "Spec": {
+            "description": "Deploy",
+            "selector": {"match_labels": {}},
+            "wait_for": {"phases": ["missing"]}
+        }
+    }]);
+
+    let response = app.clone().oneshot(submit(&plan, "manifest-bad")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = json_body(response).await;
+    assert!(body["message"].as_str().unwrap().contains("Invalid plan"));
+
+    // The failure was not kept, so the retry is handled again
+    let response = app.oneshot(submit(&plan, "manifest-bad")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(response.headers().get("idempotent-replayed").is_none());
+}
+
+#[tokio::test]
+async fn test_manifest_returns_plan_response() {
+    let app = create_test_app();
+    let plan = serde_json::json!([{
+        "Kind": "Phase",
+        "Id": "setup",
+        "Spec": {"description": "Set up", "selector": {"match_labels": {}}}
+    }]);
+
+    let response = app.clone().oneshot(submit(&plan, "manifest-ok")).await.unwrap();
+    // Without Redis /plan simulates the plan and answers 200
+    assert_eq!(response.status(), StatusCode::OK);
+    let first = json_body(response).await;
+    assert_eq!(first["phases_extracted"], 1);
+    assert!(first["plan_response"]["plan_id"].is_string());
+
+    let response = app.oneshot(submit(&plan, "manifest-ok")).await.unwrap();
+    assert_eq!(response.headers().get("idempotent-replayed").unwrap(), "true");
+    assert_eq!(json_body(response).await["plan_response"], first["plan_response"]);
+}
@@ -14,7 +14,6 @@ use tower::ServiceExt;
 use std::sync::Arc;
 use tokio::time::{sleep, timeout, Duration};
 use tokio_stream::StreamExt;
-use serde_json;
 
 fn create_test_phase(id: &str, description: &str) -> Phase {
     Phase {
@@ -28,11 +27,7 @@ fn create_test_phase(id: &str, description: &str) -> Phase {
                     .cloned()
                     .collect(),
             },
-            instance_mode: None,
-            wait_for: None,
-            retry: None,
-            on_failure: None,
-            on_success: None,
+            ..Default::default()
         },
     }
 }
@@ -61,6 +56,10 @@ async fn create_test_app_with_nats() -> Option<Router> {
         nats_client,
         logging_service: LoggingService::new(None),
         tenant_key: "global".to_string(),
+        run_manager: planter::state::runs::RunManager::default(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
     };
 
     Some(Router::new()
@@ -172,8 +171,8 @@ async fn test_full_session_workflow_with_mock_peer() {
     let log_subject = format!("plan.session.{}.log", session_id);
 
     let mut start_sub = peer_client.subscribe(start_subject.clone()).await.unwrap();
-    let state_sub = peer_client.subscribe(state_subject.clone()).await.unwrap();
-    let log_sub = peer_client.subscribe(log_subject.clone()).await.unwrap();
+    let _state_sub = peer_client.subscribe(state_subject.clone()).await.unwrap();
+    let _log_sub = peer_client.subscribe(log_subject.clone()).await.unwrap();
 
     // Wait for start message
     let start_msg = timeout(Duration::from_secs(5), start_sub.next()).await
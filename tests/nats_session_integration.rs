@@ -3,7 +3,6 @@ use tokio::time::timeout;
 use tokio_stream::StreamExt;
 use planter::nats::client::NatsClient;
 use planter::model::{Phase, PhaseSpec, Selector};
-use serde_json;
 
 /// Helper to create a test phase
 fn create_test_phase(id: &str, description: &str) -> Phase {
@@ -18,11 +17,7 @@ fn create_test_phase(id: &str, description: &str) -> Phase {
                     .cloned()
                     .collect(),
             },
-            instance_mode: None,
-            wait_for: None,
-            retry: None,
-            on_failure: None,
-            on_success: None,
+            ..Default::default()
         },
     }
 }
@@ -49,7 +44,7 @@ async fn test_nats_session_start_message() {
 
     // Create a session
     let session = client.new_session();
-    let session_id = session.session_id.clone();
+    let _session_id = session.session_id.clone();
     
     // Subscribe to start messages on this session
     let start_sub = session.subscribe_start().await.expect("Failed to subscribe to start");
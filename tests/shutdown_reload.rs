@@ -14,6 +14,10 @@ async fn spawn_server_with_state() -> u16 {
         nats_client: None,
         logging_service: LoggingService::new(None),
         tenant_key: "global".to_string(),
+        run_manager: planter::state::runs::RunManager::default(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
     };
     let app = Router::new()
         .route("/plan", post(planter::routes::plan::submit_plan))
@@ -60,7 +64,7 @@ async fn test_shutdown_and_reload_state_file() {
             }
         }
     ]);
-    let res = client.post(&format!("{}/plan", base_url))
+    let res = client.post(format!("{}/plan", base_url))
         .json(&plan)
         .send()
         .await
@@ -68,7 +72,7 @@ async fn test_shutdown_and_reload_state_file() {
     assert!(res.status().is_success(), "/plan: {}", res.status());
 
     // Simulate shutdown: call /STOP
-    let res = client.post(&format!("{}/STOP", base_url))
+    let res = client.post(format!("{}/STOP", base_url))
         .send()
         .await
         .expect("Failed to send /STOP request");
@@ -82,7 +86,7 @@ async fn test_shutdown_and_reload_state_file() {
     assert!(state_data.contains("shutdown-test"));
 
     // Simulate reload: call /RELOAD
-    let res = client.post(&format!("{}/RELOAD", base_url))
+    let res = client.post(format!("{}/RELOAD", base_url))
         .send()
         .await
         .expect("Failed to send /RELOAD request");
@@ -1,10 +1,8 @@
 use reqwest::Client;
-use std::net::SocketAddr;
 use std::time::Duration;
 use tokio::time::sleep;
 use std::fs;
-use std::process::{Command, Child};
-use std::thread;
+use std::process::Command;
 use std::env;
 
 // Real binary shutdown/reload test is ignored in CI environment
@@ -47,7 +45,7 @@ async fn test_shutdown_and_reload_real() {
             }
         }
     ]);
-    let res = client.post(&format!("{}/plan", base_url))
+    let res = client.post(format!("{}/plan", base_url))
         .json(&plan)
         .send()
         .await
@@ -55,7 +53,7 @@ async fn test_shutdown_and_reload_real() {
     assert!(res.status().is_success(), "/plan: {}", res.status());
 
     // Simulate shutdown: call /STOP
-    let res = client.post(&format!("{}/STOP", base_url))
+    let res = client.post(format!("{}/STOP", base_url))
         .send()
         .await
         .expect("Failed to send /STOP request");
@@ -4,7 +4,7 @@ use std::time::Duration;
 use tokio::time::sleep;
 use planter::routes::plan::AppState;
 use planter::log::{init_logger, LoggingService};
-use axum::{Router, routing::{get, post}};
+use axum::{Router, routing::get};
 use std::env;
 
 async fn spawn_planter_server(port: u16, phases: Option<serde_json::Value>) {
@@ -14,6 +14,10 @@ async fn spawn_planter_server(port: u16, phases: Option<serde_json::Value>) {
         nats_client: None,
         logging_service: LoggingService::new(None),
         tenant_key: "global".to_string(),
+        run_manager: planter::state::runs::RunManager::default(),
+        schedule_manager: planter::state::schedules::ScheduleManager::default(),
+        plan_lock: planter::state::lock::PlanLock::default(),
+        idempotency: planter::state::idempotency::IdempotencyStore::default(),
     };
     let app = Router::new()
         .route("/state", get(|| async move {